@@ -30,3 +30,131 @@ fn regex_nfa_bench(b: &mut test::Bencher) {
     let s = String::from_utf8(vec![b'a'; LEN]).unwrap();
     b.iter(|| regex::Regex::new(".*a").exec(Some(s.clone()), false, false, None));
 }
+
+// リテラルを多く含むパターンでの事前フィルタの効果
+// 入力は "x" * 100000 + "helloworld"、エンジンはPikeVm（no_prefilterが導入前、prefilterが導入後）
+//--------------------------------------------------------------------------------------------
+// test regex_no_prefilter_bench       ... bench:   3,639,037 ns/iter (+/- 600,797)
+// test regex_prefilter_bench          ... bench:       9,899 ns/iter (+/- 739)
+//--------------------------------------------------------------------------------------------
+fn literal_heavy_input() -> String {
+    let mut s = "x".repeat(LEN);
+    s.push_str("helloworld");
+    s
+}
+
+fn literal_heavy_regex(prefilter: bool) -> regex::Regex<'static> {
+    regex::RegexBuilder::new("hello(world|there)")
+        .engine(regex::Engine::PikeVm)
+        .prefilter(prefilter)
+        .build()
+        .unwrap()
+}
+
+#[bench]
+fn regex_prefilter_bench(b: &mut test::Bencher) {
+    let s = literal_heavy_input();
+    let re = literal_heavy_regex(true);
+    b.iter(|| re.is_match(&s));
+}
+
+#[bench]
+fn regex_no_prefilter_bench(b: &mut test::Bencher) {
+    let s = literal_heavy_input();
+    let re = literal_heavy_regex(false);
+    b.iter(|| re.is_match(&s));
+}
+
+// 先頭リテラルの出現位置が多い入力での事前フィルタ（最初の出現位置から一度だけ照合する）
+// 入力は "c" + "ab" * 20000、パターンは "ab.*c"（出現位置ごとに照合し直していた実装では11〜14秒）
+//--------------------------------------------------------------------------------------------
+// test regex_many_candidates_backtrack_bench    ... bench:   2,414,299 ns/iter (+/- 108,092)
+// test regex_many_candidates_dfa_bench          ... bench:   1,582,671 ns/iter (+/- 780,581)
+// test regex_many_candidates_no_prefilter_bench ... bench:   1,816,953 ns/iter (+/- 50,325)
+// test regex_many_candidates_pike_bench         ... bench:   1,836,271 ns/iter (+/- 110,055)
+//--------------------------------------------------------------------------------------------
+fn many_candidates_input() -> String {
+    format!("c{}", "ab".repeat(20000))
+}
+
+fn many_candidates_regex(engine: regex::Engine, prefilter: bool) -> regex::Regex<'static> {
+    regex::RegexBuilder::new("ab.*c")
+        .engine(engine)
+        .prefilter(prefilter)
+        .build()
+        .unwrap()
+}
+
+#[bench]
+fn regex_many_candidates_dfa_bench(b: &mut test::Bencher) {
+    let s = many_candidates_input();
+    let re = many_candidates_regex(regex::Engine::Dfa, true);
+    b.iter(|| re.is_match(&s));
+}
+
+#[bench]
+fn regex_many_candidates_pike_bench(b: &mut test::Bencher) {
+    let s = many_candidates_input();
+    let re = many_candidates_regex(regex::Engine::PikeVm, true);
+    b.iter(|| re.is_match(&s));
+}
+
+#[bench]
+fn regex_many_candidates_backtrack_bench(b: &mut test::Bencher) {
+    let s = many_candidates_input();
+    let re = many_candidates_regex(regex::Engine::Backtrack, true);
+    b.iter(|| re.is_match(&s));
+}
+
+#[bench]
+fn regex_many_candidates_no_prefilter_bench(b: &mut test::Bencher) {
+    let s = many_candidates_input();
+    let re = many_candidates_regex(regex::Engine::PikeVm, false);
+    b.iter(|| re.is_match(&s));
+}
+
+// Thompson構成法とGlushkov構成法の比較
+// パターン "(a|b)*a(a|b)(a|b)(a|b)" のNFA状態数は Thompson: 23（ε遷移19）、Glushkov: 10（ε遷移なし）
+//--------------------------------------------------------------------------------------------
+// test regex_glushkov_dfa_build_bench ... bench:      73,265 ns/iter (+/- 3,083)
+// test regex_glushkov_nfa_bench       ... bench:     156,591 ns/iter (+/- 26,261)
+// test regex_thompson_dfa_build_bench ... bench:     262,991 ns/iter (+/- 22,698)
+// test regex_thompson_nfa_bench       ... bench:   1,461,826 ns/iter (+/- 89,149)
+//--------------------------------------------------------------------------------------------
+static CONSTRUCTION_PATTERN: &str = "(a|b)*a(a|b)(a|b)(a|b)";
+
+fn construction_regex(
+    engine: regex::Engine,
+    construction: regex::Construction,
+) -> regex::Regex<'static> {
+    regex::RegexBuilder::new(CONSTRUCTION_PATTERN)
+        .engine(engine)
+        .construction(construction)
+        .anchored(true)
+        .build()
+        .unwrap()
+}
+
+#[bench]
+fn regex_thompson_nfa_bench(b: &mut test::Bencher) {
+    let s = "ab".repeat(500);
+    let re = construction_regex(regex::Engine::Nfa, regex::Construction::Thompson);
+    b.iter(|| re.is_match(&s));
+}
+
+#[bench]
+fn regex_glushkov_nfa_bench(b: &mut test::Bencher) {
+    let s = "ab".repeat(500);
+    let re = construction_regex(regex::Engine::Nfa, regex::Construction::Glushkov);
+    b.iter(|| re.is_match(&s));
+}
+
+#[bench]
+fn regex_thompson_dfa_build_bench(b: &mut test::Bencher) {
+    b.iter(|| construction_regex(regex::Engine::Dfa, regex::Construction::Thompson));
+}
+
+#[bench]
+fn regex_glushkov_dfa_build_bench(b: &mut test::Bencher) {
+    b.iter(|| construction_regex(regex::Engine::Dfa, regex::Construction::Glushkov));
+}
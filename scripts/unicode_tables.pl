@@ -0,0 +1,64 @@
+#!/usr/bin/env perl
+# Unicodeプロパティの範囲表を生成する
+# 使い方: perl scripts/unicode_tables.pl > src/unicode/tables.rs
+use strict;
+use warnings;
+use Unicode::UCD qw(prop_invlist);
+
+my @general_category = qw(
+    L Lu Ll Lt Lm Lo M Mn Mc Me N Nd Nl No
+    P Pc Pd Ps Pe Pi Pf Po S Sm Sc Sk So Z Zs Zl Zp C Cc Cf Co Cn
+);
+my @script = qw(
+    Arabic Armenian Cyrillic Devanagari Greek Han Hangul Hebrew
+    Hiragana Katakana Latin Thai Common Inherited
+);
+my @binary = qw(Alphabetic Lowercase Uppercase White_Space Emoji);
+
+# 反転リストから範囲の列へ（charにできないサロゲートは除く）
+sub ranges {
+    my @invlist = prop_invlist($_[0]);
+    push @invlist, 0x110000 if @invlist % 2;
+    my @ranges;
+    while (my ($start, $end) = splice(@invlist, 0, 2)) {
+        $end -= 1;
+        if ($start < 0xD800 && $end > 0xDFFF) {
+            push @ranges, [$start, 0xD7FF], [0xE000, $end];
+        } elsif ($start >= 0xD800 && $end <= 0xDFFF) {
+            next;
+        } else {
+            $start = 0xE000 if $start >= 0xD800 && $start <= 0xDFFF;
+            $end = 0xD7FF if $end >= 0xD800 && $end <= 0xDFFF;
+            push @ranges, [$start, $end];
+        }
+    }
+    return @ranges;
+}
+
+sub table {
+    my ($name, $prop) = @_;
+    my @items = map { sprintf("('\\u{%x}', '\\u{%x}')", @$_) } ranges($prop);
+    my $out = "pub const $name: &[(char, char)] = &[\n";
+    while (my @line = splice(@items, 0, 4)) {
+        $out .= "    " . join(", ", @line) . ",\n";
+    }
+    return $out . "];\n";
+}
+
+sub index_table {
+    my ($name, $prefix, @names) = @_;
+    my $out = "pub const $name: &[(&str, &[(char, char)])] = &[\n";
+    $out .= sprintf("    (\"%s\", %s_%s),\n", $_, $prefix, uc $_) for @names;
+    return $out . "];\n";
+}
+
+my $version = Unicode::UCD::UnicodeVersion();
+print "// scripts/unicode_tables.pl で生成（Unicode $version）\n";
+print "// 各表は整列済みで重ならない、両端を含む範囲の列\n\n";
+print "pub const UNICODE_VERSION: &str = \"$version\";\n\n";
+print index_table("GENERAL_CATEGORY", "GC", @general_category), "\n";
+print index_table("SCRIPT", "SC", @script), "\n";
+print index_table("BINARY", "BP", @binary);
+print "\n", table("GC_" . uc $_, "gc=$_") for @general_category;
+print "\n", table("SC_" . uc $_, "sc=$_") for @script;
+print "\n", table("BP_" . uc $_, $_) for @binary;
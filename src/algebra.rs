@@ -0,0 +1,693 @@
+#![allow(dead_code)]
+
+use crate::automaton::dfa::DFAProgram;
+use crate::automaton::farule::State;
+use crate::automaton::minimize::minimize;
+use crate::automaton::nfa::NFARulebook;
+use crate::automaton::pattern::build::Builder;
+use crate::automaton::product;
+use crate::derivative::DerivativeMachine;
+use crate::error::Error;
+use crate::parse::lexer::Lexer;
+use crate::parse::parser::{Ast, AstTree};
+use crate::regex::Engine;
+use num_bigint::BigUint;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+// Unicodeのスカラー値の数（サロゲートを除く）
+const SCALAR_VALUES: usize = 0x11_0000 - 0x800;
+
+// パターンを言語として扱う際のDFA状態数の上限
+pub const MAX_STATES: usize = 10_000;
+
+// 二つの言語が異なることを示す文字列
+#[derive(Debug, PartialEq)]
+pub struct Counterexample {
+    pub input: String,
+    pub left: bool,  // 左のパターンにマッチするか
+    pub right: bool, // 右のパターンにマッチするか
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let side = if self.left { "left" } else { "right" };
+        write!(
+            f,
+            "{:?} is matched only by the {} pattern",
+            self.input, side
+        )
+    }
+}
+
+// 言語に含まれる文字列の数
+#[derive(Debug, PartialEq)]
+pub enum Size {
+    Finite(BigUint),
+    Infinite,
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Size::Finite(n) => write!(f, "{}", n),
+            Size::Infinite => write!(f, "infinite"),
+        }
+    }
+}
+
+// 二つのパターンが同じ文字列の集合にマッチするか（異なる場合は最短の反例）
+// 言語として扱えないパターンの場合はエラー
+pub fn equivalent(a: &str, b: &str) -> Result<Result<(), Counterexample>, Error> {
+    Ok(Language::new(a)?.equivalent(&Language::new(b)?))
+}
+
+// aにマッチする文字列が全てbにもマッチするか（そうでない場合は最短の反例）
+// 言語として扱えないパターンの場合はエラー
+pub fn is_subset(a: &str, b: &str) -> Result<Result<(), Counterexample>, Error> {
+    Ok(Language::new(a)?.is_subset(&Language::new(b)?))
+}
+
+// 正規言語（パターン全体にマッチする文字列の集合）をDFAで表したもの
+// 共通部分・補集合・差集合はDFAの直積で求める
+#[derive(Debug)]
+pub struct Language {
+    dfa: DFAProgram,
+}
+
+impl Language {
+    // 言語として扱えない機能を含む場合、DFA状態数がMAX_STATESを超える場合はエラー
+    pub fn new(pattern: &str) -> Result<Self, Error> {
+        Self::with_max_states(pattern, MAX_STATES)
+    }
+
+    // DFA状態数の上限を指定してコンパイル
+    pub fn with_max_states(pattern: &str, max_states: usize) -> Result<Self, Error> {
+        Ok(Language {
+            dfa: compile_language(pattern, max_states)?,
+        })
+    }
+
+    // 文字列全体がこの言語に含まれるか
+    pub fn is_match(&self, s: &str) -> bool {
+        self.dfa.accept(s)
+    }
+
+    // 両方に含まれる文字列
+    pub fn intersection(&self, other: &Language) -> Language {
+        Language {
+            dfa: product::intersection(&self.dfa, &other.dfa),
+        }
+    }
+
+    // いずれかに含まれる文字列
+    pub fn union(&self, other: &Language) -> Language {
+        Language {
+            dfa: product::union(&self.dfa, &other.dfa),
+        }
+    }
+
+    // selfに含まれ、otherに含まれない文字列
+    pub fn difference(&self, other: &Language) -> Language {
+        Language {
+            dfa: product::difference(&self.dfa, &other.dfa),
+        }
+    }
+
+    // 含まれない全ての文字列
+    pub fn complement(&self) -> Language {
+        Language {
+            dfa: product::complement(&self.dfa),
+        }
+    }
+
+    // この言語にマッチするAST（最小化したDFAから状態除去法で求める）
+    pub fn to_ast(&self) -> AstTree {
+        minimize(&self.dfa).to_ast()
+    }
+
+    // この言語にマッチするパターン文字列
+    pub fn to_pattern(&self) -> String {
+        self.to_ast().to_string()
+    }
+
+    // 空集合か
+    pub fn is_empty(&self) -> bool {
+        self.shortest().is_none()
+    }
+
+    // 最も短い文字列（同じ長さでは辞書順で最小）
+    pub fn shortest(&self) -> Option<String> {
+        let symbols = symbols(&self.dfa);
+
+        // 幅優先探索で受理状態への最短経路を求める
+        let start = self.dfa.start();
+        let mut parent = HashMap::new();
+        parent.insert(start, None);
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+
+        while let Some(state) = queue.pop_front() {
+            if self.dfa.is_accept(&state) {
+                let mut chars = vec![];
+                let mut cur = state;
+                while let Some(&Some((prev, c))) = parent.get(&cur) {
+                    chars.push(c);
+                    cur = prev;
+                }
+                return Some(chars.into_iter().rev().collect());
+            }
+
+            for &c in symbols.iter() {
+                if let Some(next) = self.dfa.next(state, c) {
+                    if let Entry::Vacant(e) = parent.entry(next) {
+                        e.insert(Some((state, c)));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // 短い順（同じ長さでは辞書順）に最大limit個の文字列
+    // パターンに現れない文字は、その代表の一文字で表す
+    pub fn enumerate(&self, limit: usize) -> Vec<String> {
+        let symbols = symbols(&self.dfa);
+        let states = reachable(&self.dfa, &symbols);
+
+        // 有限の言語では、文字列の長さは到達可能な状態数より短い
+        let max_len = match self.size() {
+            Size::Finite(_) => states.len(),
+            Size::Infinite => usize::MAX,
+        };
+
+        let mut strings = vec![];
+        for len in 0..max_len {
+            if strings.len() >= limit {
+                break;
+            }
+
+            // 受理状態へ到達できる遷移のみを辞書順に深さ優先で辿る
+            let feasible = feasible(&self.dfa, &states, &symbols, len);
+            let mut stack = vec![];
+            if feasible[len].contains(&self.dfa.start()) {
+                stack.push((self.dfa.start(), String::new(), len));
+            }
+            while let Some((state, prefix, remain)) = stack.pop() {
+                if strings.len() >= limit {
+                    break;
+                }
+                if remain == 0 {
+                    strings.push(prefix);
+                    continue;
+                }
+                for &c in symbols.iter().rev() {
+                    if let Some(next) = self.dfa.next(state, c) {
+                        if feasible[remain - 1].contains(&next) {
+                            let mut s = prefix.clone();
+                            s.push(c);
+                            stack.push((next, s, remain - 1));
+                        }
+                    }
+                }
+            }
+        }
+        strings
+    }
+
+    // 長さ0からmax_lenまでの各長さ（文字数）の文字列の数
+    // パターンに現れない文字への遷移は、それらの文字の数だけ数える
+    pub fn counts(&self, max_len: usize) -> Vec<BigUint> {
+        let alphabet = self.dfa.alphabet();
+        let symbols: Vec<(char, BigUint)> = symbols(&self.dfa)
+            .into_iter()
+            .map(|c| {
+                let weight = if alphabet.contains(&c) {
+                    1
+                } else {
+                    SCALAR_VALUES - alphabet.len()
+                };
+                (c, BigUint::from(weight))
+            })
+            .collect();
+
+        // paths: 各状態へ到達する文字列の数
+        let mut paths: HashMap<State, BigUint> = HashMap::new();
+        paths.insert(self.dfa.start(), BigUint::from(1u32));
+
+        let mut counts = vec![];
+        for len in 0..=max_len {
+            counts.push(
+                paths
+                    .iter()
+                    .filter(|(state, _)| self.dfa.is_accept(state))
+                    .map(|(_, n)| n)
+                    .sum(),
+            );
+            if len == max_len {
+                break;
+            }
+
+            let mut next_paths: HashMap<State, BigUint> = HashMap::new();
+            for (state, n) in paths.iter() {
+                for (c, weight) in symbols.iter() {
+                    if let Some(next) = self.dfa.next(*state, *c) {
+                        *next_paths.entry(next).or_default() += n * weight;
+                    }
+                }
+            }
+            paths = next_paths;
+        }
+        counts
+    }
+
+    // 長さlen（文字数）の文字列の数
+    pub fn count(&self, len: usize) -> BigUint {
+        self.counts(len).pop().unwrap_or_default()
+    }
+
+    // 文字列の総数（受理状態へ到達できる閉路があれば無限）
+    pub fn size(&self) -> Size {
+        let symbols = symbols(&self.dfa);
+        let states = reachable(&self.dfa, &symbols);
+
+        // 受理状態へ到達できる状態（生きている状態）を逆向きに求める
+        let mut live: HashSet<State> = states
+            .iter()
+            .filter(|s| self.dfa.is_accept(s))
+            .copied()
+            .collect();
+        loop {
+            let added: Vec<State> = states
+                .iter()
+                .filter(|s| !live.contains(s))
+                .filter(|s| {
+                    symbols
+                        .iter()
+                        .any(|c| self.dfa.next(**s, *c).is_some_and(|t| live.contains(&t)))
+                })
+                .copied()
+                .collect();
+            if added.is_empty() {
+                break;
+            }
+            live.extend(added);
+        }
+
+        // 生きている状態の間の遷移でトポロジカルソートし、残る状態があれば閉路がある
+        let edges = |s: &State| -> Vec<State> {
+            let mut next: Vec<State> = symbols
+                .iter()
+                .filter_map(|c| self.dfa.next(*s, *c))
+                .filter(|t| live.contains(t))
+                .collect();
+            next.sort_unstable();
+            next.dedup();
+            next
+        };
+        let mut indegree: HashMap<State, usize> = live.iter().map(|s| (*s, 0)).collect();
+        for s in live.iter() {
+            for t in edges(s) {
+                *indegree.get_mut(&t).unwrap() += 1;
+            }
+        }
+        let mut queue: VecDeque<State> = indegree
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(s, _)| *s)
+            .collect();
+        let mut sorted = 0;
+        while let Some(s) = queue.pop_front() {
+            sorted += 1;
+            for t in edges(&s) {
+                let d = indegree.get_mut(&t).unwrap();
+                *d -= 1;
+                if *d == 0 {
+                    queue.push_back(t);
+                }
+            }
+        }
+        if sorted < live.len() {
+            return Size::Infinite;
+        }
+
+        // 閉路がなければ、文字列の長さは生きている状態数より短い
+        match live.len() {
+            0 => Size::Finite(BigUint::default()),
+            n => Size::Finite(self.counts(n - 1).into_iter().sum()),
+        }
+    }
+
+    // 同じ文字列の集合か（異なる場合は対称差の最短の文字列を反例とする）
+    pub fn equivalent(&self, other: &Language) -> Result<(), Counterexample> {
+        let diff = Language {
+            dfa: product::product(&[&self.dfa, &other.dfa], |acc| acc[0] != acc[1]),
+        };
+        self.check(other, diff)
+    }
+
+    // selfの文字列が全てotherに含まれるか（そうでない場合は差集合の最短の文字列を反例とする）
+    pub fn is_subset(&self, other: &Language) -> Result<(), Counterexample> {
+        self.check(other, self.difference(other))
+    }
+
+    fn check(&self, other: &Language, diff: Language) -> Result<(), Counterexample> {
+        match diff.shortest() {
+            None => Ok(()),
+            Some(input) => Err(Counterexample {
+                left: self.is_match(&input),
+                right: other.is_match(&input),
+                input,
+            }),
+        }
+    }
+}
+
+// 遷移に使う文字（DFAに現れる文字と、それ以外の文字の代表）を辞書順に並べたもの
+pub(crate) fn symbols(dfa: &DFAProgram) -> Vec<char> {
+    let alphabet = dfa.alphabet();
+    let other = ('a'..='z')
+        .chain('0'..='9')
+        .chain('A'..='Z')
+        .find(|c| !alphabet.contains(c))
+        .unwrap_or(NFARulebook::EVERYTHING_CHAR);
+
+    let mut symbols: Vec<char> = alphabet.into_iter().collect();
+    symbols.push(other);
+    symbols.sort_unstable();
+    symbols
+}
+
+// 開始状態から到達できる状態
+pub(crate) fn reachable(dfa: &DFAProgram, symbols: &[char]) -> Vec<State> {
+    let mut states = vec![dfa.start()];
+    let mut queue: VecDeque<State> = states.iter().copied().collect();
+    while let Some(state) = queue.pop_front() {
+        for c in symbols.iter() {
+            if let Some(next) = dfa.next(state, *c) {
+                if !states.contains(&next) {
+                    states.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    states
+}
+
+// feasible[n]: ちょうどn文字で受理状態へ到達できる状態（nは0からmax_lenまで）
+pub(crate) fn feasible(
+    dfa: &DFAProgram,
+    states: &[State],
+    symbols: &[char],
+    max_len: usize,
+) -> Vec<HashSet<State>> {
+    let mut feasible: Vec<HashSet<State>> = vec![states
+        .iter()
+        .filter(|s| dfa.is_accept(s))
+        .copied()
+        .collect()];
+    for n in 1..=max_len {
+        let next = states
+            .iter()
+            .filter(|s| {
+                symbols.iter().any(|c| {
+                    dfa.next(**s, *c)
+                        .is_some_and(|t| feasible[n - 1].contains(&t))
+                })
+            })
+            .copied()
+            .collect();
+        feasible.push(next);
+    }
+    feasible
+}
+
+// パターン全体にマッチする文字列の集合をDFAにコンパイル
+// 言語として扱えない機能を含む場合、状態数が上限を超える場合はエラー
+pub(crate) fn compile_language(pattern: &str, max_states: usize) -> Result<DFAProgram, Error> {
+    let ast = Ast::new(&Lexer::new(pattern).scan()).try_parse()?;
+    let unsupported = |feature| {
+        Err(Error::Unsupported {
+            engine: Engine::Dfa,
+            feature,
+        })
+    };
+    // 表明の成否は前後の文字で決まるため、言語として扱えない
+    if ast.has_assertion() {
+        return unsupported("assertions");
+    }
+    // プロパティの文字集合は直積・最小化の文字の扱いに含まれない
+    if ast.has_property() {
+        return unsupported("unicode properties");
+    }
+    // 後方参照・先読み・後読みは正規言語の範囲を超える
+    if ast.has_backref() {
+        return unsupported("backreferences");
+    }
+    if ast.has_lookaround() {
+        return unsupported("lookaround");
+    }
+    compile(&ast, max_states).ok_or(Error::TooManyDfaStates { limit: max_states })
+}
+
+// ASTをDFAへ変換（直積を含め、状態数が上限を超えた場合はNone）
+// 共通部分・補集合は直積で求め、それらを連結・繰り返しの中で使う場合は微分でDFAを構築する
+pub(crate) fn compile(ast: &AstTree, max_states: usize) -> Option<DFAProgram> {
+    match ast {
+        AstTree::And(l, r) => product::product_limited(
+            &[&compile(l, max_states)?, &compile(r, max_states)?],
+            |acc| acc[0] && acc[1],
+            max_states,
+        ),
+        AstTree::Not(a) => {
+            product::product_limited(&[&compile(a, max_states)?], |acc| !acc[0], max_states)
+        }
+        _ if ast.has_set_operator() => DerivativeMachine::new(ast).to_dfa(max_states),
+        _ => Builder::from_ast(ast.clone()).to_nfa().to_dfa(max_states),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_language_operation() {
+        let ident = Language::new("(a|b|f|i|o|r)+").unwrap();
+        let keyword = Language::new("if|for").unwrap();
+        let name = ident.difference(&keyword);
+
+        assert!(name.is_match("fi"));
+        assert!(name.is_match("iff"));
+        assert!(!name.is_match("if"));
+        assert!(!name.is_match("for"));
+        assert!(!name.is_match(""));
+
+        assert!(ident.intersection(&keyword).is_match("for"));
+        assert!(!ident.intersection(&keyword).is_match("ab"));
+        assert!(keyword.union(&Language::new("a").unwrap()).is_match("a"));
+        assert!(keyword.complement().is_match("fo"));
+        assert!(!keyword.complement().is_match("if"));
+    }
+
+    #[test]
+    fn test_language_shortest() {
+        assert_eq!(
+            Some("ab".to_string()),
+            Language::new("b*ab|abc").unwrap().shortest()
+        );
+        assert_eq!(
+            Some("".to_string()),
+            Language::new("a*").unwrap().shortest()
+        );
+        assert_eq!(
+            Some("a".to_string()),
+            Language::new(".").unwrap().shortest()
+        );
+        assert_eq!(None, Language::new("a&b").unwrap().shortest());
+        assert!(Language::new("a+&~(a*)").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_language_enumerate() {
+        let strings = |pattern: &str, limit| Language::new(pattern).unwrap().enumerate(limit);
+
+        assert_eq!(vec!["a", "ab", "ac"], strings("a(b|c)?", 10));
+        assert_eq!(
+            vec!["", "a", "b", "aa", "ab", "ba", "bb"],
+            strings("(a|b)*", 7)
+        );
+        assert_eq!(vec!["ab", "aab"], strings("a+b", 2));
+        assert_eq!(Vec::<String>::new(), strings("a&b", 10));
+        // パターンに現れない文字は代表の一文字
+        assert_eq!(vec!["xa", "xx"], strings("x.", 10));
+    }
+
+    #[test]
+    fn test_language_count() {
+        let counts = |pattern: &str, max_len| -> Vec<BigUint> {
+            Language::new(pattern).unwrap().counts(max_len)
+        };
+        let to_big = |v: &[u32]| -> Vec<BigUint> { v.iter().map(|n| BigUint::from(*n)).collect() };
+
+        assert_eq!(to_big(&[1, 2, 4, 8]), counts("(a|b)*", 3));
+        assert_eq!(to_big(&[0, 0, 1, 1, 1]), counts("abc*", 4));
+        assert_eq!(to_big(&[0, 2, 4, 0]), counts("(a|b)(c|d)?", 3));
+
+        // 任意の文字はUnicodeのスカラー値の数だけ数える
+        assert_eq!(
+            BigUint::from(1_112_064u32),
+            Language::new(".").unwrap().count(1)
+        );
+        assert_eq!(
+            BigUint::from(1_112_063u32),
+            Language::new("~a").unwrap().count(1)
+        );
+        assert_eq!(
+            "2339043265889942296997545003476243866000509894656"
+                .parse::<BigUint>()
+                .unwrap(),
+            Language::new("........").unwrap().count(8)
+        );
+    }
+
+    #[test]
+    fn test_language_size() {
+        let size = |pattern: &str| Language::new(pattern).unwrap().size();
+
+        assert_eq!(Size::Infinite, size("(a|b)*"));
+        assert_eq!(Size::Infinite, size("ab+"));
+        assert_eq!(Size::Finite(BigUint::from(6u32)), size("(a|b)(c|d)?"));
+        assert_eq!(Size::Finite(BigUint::from(0u32)), size("a+&~(a*)"));
+        // 受理状態へ到達できない閉路は数えない
+        assert_eq!(Size::Finite(BigUint::from(1u32)), size("a&~(b*)"));
+        assert_eq!("infinite", size("a*").to_string());
+        assert_eq!("3", size("a|b|c").to_string());
+    }
+
+    #[test]
+    fn test_language_to_pattern() {
+        for pattern in [
+            "abc",
+            "a+",
+            "(a|b)*abb",
+            "ab?c",
+            "a.c",
+            "(ab)*",
+            "a(b|c)*d|e",
+            "(a|b|f|i|o|r)+&~(if|for)",
+            "a~(b*)c",
+            "a&b",
+        ]
+        .iter()
+        {
+            let exported = Language::new(pattern).unwrap().to_pattern();
+            assert_eq!(
+                Ok(()),
+                equivalent(pattern, &exported).unwrap(),
+                "{}",
+                exported
+            );
+        }
+
+        assert_eq!("a+", Language::new("aa*").unwrap().to_pattern());
+        assert_eq!("ab?", Language::new("a|ab").unwrap().to_pattern());
+        // 共通部分・補集合を除いたパターンとして書き出す
+        let name = Language::new("(a|b)+&~(ab)").unwrap().to_pattern();
+        assert!(!name.contains('&') && !name.contains('~'), "{}", name);
+    }
+
+    #[test]
+    fn test_equivalent() {
+        assert_eq!(Ok(()), equivalent("(a|b)*", "(a*b*)*").unwrap());
+        assert_eq!(Ok(()), equivalent("a+", "aa*").unwrap());
+        assert_eq!(
+            Err(Counterexample {
+                input: "".to_string(),
+                left: true,
+                right: false,
+            }),
+            equivalent("a*", "a+").unwrap()
+        );
+        assert_eq!(
+            Err(Counterexample {
+                input: "a".to_string(),
+                left: false,
+                right: true,
+            }),
+            equivalent("ab", "ab|.").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_is_subset() {
+        assert_eq!(Ok(()), is_subset("ab+", "a.*").unwrap());
+        assert_eq!(Ok(()), is_subset("(if|for)", "(f|i|o|r)+").unwrap());
+        assert_eq!(
+            Err(Counterexample {
+                input: "a".to_string(),
+                left: true,
+                right: false,
+            }),
+            is_subset("a.*", "ab+").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_language_syntax() {
+        let name = Language::new("(a|b|f|i|o|r)+&~(if|for)").unwrap();
+        assert!(name.is_match("fi"));
+        assert!(!name.is_match("if"));
+
+        // 連結の中で補集合を使う場合
+        let l = Language::new("a~(b*)c").unwrap();
+        assert!(l.is_match("axc"));
+        assert!(l.is_match("aabc"));
+        assert!(!l.is_match("ac"));
+        assert!(!l.is_match("abbc"));
+    }
+
+    #[test]
+    fn test_language_unsupported() {
+        let unsupported = |feature| {
+            Some(Error::Unsupported {
+                engine: Engine::Dfa,
+                feature,
+            })
+        };
+        assert_eq!(unsupported("assertions"), Language::new(r"\bab").err());
+        assert_eq!(
+            unsupported("unicode properties"),
+            Language::new(r"\p{L}+").err()
+        );
+        assert_eq!(unsupported("backreferences"), Language::new(r"(a)\1").err());
+        assert_eq!(unsupported("lookaround"), Language::new("a(?=b)").err());
+        assert_eq!(unsupported("lookaround"), equivalent("a", "a(?!b)").err());
+
+        // 状態数が上限を超える場合
+        let pattern = "(a|b)*a(a|b)(a|b)(a|b)";
+        assert_eq!(
+            Some(Error::TooManyDfaStates { limit: 8 }),
+            Language::with_max_states(pattern, 8).err()
+        );
+        assert!(Language::new(pattern).is_ok());
+
+        // 各オペランドは上限未満でも、共通部分の直積が上限を超える場合（7・11・13の最小公倍数は1001）
+        let pattern = format!(
+            "({})*&({})*&({})*",
+            "a".repeat(7),
+            "a".repeat(11),
+            "a".repeat(13)
+        );
+        assert_eq!(
+            Some(Error::TooManyDfaStates { limit: 20 }),
+            Language::with_max_states(&pattern, 20).err()
+        );
+        let l = Language::new(&pattern).unwrap();
+        assert!(l.is_match(&"a".repeat(1001)));
+        assert!(!l.is_match(&"a".repeat(1000)));
+    }
+}
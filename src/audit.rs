@@ -0,0 +1,603 @@
+#![allow(dead_code)]
+
+use crate::parse::lexer::Lexer;
+use crate::parse::parser::{Ast, AstTree};
+use crate::vm::build::{Builder, RegexIR};
+use crate::vm::pike::PikeMachine;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+// 全文字にマッチする命令同士で使用する文字
+const ANY_CHAR: char = 'x';
+
+// 攻撃文字列の末尾候補（パターンに現れにくい文字を優先）
+const SUFFIX_CANDIDATES: [&str; 3] = ["", "!", "\u{0}"];
+
+// バックトラックの計算量
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Complexity {
+    Linear,
+    Polynomial(usize), // 入力長に対する次数
+    Exponential,
+}
+
+// 解析結果
+// 攻撃文字列は prefix + pump * n + suffix の形式
+// 計算量はメモ化を行わないバックトラック（Machine::memoize(false)）での値
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    pub complexity: Complexity,
+    pub memoized: bool, // 既定のバックトラック型VMが(pc, sp)の組のメモ化で線形時間に抑えるか
+    pub prefix: String,
+    pub pump: String,
+    pub suffix: String,
+}
+
+impl Report {
+    // 脆弱性の有無（メモ化を行わない場合）
+    pub fn is_vulnerable(&self) -> bool {
+        self.complexity != Complexity::Linear
+    }
+
+    // 既定の設定のバックトラック型VMでも入力長に対して線形時間を超えるか
+    // メモ化は後方参照を含むパターンでは行わないため、その場合のみ該当する
+    pub fn affects_default(&self) -> bool {
+        self.is_vulnerable() && !self.memoized
+    }
+
+    // pumpをrepeat回繰り返した攻撃文字列
+    pub fn attack_string(&self, repeat: usize) -> String {
+        format!("{}{}{}", self.prefix, self.pump.repeat(repeat), self.suffix)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.complexity {
+            Complexity::Linear => write!(f, "complexity: linear"),
+            Complexity::Polynomial(degree) => write!(f, "complexity: polynomial (O(n^{}))", degree),
+            Complexity::Exponential => write!(f, "complexity: exponential"),
+        }?;
+        write!(f, " (backtracking without memoization)")?;
+        if self.is_vulnerable() {
+            write!(
+                f,
+                "\nattack: {:?} + {:?} * n + {:?}",
+                self.prefix, self.pump, self.suffix
+            )?;
+            if self.memoized {
+                write!(f, "\ndefault engine: linear (memoized)")?;
+            } else {
+                write!(
+                    f,
+                    "\ndefault engine: affected (no memoization with backreferences)"
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// バックトラック型VMで壊滅的なバックトラックが起こりうるかを静的に解析
+// anchored=falseの場合はRegexBuilderと同様に部分文字列検索用のプログラムを解析する
+pub fn audit(pattern: &str, anchored: bool) -> Report {
+    let mut ast = Ast::new(&Lexer::new(pattern).scan()).parse();
+    let memoized = !ast.has_backref();
+
+    // 共通部分・補集合はバックトラック型VMで扱わない（DFA・微分は線形時間）
+    if ast.has_set_operator() {
+        return Report {
+            complexity: Complexity::Linear,
+            memoized,
+            prefix: String::new(),
+            pump: String::new(),
+            suffix: String::new(),
+        };
+    }
+    if ast.has_backref() {
+        ast = erase_backrefs(&ast, &ast, &[]);
+    }
+    if ast.has_lookaround() || ast.has_assertion() {
+        ast = match strip_lookarounds(&ast) {
+            Some(ast) => ast,
+            None => {
+                return Report {
+                    complexity: Complexity::Linear,
+                    memoized,
+                    prefix: String::new(),
+                    pump: String::new(),
+                    suffix: String::new(),
+                }
+            }
+        };
+    }
+    if !anchored {
+        ast = ast.unanchored();
+    }
+    Report {
+        memoized,
+        ..Auditor::new(Builder::from_ast(ast).compile()).report()
+    }
+}
+
+// 後方参照を参照先のグループの式で置き換える
+// 置き換え後のパターンは元のパターンにマッチする文字列を全て受理し、経路の曖昧さも保たれる
+// （自身を含むグループへの参照は常に失敗するが、近似として任意の一文字とする）
+fn erase_backrefs(ast: &AstTree, root: &AstTree, expanding: &[usize]) -> AstTree {
+    let sub = |a: &AstTree| Box::new(erase_backrefs(a, root, expanding));
+    match ast {
+        AstTree::Backref(n) if expanding.contains(n) => AstTree::Dot,
+        AstTree::Backref(n) => match find_group(root, *n) {
+            Some(group) => {
+                let mut expanding = expanding.to_vec();
+                expanding.push(*n);
+                erase_backrefs(group, root, &expanding)
+            }
+            None => panic!("[audit::erase_backrefs] group not found: {}", n),
+        },
+        AstTree::Capture(n, name, a) => AstTree::Capture(*n, name.clone(), sub(a)),
+        AstTree::Look(look, a) => AstTree::Look(*look, sub(a)),
+        AstTree::Concat(l, r) => AstTree::Concat(sub(l), sub(r)),
+        AstTree::Or(l, r) => AstTree::Or(sub(l), sub(r)),
+        AstTree::Repeat(a) => AstTree::Repeat(sub(a)),
+        AstTree::Plus(a) => AstTree::Plus(sub(a)),
+        AstTree::Question(a) => AstTree::Question(sub(a)),
+        ast => ast.clone(),
+    }
+}
+
+fn find_group(ast: &AstTree, n: usize) -> Option<&AstTree> {
+    match ast {
+        AstTree::Capture(m, _, a) if *m == n => Some(a),
+        _ => ast.children().into_iter().find_map(|a| find_group(a, n)),
+    }
+}
+
+// 先読み・後読みと表明を除く（空文字列のみになる場合はNone）
+// 除いた後のパターンは元のパターンにマッチする文字列を全て受理するため、攻撃文字列の検証に使える
+fn strip_lookarounds(ast: &AstTree) -> Option<AstTree> {
+    match ast {
+        AstTree::Look(_, _) | AstTree::Assert(_) => None,
+        AstTree::Concat(l, r) => match (strip_lookarounds(l), strip_lookarounds(r)) {
+            (Some(l), Some(r)) => Some(AstTree::Concat(Box::new(l), Box::new(r))),
+            (l, r) => l.or(r),
+        },
+        AstTree::Or(l, r) => match (strip_lookarounds(l), strip_lookarounds(r)) {
+            (Some(l), Some(r)) => Some(AstTree::Or(Box::new(l), Box::new(r))),
+            (Some(a), None) | (None, Some(a)) => Some(AstTree::Question(Box::new(a))),
+            (None, None) => None,
+        },
+        AstTree::Repeat(a) => strip_lookarounds(a).map(|a| AstTree::Repeat(Box::new(a))),
+        AstTree::Plus(a) => strip_lookarounds(a).map(|a| AstTree::Plus(Box::new(a))),
+        AstTree::Question(a) => strip_lookarounds(a).map(|a| AstTree::Question(Box::new(a))),
+        AstTree::Capture(n, name, a) => {
+            strip_lookarounds(a).map(|a| AstTree::Capture(*n, name.clone(), Box::new(a)))
+        }
+        ast => Some(ast.clone()),
+    }
+}
+
+// 命令列を、文字を消費する命令をノードとする曖昧なNFAとして解析
+// 同じ文字列を読む異なる経路の数がバックトラック量に対応する
+struct Auditor {
+    inst: Vec<RegexIR>,
+    nodes: Vec<usize>,                         // 文字を消費する命令
+    start: Vec<(usize, usize)>,                // 開始時に到達するノードと経路数
+    succ: HashMap<usize, Vec<(usize, usize)>>, // 文字消費後に到達するノードと経路数
+}
+
+impl Auditor {
+    pub fn new(inst: Vec<RegexIR>) -> Self {
+        let nodes: Vec<usize> = (0..inst.len())
+            .filter(|pc| {
+                matches!(
+                    inst[*pc],
+                    RegexIR::Char(_) | RegexIR::AllChar | RegexIR::Property(_)
+                )
+            })
+            .collect();
+        let mut auditor = Auditor {
+            inst,
+            nodes: nodes.clone(),
+            start: vec![],
+            succ: HashMap::new(),
+        };
+        auditor.start = auditor.epsilon(0);
+        for n in nodes {
+            let next = auditor.epsilon(n + 1);
+            auditor.succ.insert(n, next);
+        }
+
+        auditor
+    }
+
+    pub fn report(&self) -> Report {
+        // 指数的曖昧性（同じ状態へ戻る経路が二つ以上）
+        for q in self.nodes.iter() {
+            if let Some(pump) = self.exponential(*q) {
+                if let Some(report) = self.exploit(Complexity::Exponential, *q, pump) {
+                    return report;
+                }
+            }
+        }
+
+        // 多項式的曖昧性（p→p, p→q, q→q が同じ文字列で成立）
+        let mut pairs = vec![];
+        for p in self.nodes.iter() {
+            for q in self.nodes.iter().filter(|q| *q != p) {
+                if let Some(pump) = self.polynomial(*p, *q) {
+                    if let Some(report) = self.exploit(Complexity::Linear, *p, pump) {
+                        pairs.push((*p, *q, report));
+                    }
+                }
+            }
+        }
+        let degree = self.degree(&pairs);
+        if let Some((_, _, report)) = pairs.into_iter().next() {
+            return Report {
+                complexity: Complexity::Polynomial(degree),
+                ..report
+            };
+        }
+
+        Report {
+            complexity: Complexity::Linear,
+            memoized: true,
+            prefix: String::new(),
+            pump: String::new(),
+            suffix: String::new(),
+        }
+    }
+
+    // pcからJmp/Splitを辿って到達する消費命令（またはMatch）と、その経路数（2で飽和）
+    // 空ループを一周する経路も区別するため、同じ命令は一経路中に二回まで訪問を許す
+    fn epsilon(&self, pc: usize) -> Vec<(usize, usize)> {
+        let mut counts = HashMap::new();
+        let mut visits = vec![0; self.inst.len()];
+        self.epsilon_walk(pc, &mut visits, &mut counts);
+
+        let mut targets: Vec<(usize, usize)> = counts.into_iter().collect();
+        targets.sort_unstable();
+        targets
+    }
+
+    fn epsilon_walk(&self, pc: usize, visits: &mut [u8], counts: &mut HashMap<usize, usize>) {
+        if pc >= self.inst.len() || visits[pc] >= 2 {
+            return;
+        }
+
+        visits[pc] += 1;
+        match self.inst[pc] {
+            RegexIR::Jmp(x) => self.epsilon_walk(x, visits, counts),
+            RegexIR::Save(_) => self.epsilon_walk(pc + 1, visits, counts),
+            RegexIR::Split(x, y) => {
+                self.epsilon_walk(x, visits, counts);
+                self.epsilon_walk(y, visits, counts);
+            }
+            _ => {
+                let count = counts.entry(pc).or_insert(0);
+                *count = (*count + 1).min(2);
+            }
+        }
+        visits[pc] -= 1;
+    }
+
+    // 全ての命令が共通して受理する文字
+    fn common_char(&self, pcs: &[usize]) -> Option<char> {
+        let accepts = |c: char| {
+            pcs.iter().all(|pc| match self.inst[*pc] {
+                RegexIR::Char(x) => x == c,
+                RegexIR::AllChar => true,
+                RegexIR::Property(p) => p.contains(c),
+                _ => false,
+            })
+        };
+        // 候補はCharの文字、プロパティに含まれる文字の例、任意の文字
+        let candidates = pcs.iter().filter_map(|pc| match self.inst[*pc] {
+            RegexIR::Char(x) => Some(x),
+            RegexIR::Property(p) => p.sample(),
+            _ => None,
+        });
+        candidates.chain(Some(ANY_CHAR)).find(|c| accepts(*c))
+    }
+
+    // 文字消費後の遷移先（Matchは除く）
+    fn next(&self, node: usize) -> Vec<(usize, usize)> {
+        self.succ
+            .get(&node)
+            .map(|s| {
+                s.iter()
+                    .filter(|(pc, _)| self.inst[*pc] != RegexIR::Match)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // (q, q)から異なる経路を通って(q, q)へ戻る文字列を探す
+    fn exponential(&self, q: usize) -> Option<String> {
+        let start = (q, q, false);
+        let goal = (q, q, true);
+        self.search(start, goal, |&(p, r, diverged)| {
+            let c = match self.common_char(&[p, r]) {
+                Some(c) => c,
+                None => return vec![],
+            };
+            let mut next = vec![];
+            for (p2, mp) in self.next(p) {
+                for (r2, _) in self.next(r) {
+                    let split = p2 != r2 || (p == r && mp >= 2);
+                    next.push((c, (p2, r2, diverged || split)));
+                }
+            }
+            next
+        })
+    }
+
+    // 同じ文字列で (p, p, q) から (p, q, q) へ遷移できるかを探す
+    fn polynomial(&self, p: usize, q: usize) -> Option<String> {
+        self.search((p, p, q), (p, q, q), |&(x, y, z)| {
+            let c = match self.common_char(&[x, y, z]) {
+                Some(c) => c,
+                None => return vec![],
+            };
+            let mut next = vec![];
+            for (x2, _) in self.next(x) {
+                for (y2, _) in self.next(y) {
+                    for (z2, _) in self.next(z) {
+                        next.push((c, (x2, y2, z2)));
+                    }
+                }
+            }
+            next
+        })
+    }
+
+    // 積オートマトン上の幅優先探索（一文字以上読んでgoalへ到達する最短の文字列）
+    fn search<T, F>(&self, start: T, goal: T, next: F) -> Option<String>
+    where
+        T: Clone + Eq + std::hash::Hash,
+        F: Fn(&T) -> Vec<(char, T)>,
+    {
+        let mut parent: HashMap<T, (T, char)> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(start.clone());
+
+        while let Some(state) = queue.pop_front() {
+            for (c, n) in next(&state) {
+                if parent.contains_key(&n) {
+                    continue;
+                }
+                parent.insert(n.clone(), (state.clone(), c));
+                if n == goal {
+                    // 経路を逆順に辿って文字列を復元
+                    let mut word = vec![];
+                    let mut cur = n;
+                    loop {
+                        let (prev, c) = parent[&cur].clone();
+                        word.push(c);
+                        if prev == start {
+                            break;
+                        }
+                        cur = prev;
+                    }
+                    return Some(word.into_iter().rev().collect());
+                }
+                queue.push_back(n);
+            }
+        }
+
+        None
+    }
+
+    // 多項式的曖昧性の連鎖の長さから次数を求める
+    fn degree<T>(&self, pairs: &[(usize, usize, T)]) -> usize {
+        let edges: HashSet<(usize, usize)> = pairs.iter().map(|(p, q, _)| (*p, *q)).collect();
+        let mut memo = HashMap::new();
+        self.nodes
+            .iter()
+            .map(|n| self.chain(*n, &edges, &mut memo))
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    fn chain(
+        &self,
+        node: usize,
+        edges: &HashSet<(usize, usize)>,
+        memo: &mut HashMap<usize, usize>,
+    ) -> usize {
+        if let Some(len) = memo.get(&node) {
+            return *len;
+        }
+
+        // 循環が無いことを前提とするため、探索中は0として扱う
+        memo.insert(node, 0);
+        let len = edges
+            .iter()
+            .filter(|(p, _)| *p == node)
+            .map(|(_, q)| self.chain(*q, edges, memo) + 1)
+            .max()
+            .unwrap_or(0);
+        memo.insert(node, len);
+        len
+    }
+
+    // 開始位置からノードへ到達する最短の文字列
+    fn prefix(&self, node: usize) -> String {
+        let mut parent: HashMap<usize, Option<(usize, char)>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for (n, _) in self.start.iter() {
+            parent.insert(*n, None);
+            queue.push_back(*n);
+        }
+
+        while let Some(n) = queue.pop_front() {
+            if n == node {
+                let mut word = vec![];
+                let mut cur = n;
+                while let Some(Some((prev, c))) = parent.get(&cur) {
+                    word.push(*c);
+                    cur = *prev;
+                }
+                return word.into_iter().rev().collect();
+            }
+
+            if let Some(c) = self.common_char(&[n]) {
+                for (n2, _) in self.next(n) {
+                    parent.entry(n2).or_insert_with(|| {
+                        queue.push_back(n2);
+                        Some((n, c))
+                    });
+                }
+            }
+        }
+
+        String::new()
+    }
+
+    // マッチに失敗する末尾を探してレポート作成
+    // 失敗させられない場合（曖昧な経路の途中で必ずマッチする場合）は悪用できないためNone
+    fn exploit(&self, complexity: Complexity, node: usize, pump: String) -> Option<Report> {
+        let prefix = self.prefix(node);
+        let machine = PikeMachine::from_inst(self.inst.clone()).full_match(true);
+        let attack = |suffix: &str| format!("{}{}{}", prefix, pump.repeat(8), suffix);
+        let suffix = SUFFIX_CANDIDATES
+            .iter()
+            .find(|s| !machine.is_match(&attack(s)))?;
+
+        Some(Report {
+            complexity,
+            memoized: true,
+            prefix,
+            pump,
+            suffix: suffix.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vm::machine::Machine;
+
+    #[test]
+    fn test_audit_linear() {
+        for pattern in ["abc", "a*b", "a|b", "(ab)*c", "a?a?b"].iter() {
+            let report = audit(pattern, true);
+
+            assert_eq!(Complexity::Linear, report.complexity, "{}", pattern);
+            assert!(!report.is_vulnerable());
+        }
+    }
+
+    #[test]
+    fn test_audit_exponential() {
+        for pattern in ["(a*)*b", "(a|a)*b", "(a+)+b", "(a|aa)*b", "(.*a)*b"].iter() {
+            let report = audit(pattern, true);
+
+            assert_eq!(Complexity::Exponential, report.complexity, "{}", pattern);
+            // 既定のバックトラック型VMはメモ化により線形時間
+            assert!(report.memoized);
+            assert!(!report.affects_default());
+        }
+    }
+
+    #[test]
+    fn test_audit_polynomial() {
+        assert_eq!(Complexity::Polynomial(2), audit("a*a*b", true).complexity);
+        assert_eq!(Complexity::Polynomial(3), audit("a*a*a*b", true).complexity);
+        assert_eq!(Complexity::Polynomial(2), audit("a+b", false).complexity);
+    }
+
+    #[test]
+    fn test_audit_backref() {
+        // 後方参照は参照先のグループと同じ経路を持つものとして解析する
+        assert_eq!(Complexity::Linear, audit(r"(ab)\1c", true).complexity);
+        let report = audit(r"(a|b)\1(a|aa)*c", true);
+        assert_eq!(Complexity::Exponential, report.complexity);
+        // 後方参照を含む場合はメモ化しないため、既定の設定でも該当する
+        assert!(!report.memoized);
+        assert!(report.affects_default());
+    }
+
+    #[test]
+    fn test_audit_lookaround() {
+        // 先読み・後読みを除いたパターンとして解析する
+        assert_eq!(Complexity::Linear, audit("(?=a)b(?<!c)", true).complexity);
+        assert_eq!(
+            Complexity::Exponential,
+            audit("(?!x)(a|aa)*(?=b)b", true).complexity
+        );
+    }
+
+    #[test]
+    fn test_audit_assertion() {
+        // 表明を除いたパターンとして解析する
+        assert_eq!(Complexity::Linear, audit(r"\bab\b", false).complexity);
+        assert_eq!(
+            Complexity::Exponential,
+            audit(r"\A(a|aa)*\bb\z", true).complexity
+        );
+    }
+
+    #[test]
+    fn test_audit_property() {
+        // プロパティと文字の両方に含まれる文字で経路が分岐する
+        assert_eq!(
+            Complexity::Exponential,
+            audit(r"(\p{L}|a)*b", true).complexity
+        );
+        assert_eq!(Complexity::Linear, audit(r"(\p{Nd}|a)*b", true).complexity);
+        let report = audit(r"(\p{Greek}|\p{L})*1", true);
+        assert_eq!(Complexity::Exponential, report.complexity);
+        let attack = report.attack_string(10);
+        assert!(attack.chars().any(|c| c != 'x'));
+    }
+
+    #[test]
+    fn test_audit_unexploitable() {
+        // 曖昧な経路の途中で必ずマッチが成立するため、悪用できない
+        assert_eq!(Complexity::Linear, audit("abc", false).complexity);
+        assert_eq!(Complexity::Linear, audit("a+", false).complexity);
+    }
+
+    #[test]
+    fn test_audit_attack_string() {
+        let report = audit("(a|aa)*b", true);
+        let attack = report.attack_string(30);
+
+        // 攻撃文字列はマッチせず、枝刈り無しのバックトラックで上限を超える
+        let inst = Builder::new("(a|aa)*b").compile();
+        let machine = Machine::from_inst(inst)
+            .full_match(true)
+            .memoize(false)
+            .max_steps(100000);
+        assert!(machine.try_match(&attack).is_err());
+        assert!(!PikeMachine::new("(a|aa)*b")
+            .full_match(true)
+            .is_match(&attack));
+
+        // 既定のメモ化ありでは (pc, sp) の組の数以内で終了する
+        let inst = Builder::new("(a|aa)*b").compile();
+        let bound = inst.len() * (attack.len() + 1);
+        let machine = Machine::from_inst(inst).full_match(true).max_steps(bound);
+        assert_eq!(Ok(false), machine.try_match(&attack));
+    }
+
+    #[test]
+    fn test_audit_display() {
+        let report = audit("(a+)+b", true);
+        assert!(report
+            .to_string()
+            .starts_with("complexity: exponential (backtracking without memoization)"));
+        assert!(report
+            .to_string()
+            .ends_with("default engine: linear (memoized)"));
+        assert_eq!(
+            "complexity: linear (backtracking without memoization)",
+            audit("a*b", true).to_string()
+        );
+    }
+}
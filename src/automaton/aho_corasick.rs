@@ -0,0 +1,201 @@
+#![allow(dead_code)]
+
+use std::collections::{HashMap, VecDeque};
+
+// 根のノード
+const ROOT: usize = 0;
+
+// 複数リテラルの一致箇所
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LiteralMatch {
+    pub pattern: usize, // 一致したリテラルの番号
+    pub start: usize,   // 開始位置（バイト位置）
+    pub end: usize,     // 終了位置（バイト位置）
+}
+
+// Aho-Corasick法による複数リテラル検索
+#[derive(Debug)]
+pub struct MultiLiteral {
+    patterns: Vec<String>,
+    goto: Vec<HashMap<char, usize>>, // トライの遷移
+    fail: Vec<usize>,                // 失敗時の遷移先
+    output: Vec<Vec<usize>>,         // 各ノードで一致するリテラルの番号
+}
+
+impl MultiLiteral {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let mut ac = MultiLiteral {
+            patterns: patterns.iter().map(|p| p.as_ref().to_string()).collect(),
+            goto: vec![HashMap::new()],
+            fail: vec![ROOT],
+            output: vec![vec![]],
+        };
+        ac.build_trie();
+        ac.build_fail();
+        ac
+    }
+
+    // リテラルの数
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    // 番号に対応するリテラル
+    pub fn pattern(&self, id: usize) -> &str {
+        &self.patterns[id]
+    }
+
+    // いずれかのリテラルを含むか
+    pub fn is_match(&self, haystack: &str) -> bool {
+        self.find_iter(haystack).next().is_some()
+    }
+
+    // 入力全体がいずれかのリテラルと一致するか
+    pub fn is_full_match(&self, haystack: &str) -> bool {
+        let mut node = ROOT;
+        for c in haystack.chars() {
+            match self.goto[node].get(&c) {
+                Some(&next) => node = next,
+                None => return false,
+            }
+        }
+        self.output[node]
+            .iter()
+            .any(|&id| self.patterns[id].len() == haystack.len())
+    }
+
+    // 全ての一致箇所（重なりを含み、終了位置の順）
+    pub fn find_iter<'m, 'h>(&'m self, haystack: &'h str) -> Matches<'m, 'h> {
+        Matches {
+            ac: self,
+            chars: haystack.char_indices(),
+            node: ROOT,
+            pending: VecDeque::new(),
+        }
+    }
+
+    // 一文字進めた先のノード
+    fn next(&self, mut node: usize, c: char) -> usize {
+        loop {
+            if let Some(&next) = self.goto[node].get(&c) {
+                return next;
+            }
+            if node == ROOT {
+                return ROOT;
+            }
+            node = self.fail[node];
+        }
+    }
+
+    // トライ木の構築
+    fn build_trie(&mut self) {
+        for (id, pattern) in self.patterns.iter().enumerate() {
+            let mut node = ROOT;
+            for c in pattern.chars() {
+                node = match self.goto[node].get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        self.goto.push(HashMap::new());
+                        self.fail.push(ROOT);
+                        self.output.push(vec![]);
+                        let next = self.goto.len() - 1;
+                        self.goto[node].insert(c, next);
+                        next
+                    }
+                };
+            }
+            self.output[node].push(id);
+        }
+    }
+
+    // 幅優先で失敗遷移を構築し、失敗先の出力を引き継ぐ
+    fn build_fail(&mut self) {
+        let mut queue: VecDeque<usize> = self.goto[ROOT].values().copied().collect();
+
+        while let Some(node) = queue.pop_front() {
+            let edges: Vec<(char, usize)> = self.goto[node].iter().map(|(&c, &n)| (c, n)).collect();
+            for (c, child) in edges {
+                let fail = if node == ROOT {
+                    ROOT
+                } else {
+                    self.next(self.fail[node], c)
+                };
+                self.fail[child] = fail;
+
+                let inherited = self.output[fail].clone();
+                self.output[child].extend(inherited);
+                queue.push_back(child);
+            }
+        }
+    }
+}
+
+pub struct Matches<'m, 'h> {
+    ac: &'m MultiLiteral,
+    chars: std::str::CharIndices<'h>,
+    node: usize,
+    pending: VecDeque<LiteralMatch>,
+}
+
+impl<'m, 'h> Iterator for Matches<'m, 'h> {
+    type Item = LiteralMatch;
+
+    fn next(&mut self) -> Option<LiteralMatch> {
+        while self.pending.is_empty() {
+            let (i, c) = self.chars.next()?;
+            self.node = self.ac.next(self.node, c);
+
+            let end = i + c.len_utf8();
+            for &id in self.ac.output[self.node].iter() {
+                self.pending.push_back(LiteralMatch {
+                    pattern: id,
+                    start: end - self.ac.patterns[id].len(),
+                    end,
+                });
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_multi_literal_find_iter() {
+        let ac = MultiLiteral::new(&["he", "she", "his", "hers"]);
+        let found: Vec<(usize, usize, usize)> = ac
+            .find_iter("ushers")
+            .map(|m| (m.pattern, m.start, m.end))
+            .collect();
+
+        assert_eq!(vec![(1, 1, 4), (0, 2, 4), (3, 2, 6)], found);
+    }
+
+    #[test]
+    fn test_multi_literal_is_match() {
+        let ac = MultiLiteral::new(&["error", "warn", "fatal"]);
+
+        assert!(ac.is_match("[WARN] warning: disk"));
+        assert!(ac.is_match("fatalerror"));
+        assert!(!ac.is_match("info: ok"));
+        assert!(!ac.is_match(""));
+        assert!(MultiLiteral::new(&["äö"]).is_match("xäöx"));
+    }
+
+    #[test]
+    fn test_multi_literal_is_full_match() {
+        let ac = MultiLiteral::new(&["ab", "abc", "b"]);
+
+        assert!(ac.is_full_match("ab"));
+        assert!(ac.is_full_match("abc"));
+        assert!(ac.is_full_match("b"));
+        assert!(!ac.is_full_match("a"));
+        assert!(!ac.is_full_match("abcd"));
+    }
+}
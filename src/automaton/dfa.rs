@@ -1,17 +1,172 @@
 #![allow(dead_code)]
 
-use crate::automaton::farule::{FARule, State};
+use crate::automaton::elimination::Elimination;
+use crate::automaton::farule::{FARule, State, TransitionType};
+use crate::derivative::Re;
+use crate::parse::parser::{AstTree, CharKind};
+use crate::unicode::Property;
 use std::char;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
 use std::vec::Vec;
 
+// 文字指定のない文字を、各プロパティに含まれるか（と単語構成文字か）で分類する
+// クラスはi番目のプロパティに含まれる場合にiビット目、単語構成文字の場合に最上位のビットを立てた値
+#[derive(Debug, Clone)]
+pub struct Classifier {
+    properties: Vec<Property>,
+    word: bool, // 単語構成文字か否かも区別するか（表明を含む場合）
+}
+
+impl Classifier {
+    pub fn new(properties: Vec<Property>, word: bool) -> Self {
+        if properties.len() >= usize::BITS as usize - 1 {
+            panic!(
+                "[Classifier::new] too many properties ({})",
+                properties.len()
+            )
+        }
+        Classifier { properties, word }
+    }
+
+    fn word_bit(&self) -> usize {
+        1 << self.properties.len()
+    }
+
+    // 文字の属するクラス
+    pub fn classify(&self, c: char) -> usize {
+        let mut class = self
+            .properties
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.contains(c))
+            .fold(0, |class, (i, _)| class | 1 << i);
+        if self.word && CharKind::of(Some(c)) == CharKind::Word {
+            class |= self.word_bit();
+        }
+        class
+    }
+
+    // 文字が属しうる全てのクラス
+    // 範囲表の境界で区切った区間の中では各プロパティへの所属が変わらないため、区間の先頭の文字で調べる
+    pub fn classes(&self) -> Vec<usize> {
+        let mut bounds: BTreeSet<u32> = vec![0].into_iter().collect();
+        for p in self.properties.iter() {
+            for (start, end) in p.ranges().iter() {
+                bounds.insert(*start as u32);
+                bounds.insert(*end as u32 + 1);
+            }
+        }
+        let mut classes: BTreeSet<usize> = bounds
+            .into_iter()
+            // サロゲートの区間は直後の文字で代表する
+            .filter_map(|b| char::from_u32(b).or_else(|| char::from_u32(0xE000)))
+            .map(|c| self.classify(c) & !self.word_bit())
+            .collect();
+        if self.word {
+            let words: Vec<usize> = classes.iter().map(|c| c | self.word_bit()).collect();
+            classes.extend(words);
+        }
+        classes.into_iter().collect()
+    }
+
+    // クラスの文字がプロパティに含まれるか
+    pub fn is_member(&self, class: usize, property: &Property) -> bool {
+        match self.properties.iter().position(|p| p == property) {
+            Some(i) => class & 1 << i != 0,
+            None => panic!("[Classifier::is_member] unknown property: {:?}", property),
+        }
+    }
+
+    // クラスの文字の種類（単語構成文字か否か）
+    pub fn kind(&self, class: usize) -> CharKind {
+        if class & self.word_bit() != 0 {
+            CharKind::Word
+        } else {
+            CharKind::Other
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DFARulebook {
     rules: Vec<FARule>,
+    classifier: Option<Classifier>, // クラスの遷移を含む場合のみ
 }
 
 impl DFARulebook {
     pub fn new(rules: Vec<FARule>) -> Self {
-        DFARulebook { rules }
+        DFARulebook {
+            rules,
+            classifier: None,
+        }
+    }
+
+    // 文字指定のない文字をクラスで遷移するルールブック
+    pub fn with_classifier(rules: Vec<FARule>, classifier: Classifier) -> Self {
+        DFARulebook {
+            rules,
+            classifier: Some(classifier),
+        }
+    }
+
+    // 文字指定のルールに現れる文字
+    pub fn alphabet(&self) -> BTreeSet<char> {
+        self.rules
+            .iter()
+            .filter_map(|r| match r.transition {
+                TransitionType::Character(c) => Some(c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // 状態除去法により、開始状態から受理状態までの文字列にマッチするASTを求める
+    // 全文字の遷移は、同じ状態から別の状態へ遷移する文字以外の文字（.&~(a|b)）とする
+    pub fn to_ast(&self, start_state: State, accept_states: &[State]) -> AstTree {
+        let everything = |state: State| {
+            self.rules
+                .iter()
+                .find(|r| r.state == state && r.transition == TransitionType::Everything)
+                .map(|r| r.next_state)
+        };
+
+        let mut edges = vec![];
+        for r in self.rules.iter() {
+            let re = match r.transition {
+                // 全文字の遷移と同じ遷移先であれば、全文字の遷移に含める
+                TransitionType::Character(_) if everything(r.state) == Some(r.next_state) => {
+                    continue
+                }
+                TransitionType::Character(c) => Rc::new(Re::Char(c)),
+                TransitionType::Everything => {
+                    let others = self
+                        .rules
+                        .iter()
+                        .filter(|o| o.state == r.state && o.next_state != r.next_state)
+                        .filter_map(|o| match o.transition {
+                            TransitionType::Character(c) => Some(Rc::new(Re::Char(c))),
+                            _ => None,
+                        })
+                        .fold(Rc::new(Re::Empty), Re::or);
+                    match *others {
+                        Re::Empty => Rc::new(Re::Any),
+                        _ => Re::and(Rc::new(Re::Any), Re::not(others)),
+                    }
+                }
+                TransitionType::Epsilon => Rc::new(Re::Epsilon),
+                TransitionType::Assert(_)
+                | TransitionType::Class(_)
+                | TransitionType::Property(_) => {
+                    panic!(
+                        "[DFARulebook::to_ast] assertions and properties are not supported ({:?})",
+                        r
+                    )
+                }
+            };
+            edges.push((r.state, re, r.next_state));
+        }
+        Elimination::to_ast(start_state, accept_states, edges)
     }
 
     pub fn next_state(&self, state: State, character: Option<char>) -> Option<State> {
@@ -19,7 +174,23 @@ impl DFARulebook {
     }
 
     fn rule_for(&self, state: &State, character: Option<char>) -> Option<&FARule> {
-        self.rules.iter().find(|r| r.applies_to(state, &character))
+        // 文字指定のルール、文字のクラスのルール、全文字のルールの順に優先
+        let class = self
+            .classifier
+            .as_ref()
+            .zip(character)
+            .map(|(classifier, c)| classifier.classify(c));
+        self.rules
+            .iter()
+            .filter(|r| match (r.transition, class) {
+                (TransitionType::Class(k), Some(class)) => r.state == *state && k == class,
+                _ => r.applies_to(state, &character),
+            })
+            .min_by_key(|r| match r.transition {
+                TransitionType::Character(_) => 0,
+                TransitionType::Class(_) => 1,
+                _ => 2,
+            })
     }
 }
 
@@ -43,7 +214,7 @@ impl<'a> Dfa<'a> {
     }
 
     pub fn accepting(&self) -> bool {
-        self.accept_states.iter().any(|s| *s == self.current_state)
+        self.accept_states.contains(&self.current_state)
     }
 
     pub fn read_string(&mut self, s: &str) -> Result<(), &str> {
@@ -83,10 +254,168 @@ impl<'a> DFADesign<'a> {
     }
 }
 
+// 表明を含むDFAの、直前の文字の種類ごとの開始状態と、直後の文字の種類ごとの受理状態
+#[derive(Debug)]
+pub struct Boundary {
+    starts: HashMap<CharKind, State>,
+    accepts: HashMap<CharKind, Vec<State>>,
+}
+
+impl Boundary {
+    pub fn new(starts: HashMap<CharKind, State>, accepts: HashMap<CharKind, Vec<State>>) -> Self {
+        Boundary { starts, accepts }
+    }
+}
+
+// 受理状態と直後の文字の種類ごとに、受理するパターンの番号（昇順）
+// 表明を含まないDFAでは、文字の種類はCharKind::Edgeのみ
+pub type PatternIds = HashMap<(State, CharKind), Vec<usize>>;
+
+// 所有権を持つDFA（NFAConverterの変換結果を保持）
+#[derive(Debug)]
+pub struct DFAProgram {
+    start_state: State,
+    accept_states: Vec<State>,
+    rulebook: DFARulebook,
+    boundary: Option<Box<Boundary>>,   // 表明を含む場合のみ
+    patterns: Option<Box<PatternIds>>, // 複数パターンの和の場合のみ
+}
+
+impl DFAProgram {
+    pub fn new(start_state: State, accept_states: Vec<State>, rulebook: DFARulebook) -> Self {
+        DFAProgram {
+            start_state,
+            accept_states,
+            rulebook,
+            boundary: None,
+            patterns: None,
+        }
+    }
+
+    // 表明を含むDFA（開始状態・受理状態は入力の先頭・末尾のもの）
+    pub fn with_boundary(rulebook: DFARulebook, boundary: Boundary) -> Self {
+        DFAProgram {
+            start_state: boundary.starts[&CharKind::Edge],
+            accept_states: boundary
+                .accepts
+                .get(&CharKind::Edge)
+                .cloned()
+                .unwrap_or_default(),
+            rulebook,
+            boundary: Some(Box::new(boundary)),
+            patterns: None,
+        }
+    }
+
+    // 受理状態ごとのパターンの番号を設定
+    pub fn with_patterns(mut self, patterns: PatternIds) -> Self {
+        if !patterns.is_empty() {
+            self.patterns = Some(Box::new(patterns));
+        }
+        self
+    }
+
+    // 直前の文字がprevの位置から照合する場合の開始状態
+    pub fn start_after(&self, prev: Option<char>) -> State {
+        match &self.boundary {
+            Some(boundary) => boundary.starts[&CharKind::of(prev)],
+            None => self.start_state,
+        }
+    }
+
+    // 直後の文字がnextの位置で受理状態か
+    pub fn is_accept_before(&self, state: &State, next: Option<char>) -> bool {
+        match &self.boundary {
+            Some(boundary) => boundary
+                .accepts
+                .get(&CharKind::of(next))
+                .is_some_and(|accepts| accepts.contains(state)),
+            None => self.is_accept(state),
+        }
+    }
+
+    // 直後の文字がnextの位置で受理するパターンの番号（番号を設定していない場合は0のみ）
+    pub fn patterns_before(&self, state: &State, next: Option<char>) -> Vec<usize> {
+        if !self.is_accept_before(state, next) {
+            return vec![];
+        }
+        let kind = match &self.boundary {
+            Some(_) => CharKind::of(next),
+            None => CharKind::Edge,
+        };
+        match self
+            .patterns
+            .as_ref()
+            .and_then(|patterns| patterns.get(&(*state, kind)))
+        {
+            Some(ids) => ids.clone(),
+            None => vec![0],
+        }
+    }
+
+    // 受理状態へ到達できる状態（それ以外の状態へ遷移した時点で、以降は受理しない）
+    pub fn live_states(&self) -> HashSet<State> {
+        let mut live: HashSet<State> = self.accept_states.iter().copied().collect();
+        if let Some(boundary) = &self.boundary {
+            live.extend(boundary.accepts.values().flatten().copied());
+        }
+        loop {
+            let count = live.len();
+            for r in self.rulebook.rules.iter() {
+                if live.contains(&r.next_state) {
+                    live.insert(r.state);
+                }
+            }
+            if live.len() == count {
+                return live;
+            }
+        }
+    }
+
+    pub fn accept(&self, s: &str) -> bool {
+        DFADesign::new(self.start_state, &self.accept_states, &self.rulebook).accept(s)
+    }
+
+    // 開始状態
+    pub fn start(&self) -> State {
+        self.start_state
+    }
+
+    // 一文字分の遷移（遷移先がない場合はNone）
+    pub fn next(&self, state: State, c: char) -> Option<State> {
+        self.rulebook.next_state(state, Some(c))
+    }
+
+    // 受理状態か
+    pub fn is_accept(&self, state: &State) -> bool {
+        self.accept_states.contains(state)
+    }
+
+    // 文字指定の遷移に現れる文字（それ以外の文字は全文字の遷移に従う）
+    pub fn alphabet(&self) -> BTreeSet<char> {
+        self.rulebook.alphabet()
+    }
+
+    // 状態数
+    pub fn state_count(&self) -> usize {
+        let mut states: BTreeSet<State> = self.accept_states.iter().copied().collect();
+        states.insert(self.start_state);
+        for r in self.rulebook.rules.iter() {
+            states.insert(r.state);
+            states.insert(r.next_state);
+        }
+        states.len()
+    }
+
+    // 受理する文字列にマッチするAST
+    pub fn to_ast(&self) -> AstTree {
+        self.rulebook.to_ast(self.start_state, &self.accept_states)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::automaton::farule::TransitionType;
 
     #[test]
     fn test_next_state() {
@@ -114,6 +443,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn test_dfa_accepting() {
         let rule = DFARulebook::new(vec![
             FARule::new(State::new(1), TransitionType::Character('a'), State::new(2)),
@@ -159,4 +489,109 @@ mod test {
         let design = DFADesign::new(State::new(1), &accept_statuses, &rule);
         assert!(design.accept("baaab"));
     }
+
+    #[test]
+    fn test_dfa_boundary() {
+        // 開始状態1は直前が単語構成文字、2はそれ以外
+        // 受理状態は、直前と直後の文字の種類が異なる位置（\b）
+        // プロパティがないため、クラス1は単語構成文字、0はそれ以外
+        let rule = DFARulebook::with_classifier(
+            vec![
+                FARule::new(State::new(1), TransitionType::Character('_'), State::new(1)),
+                FARule::new(State::new(1), TransitionType::Class(1), State::new(1)),
+                FARule::new(State::new(1), TransitionType::Class(0), State::new(2)),
+                FARule::new(State::new(2), TransitionType::Class(1), State::new(1)),
+                FARule::new(State::new(2), TransitionType::Class(0), State::new(2)),
+            ],
+            Classifier::new(vec![], true),
+        );
+        let starts = vec![
+            (CharKind::Edge, State::new(2)),
+            (CharKind::Word, State::new(1)),
+            (CharKind::Other, State::new(2)),
+        ];
+        let accepts = vec![
+            (CharKind::Edge, vec![State::new(1)]),
+            (CharKind::Word, vec![State::new(2)]),
+            (CharKind::Other, vec![State::new(1)]),
+        ];
+        let dfa = DFAProgram::with_boundary(
+            rule,
+            Boundary::new(starts.into_iter().collect(), accepts.into_iter().collect()),
+        );
+
+        assert!(dfa.accept("ab"));
+        assert!(!dfa.accept("a "));
+        assert_eq!(Some(State::new(1)), dfa.next(State::new(2), '_'));
+        assert_eq!(Some(State::new(2)), dfa.next(State::new(1), '-'));
+        assert_eq!(State::new(1), dfa.start_after(Some('a')));
+        assert!(dfa.is_accept_before(&dfa.start_after(Some('a')), Some(' ')));
+        assert!(!dfa.is_accept_before(&dfa.start_after(None), Some(' ')));
+    }
+
+    #[test]
+    fn test_classifier() {
+        let greek = Property::new("Greek", false).unwrap();
+        let letter = Property::new("L", false).unwrap();
+        let classifier = Classifier::new(vec![greek, letter], true);
+
+        assert_eq!(0b111, classifier.classify('α'));
+        assert_eq!(0b110, classifier.classify('a'));
+        assert_eq!(0b100, classifier.classify('1'));
+        assert_eq!(0b000, classifier.classify(' '));
+        assert!(classifier.is_member(0b011, &letter));
+        assert!(!classifier.is_member(0b110, &greek));
+        assert_eq!(CharKind::Word, classifier.kind(0b100));
+        assert_eq!(CharKind::Other, classifier.kind(0b011));
+
+        // ギリシア文字のうち文字でないもの（͵など）も含め、現れうるクラスを全て列挙する
+        let classes = classifier.classes();
+        for c in ['α', 'a', '1', ' ', '͵', '\u{10FFFF}'].iter() {
+            assert!(classes.contains(&classifier.classify(*c)), "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn test_dfa_class_rule() {
+        // プロパティに含まれる文字はクラス1、それ以外は0
+        let digit = Property::new("Nd", false).unwrap();
+        let rule = DFARulebook::with_classifier(
+            vec![
+                FARule::new(State::new(1), TransitionType::Character('a'), State::new(2)),
+                FARule::new(State::new(1), TransitionType::Class(1), State::new(3)),
+                FARule::new(State::new(1), TransitionType::Everything, State::new(4)),
+            ],
+            Classifier::new(vec![digit], false),
+        );
+
+        assert_eq!(
+            Some(State::new(2)),
+            rule.next_state(State::new(1), Some('a'))
+        );
+        assert_eq!(
+            Some(State::new(3)),
+            rule.next_state(State::new(1), Some('٣'))
+        );
+        assert_eq!(
+            Some(State::new(4)),
+            rule.next_state(State::new(1), Some('b'))
+        );
+    }
+
+    #[test]
+    fn test_dfa_prefer_character_rule() {
+        let rule = DFARulebook::new(vec![
+            FARule::new(State::new(1), TransitionType::Everything, State::new(3)),
+            FARule::new(State::new(1), TransitionType::Character('a'), State::new(2)),
+        ]);
+
+        assert_eq!(
+            Some(State::new(2)),
+            rule.next_state(State::new(1), Some('a'))
+        );
+        assert_eq!(
+            Some(State::new(3)),
+            rule.next_state(State::new(1), Some('b'))
+        );
+    }
 }
@@ -0,0 +1,237 @@
+#![allow(dead_code)]
+
+use crate::automaton::farule::State;
+use crate::derivative::Re;
+use crate::parse::parser::AstTree;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::rc::Rc;
+
+// 状態除去法によるオートマトンから正規表現への変換
+// 遷移を式をラベルとする辺とみなし、新しい開始・受理状態の間の辺が一本になるまで状態を除去する
+pub struct Elimination {
+    edges: BTreeMap<(usize, usize), Rc<Re>>, // (遷移元, 遷移先)ごとの式
+    nodes: BTreeSet<usize>,                  // 除去していない元の状態
+    start: usize,                            // 新しい開始状態
+    accept: usize,                           // 新しい受理状態
+}
+
+impl Elimination {
+    // 式をラベルとする遷移からASTを求める
+    pub fn to_ast(
+        start: State,
+        accept_states: &[State],
+        edges: Vec<(State, Rc<Re>, State)>,
+    ) -> AstTree {
+        let mut ids: HashMap<State, usize> = HashMap::new();
+        let mut id = |s: State| {
+            let next = ids.len();
+            *ids.entry(s).or_insert(next)
+        };
+
+        let start = id(start);
+        let accept_states: Vec<usize> = accept_states.iter().map(|s| id(*s)).collect();
+        let edges: Vec<(usize, Rc<Re>, usize)> = edges
+            .into_iter()
+            .map(|(from, re, to)| (id(from), re, id(to)))
+            .collect();
+
+        let nodes: BTreeSet<usize> = (0..ids.len()).collect();
+        let mut e = Elimination {
+            edges: BTreeMap::new(),
+            nodes,
+            start: ids.len(),
+            accept: ids.len() + 1,
+        };
+        e.add(e.start, Rc::new(Re::Epsilon), start);
+        for a in accept_states {
+            e.add(a, Rc::new(Re::Epsilon), e.accept);
+        }
+        for (from, re, to) in edges {
+            e.add(from, re, to);
+        }
+
+        e.trim();
+        while let Some(q) = e.next_node() {
+            e.remove(q);
+        }
+        match e.edges.get(&(e.start, e.accept)) {
+            Some(re) => re.to_ast(),
+            None => Re::Empty.to_ast(),
+        }
+    }
+
+    // 並行する辺は選択でまとめる
+    fn add(&mut self, from: usize, re: Rc<Re>, to: usize) {
+        if *re == Re::Empty {
+            return;
+        }
+        let re = match self.edges.remove(&(from, to)) {
+            Some(old) => Re::or(old, re),
+            None => re,
+        };
+        self.edges.insert((from, to), re);
+    }
+
+    // 開始状態から到達できない、または受理状態へ到達できない状態を除く
+    fn trim(&mut self) {
+        let forward = self.closure(self.start, |(from, to)| (*from, *to));
+        let backward = self.closure(self.accept, |(from, to)| (*to, *from));
+
+        self.nodes
+            .retain(|q| forward.contains(q) && backward.contains(q));
+        let nodes = &self.nodes;
+        let (start, accept) = (self.start, self.accept);
+        let alive = |q: &usize| *q == start || *q == accept || nodes.contains(q);
+        self.edges.retain(|(from, to), _| alive(from) && alive(to));
+    }
+
+    fn closure<F: Fn(&(usize, usize)) -> (usize, usize)>(
+        &self,
+        root: usize,
+        dir: F,
+    ) -> BTreeSet<usize> {
+        let mut visited: BTreeSet<usize> = vec![root].into_iter().collect();
+        let mut stack = vec![root];
+        while let Some(q) = stack.pop() {
+            for key in self.edges.keys() {
+                let (from, to) = dir(key);
+                if from == q && visited.insert(to) {
+                    stack.push(to);
+                }
+            }
+        }
+        visited
+    }
+
+    // 入次数×出次数が最小の状態から除去する（式が大きくなりにくい）
+    fn next_node(&self) -> Option<usize> {
+        self.nodes.iter().copied().min_by_key(|q| {
+            let degree =
+                |f: &dyn Fn(&(usize, usize)) -> bool| self.edges.keys().filter(|k| f(k)).count();
+            degree(&|(from, to)| to == q && from != q) * degree(&|(from, to)| from == q && to != q)
+        })
+    }
+
+    // 状態qを除去し、p→q→rの経路をp→rの辺 R(p,q) R(q,q)* R(q,r) に置き換える
+    fn remove(&mut self, q: usize) {
+        self.nodes.remove(&q);
+        let loop_re = match self.edges.remove(&(q, q)) {
+            Some(re) => star(re),
+            None => Rc::new(Re::Epsilon),
+        };
+
+        let incoming: Vec<(usize, Rc<Re>)> = self
+            .edges
+            .iter()
+            .filter(|((_, to), _)| *to == q)
+            .map(|((from, _), re)| (*from, re.clone()))
+            .collect();
+        let outgoing: Vec<(usize, Rc<Re>)> = self
+            .edges
+            .iter()
+            .filter(|((from, _), _)| *from == q)
+            .map(|((_, to), re)| (*to, re.clone()))
+            .collect();
+        self.edges.retain(|(from, to), _| *from != q && *to != q);
+
+        for (p, in_re) in incoming.iter() {
+            for (r, out_re) in outgoing.iter() {
+                let path = Re::concat(in_re.clone(), Re::concat(loop_re.clone(), out_re.clone()));
+                self.add(*p, path, *r);
+            }
+        }
+    }
+}
+
+// 繰り返し（(a|ε)* = a*）
+fn star(re: Rc<Re>) -> Rc<Re> {
+    match &*re {
+        Re::Or(items) => Re::star(
+            items
+                .iter()
+                .filter(|r| ***r != Re::Epsilon)
+                .fold(Rc::new(Re::Empty), |acc, r| Re::or(acc, r.clone())),
+        ),
+        _ => Re::star(re),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::automaton::dfa::{DFAProgram, DFARulebook};
+    use crate::automaton::farule::{FARule, TransitionType};
+    use crate::automaton::pattern::build::Builder;
+
+    fn char_edge(from: usize, c: char, to: usize) -> (State, Rc<Re>, State) {
+        (State::new(from), Rc::new(Re::Char(c)), State::new(to))
+    }
+
+    #[test]
+    fn test_elimination() {
+        // 0 -a-> 1 -b-> 1
+        let ast = Elimination::to_ast(
+            State::new(0),
+            &[State::new(1)],
+            vec![char_edge(0, 'a', 1), char_edge(1, 'b', 1)],
+        );
+        assert_eq!("ab*", ast.to_string());
+
+        // 0 -a-> 1 -a-> 1 （受理状態は0と1）
+        let ast = Elimination::to_ast(
+            State::new(0),
+            &[State::new(0), State::new(1)],
+            vec![char_edge(0, 'a', 1), char_edge(1, 'a', 1)],
+        );
+        assert_eq!("a*", ast.to_string());
+
+        // 受理状態へ到達できない状態は除く
+        let ast = Elimination::to_ast(
+            State::new(0),
+            &[State::new(1)],
+            vec![
+                char_edge(0, 'a', 1),
+                char_edge(0, 'b', 2),
+                char_edge(2, 'b', 2),
+            ],
+        );
+        assert_eq!("a", ast.to_string());
+    }
+
+    #[test]
+    fn test_elimination_empty() {
+        assert_eq!(
+            "~.*",
+            Elimination::to_ast(State::new(0), &[], vec![]).to_string()
+        );
+        assert_eq!(
+            "~.+",
+            Elimination::to_ast(State::new(0), &[State::new(0)], vec![]).to_string()
+        );
+    }
+
+    #[test]
+    fn test_dfa_to_ast() {
+        // 全文字の遷移は、同じ状態から出る文字指定の遷移以外の文字
+        let dfa = DFAProgram::new(
+            State::new(0),
+            vec![State::new(1)],
+            DFARulebook::new(vec![
+                FARule::new(State::new(0), TransitionType::Character('a'), State::new(2)),
+                FARule::new(State::new(0), TransitionType::Everything, State::new(1)),
+            ]),
+        );
+        assert_eq!(".&~a", dfa.to_ast().to_string());
+    }
+
+    #[test]
+    fn test_nfa_to_ast() {
+        // ε遷移を含むNFAから変換しても同じ文字列を受理する
+        let nfa = Builder::new("a(b|c)*d?").to_nfa();
+        let ast = nfa.to_ast();
+        let dfa = Builder::from_ast(ast).to_nfa();
+        for s in ["a", "abcb", "ad", "acd", "", "d", "abdd"].iter() {
+            assert_eq!(nfa.accept(s), dfa.accept(s), "{}", s);
+        }
+    }
+}
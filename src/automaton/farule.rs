@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use crate::parse::parser::{Assertion, CharKind};
+use crate::unicode::Property;
 use rand::Rng;
 use std::char;
 
@@ -26,11 +28,14 @@ impl State {
 impl Copy for State {}
 
 // 遷移タイプ
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TransitionType {
-    Character(char), // 通常の文字
-    Epsilon,         // イプシロン遷移
-    Everything,      // 全ての文字を遷移
+    Character(char),    // 通常の文字
+    Epsilon,            // イプシロン遷移
+    Everything,         // 全ての文字を遷移
+    Assert(Assertion),  // 前後の文字が条件を満たす場合のみのε遷移（NFAのみ）
+    Property(Property), // Unicodeプロパティの集合に含まれる文字で遷移（NFAのみ）
+    Class(usize), // 文字指定のない文字のうち、同じクラスの文字で遷移（DFAのみ、dfa::Classifier）
 }
 
 // 有限オートマトンルール
@@ -55,6 +60,7 @@ impl FARule {
             Some(c1) => match self.transition {
                 TransitionType::Character(c2) => self.state.id == state.id && c1 == c2,
                 TransitionType::Everything => self.state.id == state.id,
+                TransitionType::Property(p) => self.state.id == state.id && p.contains(c1),
                 _ => false,
             },
             None => match self.transition {
@@ -64,9 +70,27 @@ impl FARule {
         }
     }
 
+    // 前後の文字の種類に応じたε遷移（成り立つ表明を含む）
+    pub fn applies_to_between(&self, state: &State, prev: CharKind, next: CharKind) -> bool {
+        match self.transition {
+            TransitionType::Epsilon => self.state.id == state.id,
+            TransitionType::Assert(a) => self.state.id == state.id && a.is_match(prev, next),
+            _ => false,
+        }
+    }
+
     pub fn follow(&self) -> &State {
         &self.next_state
     }
+
+    // 遷移の向きを逆にしたルール（表明は前後の文字を入れ替える）
+    pub fn reverse(&self) -> FARule {
+        let transition = match self.transition {
+            TransitionType::Assert(a) => TransitionType::Assert(a.reverse()),
+            t => t,
+        };
+        FARule::new(self.next_state, transition, self.state)
+    }
 }
 
 #[cfg(test)]
@@ -87,5 +111,34 @@ mod test {
             assert!(!rule.applies_to(&State::new(2), &Some('a')));
             assert!(rule.applies_to(&State::new(1), &None));
         }
+        {
+            let greek = Property::new("Greek", false).unwrap();
+            let rule = FARule::new(
+                State::new(1),
+                TransitionType::Property(greek),
+                State::new(2),
+            );
+            assert!(rule.applies_to(&State::new(1), &Some('λ')));
+            assert!(!rule.applies_to(&State::new(1), &Some('a')));
+            assert!(!rule.applies_to(&State::new(1), &None));
+        }
+    }
+
+    #[test]
+    fn test_applies_to_between() {
+        let rule = FARule::new(
+            State::new(1),
+            TransitionType::Assert(Assertion::WordBoundary),
+            State::new(2),
+        );
+        assert!(!rule.applies_to(&State::new(1), &None));
+        assert!(rule.applies_to_between(&State::new(1), CharKind::Edge, CharKind::Word));
+        assert!(!rule.applies_to_between(&State::new(1), CharKind::Other, CharKind::Edge));
+        assert!(rule
+            .reverse()
+            .applies_to_between(&State::new(2), CharKind::Word, CharKind::Other));
+
+        let rule = FARule::new(State::new(1), TransitionType::Epsilon, State::new(2));
+        assert!(rule.applies_to_between(&State::new(1), CharKind::Word, CharKind::Word));
     }
 }
@@ -0,0 +1,196 @@
+#![allow(dead_code)]
+
+use crate::automaton::farule::{FARule, State, TransitionType};
+use crate::automaton::nfa::{NFAProgram, NFARulebook};
+use crate::parse::parser::AstTree;
+use std::collections::BTreeSet;
+
+// 初期状態（位置は1から振る）
+const INITIAL: usize = 0;
+
+// 部分式のfirst/last集合
+struct Positions {
+    nullable: bool,
+    first: BTreeSet<usize>,
+    last: BTreeSet<usize>,
+}
+
+// Glushkov構成法（位置オートマトン）
+// 文字を消費するノードに位置を振り、first/last/follow集合からε遷移のないNFAを作成する
+pub struct Glushkov {
+    labels: Vec<TransitionType>, // 各位置の遷移（添字0は初期状態のため未使用）
+    follow: Vec<BTreeSet<usize>>, // 各位置の次に現れうる位置
+}
+
+impl Glushkov {
+    // ASTからNFAを作成
+    pub fn to_nfa(ast: &AstTree) -> NFAProgram {
+        let mut g = Glushkov {
+            labels: vec![TransitionType::Epsilon],
+            follow: vec![BTreeSet::new()],
+        };
+        g.build(ast)
+    }
+
+    fn build(&mut self, ast: &AstTree) -> NFAProgram {
+        let root = self.positions(ast);
+
+        let mut rules = vec![];
+        for &q in root.first.iter() {
+            rules.push(self.rule(INITIAL, q));
+        }
+        for (p, follow) in self.follow.iter().enumerate() {
+            for &q in follow.iter() {
+                rules.push(self.rule(p, q));
+            }
+        }
+
+        let mut accept_states: Vec<State> = root.last.iter().map(|&p| State::new(p)).collect();
+        if root.nullable {
+            accept_states.push(State::new(INITIAL));
+        }
+
+        NFAProgram::new(State::new(INITIAL), accept_states, NFARulebook::new(rules))
+    }
+
+    // 位置pから位置qへの遷移（qの文字で遷移）
+    fn rule(&self, p: usize, q: usize) -> FARule {
+        FARule::new(State::new(p), self.labels[q], State::new(q))
+    }
+
+    // 新しい位置
+    fn position(&mut self, label: TransitionType) -> Positions {
+        self.labels.push(label);
+        self.follow.push(BTreeSet::new());
+        let p = self.labels.len() - 1;
+
+        Positions {
+            nullable: false,
+            first: vec![p].into_iter().collect(),
+            last: vec![p].into_iter().collect(),
+        }
+    }
+
+    // lastの各位置の後にfirstの位置が続く
+    fn connect(&mut self, last: &BTreeSet<usize>, first: &BTreeSet<usize>) {
+        for &p in last.iter() {
+            self.follow[p].extend(first.iter().copied());
+        }
+    }
+
+    fn positions(&mut self, ast: &AstTree) -> Positions {
+        match ast {
+            AstTree::Literal(c) => self.position(TransitionType::Character(*c)),
+            AstTree::Dot => self.position(TransitionType::Everything),
+            AstTree::Property(p) => self.position(TransitionType::Property(*p)),
+            AstTree::String(_) | AstTree::Class(_) => self.positions(&ast.expand()),
+            AstTree::Concat(l, r) => {
+                let l = self.positions(l);
+                let r = self.positions(r);
+                self.connect(&l.last, &r.first);
+
+                let mut first = l.first;
+                if l.nullable {
+                    first.extend(r.first);
+                }
+                let mut last = r.last;
+                if r.nullable {
+                    last.extend(l.last);
+                }
+                Positions {
+                    nullable: l.nullable && r.nullable,
+                    first,
+                    last,
+                }
+            }
+            AstTree::Or(l, r) => {
+                let mut l = self.positions(l);
+                let r = self.positions(r);
+                l.nullable |= r.nullable;
+                l.first.extend(r.first);
+                l.last.extend(r.last);
+                l
+            }
+            AstTree::Repeat(a) => {
+                let a = self.positions(a);
+                self.connect(&a.last, &a.first);
+                Positions {
+                    nullable: true,
+                    ..a
+                }
+            }
+            AstTree::Plus(a) => {
+                let a = self.positions(a);
+                self.connect(&a.last, &a.first);
+                a
+            }
+            AstTree::Question(a) => Positions {
+                nullable: true,
+                ..self.positions(a)
+            },
+            AstTree::Capture(_, _, a) => self.positions(a),
+            AstTree::And(_, _) | AstTree::Not(_) => {
+                panic!(
+                    "[Glushkov::positions] set operators are not supported ({:?})",
+                    ast
+                )
+            }
+            AstTree::Backref(_) => {
+                panic!(
+                    "[Glushkov::positions] backreferences are not supported ({:?})",
+                    ast
+                )
+            }
+            AstTree::Look(_, _) => {
+                panic!(
+                    "[Glushkov::positions] lookaround is not supported ({:?})",
+                    ast
+                )
+            }
+            AstTree::Assert(_) => {
+                panic!(
+                    "[Glushkov::positions] assertions are not supported ({:?})",
+                    ast
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::automaton::pattern::build::Builder;
+    use crate::parse::lexer::Lexer;
+    use crate::parse::parser::Ast;
+
+    fn nfa(pattern: &str) -> NFAProgram {
+        Glushkov::to_nfa(&Ast::new(&Lexer::new(pattern).scan()).parse())
+    }
+
+    #[test]
+    fn test_glushkov_accept() {
+        let n = nfa("a(b|c)*d+");
+        assert!(n.accept("ad"));
+        assert!(n.accept("abcbdd"));
+        assert!(!n.accept("abc"));
+
+        assert!(nfa("(ab)?").accept(""));
+        assert!(nfa("a.c").accept("axc"));
+        assert!(!nfa("a.c").accept("ac"));
+        assert!(nfa("(a*)*b").accept("aab"));
+        assert!(nfa("(a|b)*abb").to_dfa(100).unwrap().accept("babb"));
+    }
+
+    #[test]
+    fn test_glushkov_state_count() {
+        // 状態数は位置数+1で、Thompson構成法より少ない
+        let pattern = "(a|b)*abb";
+        let glushkov = nfa(pattern);
+        let thompson = Builder::new(pattern).to_nfa();
+
+        assert_eq!(6, glushkov.state_count());
+        assert!(glushkov.state_count() < thompson.state_count());
+        assert_eq!(0, glushkov.epsilon_count());
+    }
+}
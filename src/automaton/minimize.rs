@@ -0,0 +1,127 @@
+#![allow(dead_code)]
+
+use crate::automaton::dfa::{DFAProgram, DFARulebook};
+use crate::automaton::farule::{FARule, State, TransitionType};
+use crate::automaton::nfa::NFARulebook;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+// Mooreのアルゴリズム（分割の細分化）によるDFAの最小化
+// 遷移先がない場合は「死んだ状態」(None)として、同じ分割に含めて扱う
+pub fn minimize(dfa: &DFAProgram) -> DFAProgram {
+    // DFAに現れる文字と、それ以外の文字の代表
+    let alphabet: BTreeSet<char> = dfa.alphabet();
+    let symbols: Vec<char> = alphabet
+        .iter()
+        .copied()
+        .chain(std::iter::once(NFARulebook::EVERYTHING_CHAR))
+        .collect();
+    let next = |s: &Option<State>, c: char| s.and_then(|s| dfa.next(s, c));
+
+    // 開始状態から到達できる状態
+    let mut states: Vec<Option<State>> = vec![Some(dfa.start()), None];
+    let mut queue: VecDeque<Option<State>> = states.iter().copied().collect();
+    while let Some(s) = queue.pop_front() {
+        for c in symbols.iter() {
+            let t = next(&s, *c);
+            if !states.contains(&t) {
+                states.push(t);
+                queue.push_back(t);
+            }
+        }
+    }
+
+    // 受理状態かどうかで分割し、遷移先の分割が異なる状態を分けていく
+    let mut block: HashMap<Option<State>, usize> = states
+        .iter()
+        .map(|s| (*s, s.is_some_and(|s| dfa.is_accept(&s)) as usize))
+        .collect();
+    let mut count = 0;
+    loop {
+        let mut ids: HashMap<(usize, Vec<usize>), usize> = HashMap::new();
+        let refined: HashMap<Option<State>, usize> = states
+            .iter()
+            .map(|s| {
+                let signature = (
+                    block[s],
+                    symbols.iter().map(|c| block[&next(s, *c)]).collect(),
+                );
+                let id = ids.len();
+                (*s, *ids.entry(signature).or_insert(id))
+            })
+            .collect();
+        block = refined;
+        if ids.len() == count {
+            break;
+        }
+        count = ids.len();
+    }
+
+    // 各分割の代表の遷移から規則を作成（全文字の遷移と同じ遷移先の文字は省く）
+    let dead = block[&None];
+    let mut rules = vec![];
+    let mut accept_states = BTreeSet::new();
+    let mut visited = BTreeSet::new();
+    for s in states.iter().filter(|s| s.is_some()) {
+        let from = block[s];
+        if from == dead || !visited.insert(from) {
+            continue;
+        }
+        if s.is_some_and(|s| dfa.is_accept(&s)) {
+            accept_states.insert(State::new(from));
+        }
+
+        let other = block[&next(s, NFARulebook::EVERYTHING_CHAR)];
+        if other != dead {
+            rules.push(FARule::new(
+                State::new(from),
+                TransitionType::Everything,
+                State::new(other),
+            ));
+        }
+        for c in alphabet.iter() {
+            let to = block[&next(s, *c)];
+            if to != other {
+                rules.push(FARule::new(
+                    State::new(from),
+                    TransitionType::Character(*c),
+                    State::new(to),
+                ));
+            }
+        }
+    }
+
+    DFAProgram::new(
+        State::new(block[&Some(dfa.start())]),
+        accept_states.into_iter().collect(),
+        DFARulebook::new(rules),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::automaton::pattern::build::Builder;
+
+    #[test]
+    fn test_minimize() {
+        // (a|b)*abbの最小DFAは4状態
+        let dfa = Builder::new("(a|b)*abb").to_nfa().to_dfa(100).unwrap();
+        let min = minimize(&dfa);
+
+        assert_eq!(4, min.state_count());
+        for s in ["abb", "babb", "aabb", "ab", "abba", ""].iter() {
+            assert_eq!(dfa.accept(s), min.accept(s), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_minimize_everything() {
+        // 全文字の遷移と文字指定の遷移が混在する場合
+        let dfa = Builder::new("a.c|abd").to_nfa().to_dfa(100).unwrap();
+        let min = minimize(&dfa);
+
+        for s in ["abc", "axc", "abd", "axd", "ac", "abcd"].iter() {
+            assert_eq!(dfa.accept(s), min.accept(s), "{}", s);
+        }
+    }
+}
@@ -1,4 +1,11 @@
-mod dfa;
-mod farule;
-mod nfa;
+pub mod aho_corasick;
+pub mod dfa;
+pub mod elimination;
+pub mod farule;
+pub mod glushkov;
+pub mod minimize;
+pub mod nfa;
+pub mod overlap;
 pub mod pattern;
+pub mod product;
+pub mod search;
@@ -1,14 +1,20 @@
 #![allow(dead_code)]
 
-use crate::automaton::dfa::{DFADesign, DFARulebook};
+use crate::automaton::dfa::{Boundary, Classifier, DFADesign, DFAProgram, DFARulebook, PatternIds};
+use crate::automaton::elimination::Elimination;
 use crate::automaton::farule::{FARule, State, TransitionType};
+use crate::derivative::Re;
+use crate::parse::parser::{AstTree, CharKind};
+use crate::unicode::Property;
 use std::char;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
 use std::vec::Vec;
 
 #[derive(Debug)]
 pub struct NFARulebook {
     rules: Vec<FARule>,
+    index: HashMap<State, Vec<usize>>, // 遷移元の状態ごとのルールの位置
 }
 
 impl NFARulebook {
@@ -16,7 +22,20 @@ impl NFARulebook {
     pub const EVERYTHING_CHAR: char = '∀';
 
     pub fn new(rules: Vec<FARule>) -> Self {
-        NFARulebook { rules }
+        let mut index: HashMap<State, Vec<usize>> = HashMap::new();
+        for (i, r) in rules.iter().enumerate() {
+            index.entry(r.state).or_default().push(i);
+        }
+        NFARulebook { rules, index }
+    }
+
+    // 状態sから遷移するルール
+    fn rules_from<'b>(&'b self, s: &State) -> impl Iterator<Item = &'b FARule> + 'b {
+        self.index
+            .get(s)
+            .into_iter()
+            .flatten()
+            .map(move |i| &self.rules[*i])
     }
 
     // ルールに適用されている入力文字の配列を取得
@@ -37,19 +56,114 @@ impl NFARulebook {
             .collect()
     }
 
+    // 状態除去法により、開始状態から受理状態までの文字列にマッチするASTを求める
+    pub fn to_ast(&self, start_state: State, accept_states: &[State]) -> AstTree {
+        let edges = self
+            .rules
+            .iter()
+            .map(|r| {
+                let re = match r.transition {
+                    TransitionType::Character(c) => Re::Char(c),
+                    TransitionType::Everything => Re::Any,
+                    TransitionType::Epsilon => Re::Epsilon,
+                    TransitionType::Assert(_)
+                    | TransitionType::Class(_)
+                    | TransitionType::Property(_) => {
+                        panic!(
+                            "[NFARulebook::to_ast] assertions and properties are not supported ({:?})",
+                            r
+                        )
+                    }
+                };
+                (r.state, Rc::new(re), r.next_state)
+            })
+            .collect();
+        Elimination::to_ast(start_state, accept_states, edges)
+    }
+
     // 現在の状態から次の状態の集合を取得
     pub fn next_state(&self, states: &HashSet<State>, c: Option<char>) -> HashSet<State> {
         states
             .iter()
-            .map(|s| self.rule_for(s, c))
-            .flatten()
+            .flat_map(|s| self.rule_for(s, c))
             .collect::<HashSet<State>>()
     }
 
-    // ルールを適用し、次の状態を返す
-    fn rule_for(&self, s: &State, c: Option<char>) -> Vec<State> {
+    // 表明のルールを含むか
+    pub fn has_assertion(&self) -> bool {
         self.rules
             .iter()
+            .any(|r| matches!(r.transition, TransitionType::Assert(_)))
+    }
+
+    // ルールに現れるUnicodeプロパティ（重複なし）
+    pub fn properties(&self) -> Vec<Property> {
+        let mut properties: Vec<Property> = vec![];
+        for r in self.rules.iter() {
+            if let TransitionType::Property(p) = r.transition {
+                if !properties.contains(&p) {
+                    properties.push(p);
+                }
+            }
+        }
+        properties
+    }
+
+    // ε遷移を繰り返した状態の集合
+    pub fn closure(&self, states: &HashSet<State>) -> HashSet<State> {
+        self.closure_by(states, |r, s| r.applies_to(s, &None))
+    }
+
+    // 直前・直後の文字の種類で成り立つ表明を含めて、ε遷移を繰り返した状態の集合
+    pub fn closure_between(
+        &self,
+        states: &HashSet<State>,
+        prev: CharKind,
+        next: CharKind,
+    ) -> HashSet<State> {
+        self.closure_by(states, |r, s| r.applies_to_between(s, prev, next))
+    }
+
+    // appliesを満たすルールで、新しく到達した状態から遷移を繰り返す
+    fn closure_by<F>(&self, states: &HashSet<State>, applies: F) -> HashSet<State>
+    where
+        F: Fn(&FARule, &State) -> bool,
+    {
+        let mut closure = states.clone();
+        let mut stack: Vec<State> = states.iter().copied().collect();
+        while let Some(s) = stack.pop() {
+            for r in self.rules_from(&s).filter(|r| applies(r, &s)) {
+                if closure.insert(*r.follow()) {
+                    stack.push(*r.follow());
+                }
+            }
+        }
+        closure
+    }
+
+    // 文字指定のない、classifierでクラスclassに分類される文字に対する次の状態の集合
+    // （全文字の遷移と、クラスの文字を含むプロパティの遷移が適用される）
+    fn next_state_class(
+        &self,
+        states: &HashSet<State>,
+        class: usize,
+        classifier: &Classifier,
+    ) -> HashSet<State> {
+        states
+            .iter()
+            .flat_map(|s| self.rules_from(s))
+            .filter(|r| match r.transition {
+                TransitionType::Everything => true,
+                TransitionType::Property(p) => classifier.is_member(class, &p),
+                _ => false,
+            })
+            .map(|r| *r.follow())
+            .collect()
+    }
+
+    // ルールを適用し、次の状態を返す
+    fn rule_for(&self, s: &State, c: Option<char>) -> Vec<State> {
+        self.rules_from(s)
             .filter(|r| r.applies_to(s, &c))
             .map(|r| *r.follow())
             .collect::<Vec<State>>()
@@ -86,25 +200,24 @@ impl<'a> Nfa<'a> {
     }
 
     pub fn read_string(&mut self, s: &str) {
+        // 表明の判定のため、直前の文字の種類を保持
+        let mut prev = CharKind::Edge;
         s.chars().for_each(|c| {
             // ε遷移を行ってから通常遷移
-            self.trans_epsilon();
+            let next = CharKind::of(Some(c));
+            self.trans_epsilon(prev, next);
             self.current_state = self.rulebook.next_state(&self.current_state, Some(c));
+            prev = next;
         });
 
         // 読み込み完了後、ε遷移
-        self.trans_epsilon();
+        self.trans_epsilon(prev, CharKind::Edge);
     }
 
-    fn trans_epsilon(&mut self) {
-        // ε遷移の結果がサブセットにならなくなるまで遷移
-        let epsilon = self.rulebook.next_state(&self.current_state, None);
-        if epsilon.is_subset(&self.current_state) {
-            return;
-        }
-
-        self.current_state.extend(epsilon);
-        self.trans_epsilon();
+    fn trans_epsilon(&mut self, prev: CharKind, next: CharKind) {
+        self.current_state = self
+            .rulebook
+            .closure_between(&self.current_state, prev, next);
     }
 }
 
@@ -134,6 +247,228 @@ impl<'a> NFADesign<'a> {
     }
 }
 
+// 所有権を持つNFA（パターンから生成したルールを保持）
+#[derive(Debug)]
+pub struct NFAProgram {
+    start_state: State,
+    accept_states: Vec<State>,
+    rulebook: NFARulebook,
+    patterns: Vec<usize>, // 各受理状態のパターンの番号（複数パターンの和の場合のみ）
+}
+
+impl NFAProgram {
+    pub fn new(start_state: State, accept_states: Vec<State>, rulebook: NFARulebook) -> Self {
+        NFAProgram {
+            start_state,
+            accept_states,
+            rulebook,
+            patterns: vec![],
+        }
+    }
+
+    // 複数パターンの和（i番目のNFAの受理状態をパターンiの受理状態とする）
+    // 変換したDFAでは、各受理状態で受理するパターンの番号を求められる
+    pub fn union(programs: Vec<NFAProgram>) -> NFAProgram {
+        let start = State::create_at_rnd();
+        let mut rules = vec![];
+        let mut accept_states = vec![];
+        let mut patterns = vec![];
+        for (id, program) in programs.into_iter().enumerate() {
+            rules.push(FARule::new(
+                start,
+                TransitionType::Epsilon,
+                program.start_state,
+            ));
+            rules.extend(program.rulebook.rules);
+            patterns.extend(program.accept_states.iter().map(|_| id));
+            accept_states.extend(program.accept_states);
+        }
+
+        NFAProgram {
+            start_state: start,
+            accept_states,
+            rulebook: NFARulebook::new(rules),
+            patterns,
+        }
+    }
+
+    // ルール数
+    pub fn size(&self) -> usize {
+        self.rulebook.rules.len()
+    }
+
+    // 状態数
+    pub fn state_count(&self) -> usize {
+        let mut states: HashSet<State> = self.accept_states.iter().copied().collect();
+        states.insert(self.start_state);
+        for r in self.rulebook.rules.iter() {
+            states.insert(r.state);
+            states.insert(r.next_state);
+        }
+        states.len()
+    }
+
+    // ε遷移のルール数
+    pub fn epsilon_count(&self) -> usize {
+        self.rulebook
+            .rules
+            .iter()
+            .filter(|r| r.transition == TransitionType::Epsilon)
+            .count()
+    }
+
+    pub fn accept(&self, s: &str) -> bool {
+        NFADesign::new(self.start_state, &self.accept_states, &self.rulebook).accept(s)
+    }
+
+    // 全ての遷移を逆向きにしたNFA（逆順の文字列を受理する）
+    // 新しい開始状態から元の受理状態へε遷移し、元の開始状態を受理状態とする
+    pub fn reverse(&self) -> NFAProgram {
+        let start = State::create_at_rnd();
+        let mut rules: Vec<FARule> = self.rulebook.rules.iter().map(|r| r.reverse()).collect();
+        rules.extend(
+            self.accept_states
+                .iter()
+                .map(|a| FARule::new(start, TransitionType::Epsilon, *a)),
+        );
+
+        NFAProgram::new(start, vec![self.start_state], NFARulebook::new(rules))
+    }
+
+    // 受理する文字列にマッチするAST
+    pub fn to_ast(&self) -> AstTree {
+        self.rulebook.to_ast(self.start_state, &self.accept_states)
+    }
+
+    // 部分集合構成法によりDFAへ変換（状態数が上限を超えた場合はNone）
+    pub fn to_dfa(&self, max_states: usize) -> Option<DFAProgram> {
+        if self.rulebook.has_assertion() || !self.rulebook.properties().is_empty() {
+            return self.to_class_dfa(max_states);
+        }
+        let mut converter =
+            NFAConverter::new(self.start_state, &self.accept_states, &self.rulebook);
+        converter.build_limited(max_states).ok()?;
+        let patterns = converter.to_dfa_patterns(&self.patterns);
+        converter
+            .into_program()
+            .map(|dfa| dfa.with_patterns(patterns))
+    }
+
+    // 状態の集合に含まれる受理状態のパターンの番号（昇順、重複なし）
+    fn patterns_in(&self, set: &HashSet<State>) -> Vec<usize> {
+        let mut ids: Vec<usize> = self
+            .accept_states
+            .iter()
+            .zip(self.patterns.iter())
+            .filter(|(a, _)| set.contains(a))
+            .map(|(_, id)| *id)
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    // 表明・Unicodeプロパティを含むNFAの部分集合構成法
+    // 表明は直後の文字が決まるまで判定できないため、DFAの状態はε遷移前のNFA状態の集合と
+    // 直前の文字の種類の組とし、文字を読む際に前後の文字の種類でε遷移してから遷移する
+    // 開始状態は直前の文字の種類ごと、受理状態は直後の文字の種類ごとに求める
+    // 文字指定のない文字は、プロパティへの所属（と単語構成文字か）によるクラスごとに遷移する
+    fn to_class_dfa(&self, max_states: usize) -> Option<DFAProgram> {
+        type Key = (Vec<State>, CharKind);
+        fn intern(key: Key, ids: &mut HashMap<Key, State>, queue: &mut Vec<Key>) -> State {
+            *ids.entry(key.clone()).or_insert_with(|| {
+                queue.push(key);
+                State::create_at_rnd()
+            })
+        }
+        let key = |set: HashSet<State>, kind: CharKind| -> Key {
+            let mut states: Vec<State> = set.into_iter().collect();
+            states.sort();
+            (states, kind)
+        };
+
+        let assertion = self.rulebook.has_assertion();
+        let classifier = Classifier::new(self.rulebook.properties(), assertion);
+        let classes = classifier.classes();
+        // 表明を含まない場合は文字の種類を区別しない
+        let kinds: &[CharKind] = if assertion {
+            &CharKind::ALL
+        } else {
+            &[CharKind::Edge]
+        };
+        let kind_of = |kind: CharKind| if assertion { kind } else { CharKind::Edge };
+
+        let chars: Vec<char> = self
+            .rulebook
+            .alphabet()
+            .into_iter()
+            .filter(|c| *c != NFARulebook::EVERYTHING_CHAR)
+            .collect();
+        let mut ids: HashMap<Key, State> = HashMap::new();
+        let mut queue: Vec<Key> = vec![];
+        let mut starts = HashMap::new();
+        for kind in kinds.iter() {
+            let start = key(vec![self.start_state].into_iter().collect(), *kind);
+            starts.insert(*kind, intern(start, &mut ids, &mut queue));
+        }
+
+        let mut rules = vec![];
+        let mut accepts: HashMap<CharKind, Vec<State>> = HashMap::new();
+        let mut patterns: PatternIds = HashMap::new();
+        let mut searched = 0;
+        while searched < queue.len() {
+            if searched >= max_states {
+                return None;
+            }
+            let (set, prev) = queue[searched].clone();
+            let state = ids[&queue[searched]];
+            searched += 1;
+
+            let set: HashSet<State> = set.into_iter().collect();
+            let closures: HashMap<CharKind, HashSet<State>> = kinds
+                .iter()
+                .map(|next| (*next, self.rulebook.closure_between(&set, prev, *next)))
+                .collect();
+            for (next, closure) in closures.iter() {
+                if self.accept_states.iter().any(|a| closure.contains(a)) {
+                    accepts.entry(*next).or_default().push(state);
+                }
+                let ids = self.patterns_in(closure);
+                if !ids.is_empty() {
+                    patterns.insert((state, *next), ids);
+                }
+            }
+
+            for c in chars.iter() {
+                let kind = kind_of(CharKind::of(Some(*c)));
+                let next_set = self.rulebook.next_state(&closures[&kind], Some(*c));
+                let next = intern(key(next_set, kind), &mut ids, &mut queue);
+                rules.push(FARule::new(state, TransitionType::Character(*c), next));
+            }
+            for class in classes.iter() {
+                let kind = kind_of(classifier.kind(*class));
+                let next_set =
+                    self.rulebook
+                        .next_state_class(&closures[&kind], *class, &classifier);
+                let next = intern(key(next_set, kind), &mut ids, &mut queue);
+                rules.push(FARule::new(state, TransitionType::Class(*class), next));
+            }
+        }
+
+        let rulebook = DFARulebook::with_classifier(rules, classifier);
+        if assertion {
+            let dfa = DFAProgram::with_boundary(rulebook, Boundary::new(starts, accepts));
+            return Some(dfa.with_patterns(patterns));
+        }
+        let dfa = DFAProgram::new(
+            starts[&CharKind::Edge],
+            accepts.remove(&CharKind::Edge).unwrap_or_default(),
+            rulebook,
+        );
+        Some(dfa.with_patterns(patterns))
+    }
+}
+
 #[derive(Debug)]
 struct StateMap {
     start: HashSet<State>,
@@ -214,7 +549,6 @@ pub struct NFAConverter<'a> {
     dfa_rulebook: Option<DFARulebook>,
 }
 
-type DtranRule = (HashSet<State>, char, HashSet<State>);
 impl<'a> NFAConverter<'a> {
     pub fn new(start: State, accept_states: &'a [State], rulebook: &'a NFARulebook) -> Self {
         NFAConverter {
@@ -228,12 +562,25 @@ impl<'a> NFAConverter<'a> {
 
     // DFAルールの作成
     pub fn build(&mut self) -> &Self {
-        // DFAルール作成
+        self.build_limited(usize::MAX)
+            .unwrap_or_else(|_| panic!("[NFAConverter::build] too many states"))
+    }
+
+    // 状態数の上限を指定してDFAルールを作成（超過時は探索済みの状態数を返す）
+    pub fn build_limited(&mut self, max_states: usize) -> Result<&Self, usize> {
         let mut st_set = HashSet::new();
         st_set.insert(self.start_state);
-        self.dfa_rulebook = Some(self.dfa_rulebook(&st_set));
+        self.dfa_rulebook = Some(self.dfa_rulebook(&st_set, max_states)?);
+
+        Ok(self)
+    }
 
-        self
+    // 変換結果のDFAを取り出す
+    pub fn into_program(self) -> Option<DFAProgram> {
+        let start = self.state_map.get_start();
+        let accept = self.to_dfa_accept();
+        self.dfa_rulebook
+            .map(|rulebook| DFAProgram::new(start, accept, rulebook))
     }
 
     // NFA→DFA変換後の受理状態を返す
@@ -251,17 +598,26 @@ impl<'a> NFAConverter<'a> {
     }
 
     // DFAルール作成
-    fn dfa_rulebook(&mut self, start: &HashSet<State>) -> DFARulebook {
-        let mut dtran = vec![];
+    fn dfa_rulebook(
+        &mut self,
+        start: &HashSet<State>,
+        max_states: usize,
+    ) -> Result<DFARulebook, usize> {
+        let mut rules = vec![];
 
         // ε遷移を行い、各入力文字に対する遷移を行う
         let mut queue = VecDeque::new();
-        let mut searched_set = vec![];
+        let mut searched = 0;
         let ep_start = self.epsilon(start);
+        self.state_map.insert_map(&ep_start);
         queue.push_back(ep_start.clone());
 
         // イプシロン遷移後の状態を開始状態として登録
         self.save_start_state(&ep_start);
+        let alphabet = self.rulebook.alphabet();
+        // ε遷移前の集合（整列したキー）から遷移先の状態への対応
+        // 文字クラスでは多くの文字が同じ集合へ遷移するため、ε遷移の計算を省く
+        let mut moved: HashMap<Vec<State>, State> = HashMap::new();
 
         while !queue.is_empty() {
             // 探索済み配列へ追加
@@ -271,58 +627,40 @@ impl<'a> NFAConverter<'a> {
                     queue
                 )
             });
-            searched_set.push(set.clone());
+            searched += 1;
+            if searched > max_states {
+                return Err(searched);
+            }
+            let state = self.state_map.get_state(&set);
 
             // 各文字から遷移する集合を取得
-            self.rulebook.alphabet().into_iter().for_each(|c| {
+            for c in alphabet.iter().copied() {
                 let next_set = self.rulebook.next_state(&set, Some(c));
-                let ep_next_set = self.epsilon(&next_set);
-
-                // 遷移後の状態を探索していなければ、探索配列へ登録
-                dtran.push((set.clone(), c, ep_next_set.clone()));
-                if !searched_set.contains(&ep_next_set) {
-                    queue.push_back(ep_next_set);
-                }
-            })
+                let key = self.state_map.key(&next_set);
+                let next_state = match moved.get(&key) {
+                    Some(next_state) => *next_state,
+                    None => {
+                        // 遷移後の状態を探索していなければ、探索配列へ登録
+                        let ep_next_set = self.epsilon(&next_set);
+                        if !self.state_map.is_inserted(&ep_next_set) {
+                            self.state_map.insert_map(&ep_next_set);
+                            queue.push_back(ep_next_set.clone());
+                        }
+                        let next_state = self.state_map.get_state(&ep_next_set);
+                        moved.insert(key, next_state);
+                        next_state
+                    }
+                };
+                rules.push(Self::build_farule(c, state, next_state));
+            }
         }
 
-        DFARulebook::new(self.farule(dtran))
-    }
-
-    // 部分集合構成法により抽出したルールをFARuleへ
-    fn farule(&mut self, dtran: Vec<DtranRule>) -> Vec<FARule> {
-        dtran
-            .into_iter()
-            .map(|d| {
-                // 各状態の集合に対応する状態を作成しながら、ルールを作成
-                let (st_state, character, next_state) = d;
-
-                // 集合に対する状態が登録されていなければ、登録
-                if !self.state_map.is_inserted(&st_state) {
-                    self.state_map.insert_map(&st_state);
-                }
-                if !self.state_map.is_inserted(&next_state) {
-                    self.state_map.insert_map(&next_state);
-                }
-
-                // FARuleを作成
-                self.build_farule(character, &st_state, &next_state)
-            })
-            .collect()
+        Ok(DFARulebook::new(rules))
     }
 
     // ε遷移
     fn epsilon(&self, start: &HashSet<State>) -> HashSet<State> {
-        let mut s = start.clone();
-        let ep = self.rulebook.next_state(&s, None);
-        if ep.is_subset(&s) {
-            // ε遷移の集合を返す
-            return s;
-        }
-
-        // 遷移後の集合をマージ
-        s.extend(ep);
-        self.epsilon(&s)
+        self.rulebook.closure(start)
     }
 
     // 探索済み判定
@@ -347,33 +685,34 @@ impl<'a> NFAConverter<'a> {
     }
 
     // DFA受理状態を取得
+    // NFAの受理状態ごとのパターンの番号から、DFAの受理状態ごとのパターンの番号を求める
+    pub fn to_dfa_patterns(&self, patterns: &[usize]) -> PatternIds {
+        let mut ids: PatternIds = HashMap::new();
+        for (a, id) in self.accept_states.iter().zip(patterns.iter()) {
+            for s in self.state_map.get_include_state(a) {
+                let entry = ids.entry((s, CharKind::Edge)).or_default();
+                if !entry.contains(id) {
+                    entry.push(*id);
+                }
+            }
+        }
+        ids.values_mut().for_each(|v| v.sort_unstable());
+        ids
+    }
+
     fn to_dfa_accept(&self) -> Vec<State> {
         self.accept_states
             .iter()
-            .map(|s| self.state_map.get_include_state(s))
-            .flatten()
+            .flat_map(|s| self.state_map.get_include_state(s))
             .collect()
     }
 
     // FARule作成
-    fn build_farule(
-        &self,
-        c: char,
-        st_state: &HashSet<State>,
-        next_state: &HashSet<State>,
-    ) -> FARule {
+    fn build_farule(c: char, state: State, next_state: State) -> FARule {
         if c == NFARulebook::EVERYTHING_CHAR {
-            FARule::new(
-                self.state_map.get_state(st_state),
-                TransitionType::Everything,
-                self.state_map.get_state(next_state),
-            )
+            FARule::new(state, TransitionType::Everything, next_state)
         } else {
-            FARule::new(
-                self.state_map.get_state(st_state),
-                TransitionType::Character(c),
-                self.state_map.get_state(next_state),
-            )
+            FARule::new(state, TransitionType::Character(c), next_state)
         }
     }
 }
@@ -382,6 +721,7 @@ impl<'a> NFAConverter<'a> {
 mod test {
     use super::*;
     use crate::automaton::farule::TransitionType;
+    use crate::parse::parser::Assertion;
 
     #[test]
     fn test_nfarulebook() {
@@ -478,6 +818,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison, clippy::useless_vec)]
     fn test_nfa_accepting() {
         let book = NFARulebook::new(vec![
             FARule::new(State::new(1), TransitionType::Character('a'), State::new(1)),
@@ -699,4 +1040,36 @@ mod test {
             assert!(dfa.accept("aaaaaa"));
         }
     }
+
+    #[test]
+    fn test_nfa_program_boundary() {
+        // \ba\b
+        let boundary = TransitionType::Assert(Assertion::WordBoundary);
+        let nfa = NFAProgram::new(
+            State::new(1),
+            vec![State::new(4)],
+            NFARulebook::new(vec![
+                FARule::new(State::new(1), boundary, State::new(2)),
+                FARule::new(State::new(2), TransitionType::Character('a'), State::new(3)),
+                FARule::new(State::new(3), boundary, State::new(4)),
+            ]),
+        );
+
+        assert!(nfa.accept("a"));
+        assert!(!nfa.accept("aa"));
+        assert!(!nfa.accept(""));
+
+        let dfa = nfa.to_dfa(100).unwrap();
+        assert!(dfa.accept("a"));
+        assert!(!dfa.accept("aa"));
+        assert!(!dfa.accept(""));
+        assert!(nfa.to_dfa(2).is_none());
+
+        // 途中の位置では前後の文字で受理を判定する
+        let state = dfa.next(dfa.start_after(Some(' ')), 'a').unwrap();
+        assert!(dfa.is_accept_before(&state, Some('-')));
+        assert!(!dfa.is_accept_before(&state, Some('b')));
+        let state = dfa.next(dfa.start_after(Some('b')), 'a').unwrap();
+        assert!(!dfa.is_accept_before(&state, None));
+    }
 }
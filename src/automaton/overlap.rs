@@ -0,0 +1,239 @@
+#![allow(dead_code)]
+
+use crate::automaton::dfa::DFAProgram;
+use crate::automaton::farule::State;
+use crate::automaton::nfa::NFAProgram;
+use std::collections::{HashSet, VecDeque};
+
+// 重なりを含む一致箇所
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct OverlapMatch {
+    pub pattern: usize, // 一致したパターンの番号
+    pub start: usize,   // 開始位置（バイト位置）
+    pub end: usize,     // 終了位置（バイト位置）
+}
+
+// 重なりを含む全ての一致範囲を求めるDFAの組
+// 全パターンの和（.*p0|.*p1|..）のDFAで入力を一度走査して各位置で終わるパターンを求め、
+// 終わるパターンごとに、そのパターンを反転したDFAで終了位置から逆に走査して開始位置を求める
+#[derive(Debug)]
+pub struct OverlapSearcher {
+    forward: DFAProgram,
+    reverse: Vec<DFAProgram>,
+    live: Vec<HashSet<State>>, // 反転したDFAごとの、受理状態へ到達できる状態
+}
+
+impl OverlapSearcher {
+    // patternsは各パターンのNFA、leadingは各パターンの前に任意の文字列が続くNFA
+    // いずれかのDFAの状態数が上限を超えた場合はNone
+    pub fn new(
+        patterns: &[NFAProgram],
+        leading: Vec<NFAProgram>,
+        max_states: usize,
+    ) -> Option<Self> {
+        let forward = NFAProgram::union(leading).to_dfa(max_states)?;
+        let reverse = patterns
+            .iter()
+            .map(|p| p.reverse().to_dfa(max_states))
+            .collect::<Option<Vec<DFAProgram>>>()?;
+        let live = reverse.iter().map(|r| r.live_states()).collect();
+
+        Some(OverlapSearcher {
+            forward,
+            reverse,
+            live,
+        })
+    }
+
+    // 全ての一致箇所（終了位置、パターンの番号、開始位置の順）
+    pub fn find_iter<'s, 'h>(&'s self, haystack: &'h str) -> Overlapping<'s, 'h> {
+        Overlapping {
+            searcher: self,
+            haystack,
+            state: Some(self.forward.start_after(None)),
+            position: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    // いずれかのパターンが終わる位置と、そこで終わるパターンの番号（位置の順）
+    pub fn ends(&self, haystack: &str) -> Vec<(usize, Vec<usize>)> {
+        let mut ends = vec![];
+        let mut state = self.forward.start_after(None);
+        for (i, c) in haystack.char_indices() {
+            let ids = self.forward.patterns_before(&state, Some(c));
+            if !ids.is_empty() {
+                ends.push((i, ids));
+            }
+            match self.forward.next(state, c) {
+                Some(next) => state = next,
+                None => return ends,
+            }
+        }
+        let ids = self.forward.patterns_before(&state, None);
+        if !ids.is_empty() {
+            ends.push((haystack.len(), ids));
+        }
+        ends
+    }
+
+    // パターンidが位置endで終わる一致の開始位置（昇順）
+    fn starts(&self, id: usize, haystack: &str, end: usize) -> Vec<usize> {
+        let (reverse, live) = (&self.reverse[id], &self.live[id]);
+        let before = |i: usize| haystack[..i].chars().next_back();
+        // 逆向きでは直後の文字が直前となる
+        let mut state = reverse.start_after(haystack[end..].chars().next());
+        let mut starts = vec![];
+        if reverse.is_accept_before(&state, before(end)) {
+            starts.push(end);
+        }
+
+        for (i, c) in haystack[..end].char_indices().rev() {
+            match reverse.next(state, c) {
+                Some(next) if live.contains(&next) => state = next,
+                _ => break,
+            }
+            if reverse.is_accept_before(&state, before(i)) {
+                starts.push(i);
+            }
+        }
+        starts.reverse();
+        starts
+    }
+}
+
+pub struct Overlapping<'s, 'h> {
+    searcher: &'s OverlapSearcher,
+    haystack: &'h str,
+    state: Option<State>, // 位置positionまでを読んだ状態（末尾まで走査した場合はNone）
+    position: usize,
+    pending: VecDeque<OverlapMatch>,
+}
+
+impl<'s, 'h> Iterator for Overlapping<'s, 'h> {
+    type Item = OverlapMatch;
+
+    fn next(&mut self) -> Option<OverlapMatch> {
+        while self.pending.is_empty() {
+            let state = self.state?;
+            let end = self.position;
+            let next = self.haystack[end..].chars().next();
+            let ids = self.searcher.forward.patterns_before(&state, next);
+
+            // 次の位置へ進める
+            self.state = next.and_then(|c| self.searcher.forward.next(state, c));
+            self.position += next.map_or(0, char::len_utf8);
+
+            for id in ids {
+                for start in self.searcher.starts(id, self.haystack, end) {
+                    self.pending.push_back(OverlapMatch {
+                        pattern: id,
+                        start,
+                        end,
+                    });
+                }
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::automaton::pattern::build::Builder;
+    use crate::parse::lexer::Lexer;
+    use crate::parse::parser::Ast;
+
+    fn searcher(patterns: &[&str]) -> OverlapSearcher {
+        let asts: Vec<_> = patterns
+            .iter()
+            .map(|p| Ast::new(&Lexer::new(p).scan()).parse())
+            .collect();
+        let nfas: Vec<NFAProgram> = asts
+            .iter()
+            .map(|a| Builder::from_ast(a.clone()).to_nfa())
+            .collect();
+        let leading = asts
+            .into_iter()
+            .map(|a| Builder::from_ast(a.leading_any()).to_nfa())
+            .collect();
+        OverlapSearcher::new(&nfas, leading, 100).unwrap()
+    }
+
+    fn spans(searcher: &OverlapSearcher, haystack: &str) -> Vec<(usize, usize, usize)> {
+        searcher
+            .find_iter(haystack)
+            .map(|m| (m.pattern, m.start, m.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_overlap_find_iter() {
+        let s = searcher(&["aa"]);
+        assert_eq!(vec![(0, 0, 2), (0, 1, 3), (0, 2, 4)], spans(&s, "aaaa"));
+        assert_eq!(Vec::<(usize, usize, usize)>::new(), spans(&s, "abab"));
+
+        // 同じ終了位置では開始位置の順
+        let s = searcher(&["a+"]);
+        assert_eq!(
+            vec![(0, 0, 1), (0, 0, 2), (0, 1, 2), (0, 3, 4)],
+            spans(&s, "aaba")
+        );
+
+        // 空文字列へのマッチは各位置で報告する
+        let s = searcher(&["b*"]);
+        assert_eq!(vec![(0, 0, 0), (0, 0, 1), (0, 1, 1)], spans(&s, "b"));
+        assert_eq!(
+            vec![
+                (0, 0, 0),
+                (0, 0, 2),
+                (0, 2, 2),
+                (0, 0, 4),
+                (0, 2, 4),
+                (0, 4, 4)
+            ],
+            spans(&searcher(&["é*"]), "éé")
+        );
+    }
+
+    #[test]
+    fn test_overlap_patterns() {
+        let s = searcher(&["he", "she", "his", "hers"]);
+        assert_eq!(vec![(0, 2, 4), (1, 1, 4), (3, 2, 6)], spans(&s, "ushers"));
+        assert_eq!(vec![(4, vec![0, 1]), (6, vec![3])], s.ends("ushers"));
+
+        let s = searcher(&["ab+", "b+c", "[abc]"]);
+        assert_eq!(
+            vec![
+                (1, vec![2]),
+                (2, vec![0, 2]),
+                (3, vec![0, 2]),
+                (4, vec![1, 2])
+            ],
+            s.ends("abbc")
+        );
+        assert_eq!(
+            vec![
+                (2, 0, 1),
+                (0, 0, 2),
+                (2, 1, 2),
+                (0, 0, 3),
+                (2, 2, 3),
+                (1, 1, 4),
+                (1, 2, 4),
+                (2, 3, 4)
+            ],
+            spans(&s, "abbc")
+        );
+    }
+
+    #[test]
+    fn test_overlap_assertion() {
+        let s = searcher(&[r"\bab", r"b\b"]);
+        assert_eq!(
+            vec![(0, 0, 2), (1, 1, 2), (0, 3, 5), (1, 4, 5), (1, 8, 9)],
+            spans(&s, "ab ab cab")
+        );
+    }
+}
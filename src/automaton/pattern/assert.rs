@@ -0,0 +1,81 @@
+#![allow(dead_code)]
+
+use crate::automaton::farule::{FARule, State, TransitionType};
+use crate::automaton::nfa::{NFADesign, NFARulebook};
+use crate::automaton::pattern::base::BasePattern;
+use crate::parse::parser::Assertion;
+
+// 前後の文字が条件を満たす場合のみ行うε遷移
+#[derive(Debug)]
+pub struct Assert {
+    assertion: Assertion,
+    start_state: State,
+    accept_state: State,
+}
+
+impl Assert {
+    pub fn new(assertion: Assertion) -> Self {
+        Assert {
+            assertion,
+            start_state: State::create_at_rnd(),
+            accept_state: State::create_at_rnd(),
+        }
+    }
+}
+
+impl BasePattern for Assert {
+    fn is_match(&self, s: &str) -> bool {
+        let rules = self.rules();
+        NFADesign::new(
+            self.start_state,
+            &self.accept_state(),
+            &NFARulebook::new(rules),
+        )
+        .accept(s)
+    }
+
+    fn rules(&self) -> Vec<FARule> {
+        vec![FARule::new(
+            self.start_state,
+            TransitionType::Assert(self.assertion),
+            self.accept_state,
+        )]
+    }
+
+    fn accept_state(&self) -> Vec<State> {
+        vec![self.accept_state]
+    }
+
+    fn start_state(&self) -> State {
+        self.start_state
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::automaton::pattern::{concat::Concat, dot::Dot, literal::Literal};
+    use std::boxed::Box;
+
+    #[test]
+    fn test_assert() {
+        {
+            assert!(Assert::new(Assertion::StartText).is_match(""));
+            assert!(!Assert::new(Assertion::WordBoundary).is_match(""));
+            assert!(Assert::new(Assertion::NotWordBoundary).is_match(""));
+        }
+        {
+            // a\b.
+            let c = Concat::new(
+                Box::new(Literal::new('a')),
+                Box::new(Concat::new(
+                    Box::new(Assert::new(Assertion::WordBoundary)),
+                    Box::new(Dot::new()),
+                )),
+            );
+
+            assert!(c.is_match("a "));
+            assert!(!c.is_match("ab"));
+        }
+    }
+}
@@ -1,9 +1,10 @@
 #![allow(dead_code)]
 
+use crate::automaton::nfa::{NFAProgram, NFARulebook};
 use crate::automaton::pattern::base::BasePattern;
 use crate::automaton::pattern::{
-    concat::Concat, dot::Dot, literal::Literal, or::Or, plus::Plus, question::Question,
-    repeat::Repeat,
+    assert::Assert, concat::Concat, dot::Dot, literal::Literal, or::Or, plus::Plus,
+    property::Property, question::Question, repeat::Repeat,
 };
 use crate::parse::lexer::Lexer;
 use crate::parse::parser::{Ast, AstTree};
@@ -14,9 +15,12 @@ pub struct Builder {
 
 impl Builder {
     pub fn new(pattern: &str) -> Self {
-        Builder {
-            ast: Ast::new(&Lexer::new(pattern).scan()).parse(),
-        }
+        Self::from_ast(Ast::new(&Lexer::new(pattern).scan()).parse())
+    }
+
+    // パース済みのASTから作成
+    pub fn from_ast(ast: AstTree) -> Self {
+        Builder { ast }
     }
 
     // DFAインスタンスを返す
@@ -24,6 +28,16 @@ impl Builder {
         self.to_pattern(&self.ast)
     }
 
+    // ルールを展開済みのNFAを返す
+    pub fn to_nfa(&self) -> NFAProgram {
+        let pattern = self.to_pattern(&self.ast);
+        NFAProgram::new(
+            pattern.start_state(),
+            pattern.accept_state(),
+            NFARulebook::new(pattern.rules()),
+        )
+    }
+
     // DFA型インスタンスを生成
     fn to_pattern(&self, ast: &AstTree) -> Box<dyn BasePattern> {
         match ast {
@@ -44,7 +58,29 @@ impl Builder {
             AstTree::Literal(c) => Box::new(Literal::new(*c)),
             AstTree::Plus(ast) => Box::new(Plus::new(self.to_pattern(ast), self.to_pattern(ast))),
             AstTree::Question(ast) => Box::new(Question::new(self.to_pattern(ast))),
+            AstTree::String(_) | AstTree::Class(_) => self.to_pattern(&ast.expand()),
             AstTree::Dot => Box::new(Dot::new()),
+            AstTree::Assert(a) => Box::new(Assert::new(*a)),
+            AstTree::Property(p) => Box::new(Property::new(*p)),
+            AstTree::Capture(_, _, ast) => self.to_pattern(ast),
+            AstTree::And(_, _) | AstTree::Not(_) => {
+                panic!(
+                    "[Builder::to_pattern] set operators are not supported ({:?})",
+                    ast
+                )
+            }
+            AstTree::Backref(_) => {
+                panic!(
+                    "[Builder::to_pattern] backreferences are not supported ({:?})",
+                    ast
+                )
+            }
+            AstTree::Look(_, _) => {
+                panic!(
+                    "[Builder::to_pattern] lookaround is not supported ({:?})",
+                    ast
+                )
+            }
         }
     }
 }
@@ -160,6 +196,70 @@ mod test {
             assert!(!Builder::new("ab|cd").to_dfa().is_match("abcd"));
         }
     }
+    #[test]
+    fn test_to_nfa() {
+        let nfa = Builder::new("a(bc)*").to_nfa();
+
+        assert!(nfa.accept("a"));
+        assert!(nfa.accept("abcbc"));
+        assert!(!nfa.accept("ab"));
+
+        let dfa = nfa.to_dfa(100).unwrap();
+        assert!(dfa.accept("a"));
+        assert!(dfa.accept("abcbc"));
+        assert!(!dfa.accept("ab"));
+        assert!(nfa.to_dfa(1).is_none());
+    }
+
+    #[test]
+    fn test_to_nfa_assertion() {
+        let inputs = [
+            "", "a", "ab", "ab ", " ab", "ab ab", "abab", "a b", "ba", "b a ", "aab", "_ab",
+        ];
+        for pattern in [
+            r".*\bab\b.*",
+            r"\A(a|b.)*\z",
+            r"(\Ba)+.*",
+            r"a\B.",
+            r".*b\b",
+        ]
+        .iter()
+        {
+            let nfa = Builder::new(pattern).to_nfa();
+            let dfa = nfa.to_dfa(1000).unwrap();
+            let reverse = nfa.reverse().to_dfa(1000).unwrap();
+            for input in inputs.iter() {
+                let reversed: String = input.chars().rev().collect();
+                assert_eq!(
+                    nfa.accept(input),
+                    dfa.accept(input),
+                    "{} {}",
+                    pattern,
+                    input
+                );
+                assert_eq!(
+                    nfa.accept(input),
+                    reverse.accept(&reversed),
+                    "{} {}",
+                    pattern,
+                    input
+                );
+            }
+        }
+        assert!(Builder::new(r".*\bab\b.*").to_nfa().accept("x ab y"));
+        assert!(!Builder::new(r".*\bab\b.*").to_nfa().accept("xab y"));
+    }
+
+    #[test]
+    fn test_to_nfa_reverse() {
+        let nfa = Builder::new("a(bc)*d").to_nfa().reverse();
+
+        assert!(nfa.accept("da"));
+        assert!(nfa.accept("dcbcba"));
+        assert!(!nfa.accept("abcd"));
+        assert!(nfa.to_dfa(100).unwrap().accept("dcba"));
+    }
+
     #[test]
     fn test_parser_question() {
         {
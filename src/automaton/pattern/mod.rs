@@ -1,3 +1,4 @@
+pub mod assert;
 pub mod base;
 pub mod build;
 pub mod concat;
@@ -6,5 +7,6 @@ pub mod empty;
 pub mod literal;
 pub mod or;
 pub mod plus;
+pub mod property;
 pub mod question;
 pub mod repeat;
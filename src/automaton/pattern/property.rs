@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+
+use crate::automaton::farule::{FARule, State, TransitionType};
+use crate::automaton::nfa::{NFADesign, NFARulebook};
+use crate::automaton::pattern::base::BasePattern;
+use crate::unicode::Property as UnicodeProperty;
+
+// Unicodeプロパティの集合に含まれる一文字
+#[derive(Debug)]
+pub struct Property {
+    property: UnicodeProperty,
+    start_state: State,
+    accept_state: State,
+}
+
+impl Property {
+    pub fn new(property: UnicodeProperty) -> Self {
+        Property {
+            property,
+            start_state: State::create_at_rnd(),
+            accept_state: State::create_at_rnd(),
+        }
+    }
+}
+
+impl BasePattern for Property {
+    fn is_match(&self, s: &str) -> bool {
+        let rules = self.rules();
+        NFADesign::new(
+            self.start_state,
+            &self.accept_state(),
+            &NFARulebook::new(rules),
+        )
+        .accept(s)
+    }
+
+    fn rules(&self) -> Vec<FARule> {
+        vec![FARule::new(
+            self.start_state,
+            TransitionType::Property(self.property),
+            self.accept_state,
+        )]
+    }
+
+    fn accept_state(&self) -> Vec<State> {
+        vec![self.accept_state]
+    }
+
+    fn start_state(&self) -> State {
+        self.start_state
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::automaton::pattern::{concat::Concat, literal::Literal, repeat::Repeat};
+    use std::boxed::Box;
+
+    #[test]
+    fn test_property() {
+        {
+            let p = Property::new(UnicodeProperty::new("Greek", false).unwrap());
+
+            assert!(p.is_match("Σ"));
+            assert!(!p.is_match("S"));
+            assert!(!p.is_match(""));
+            assert!(!p.is_match("ΣΣ"));
+        }
+        {
+            // \P{L}*
+            let r = Repeat::new(Box::new(Property::new(
+                UnicodeProperty::new("L", true).unwrap(),
+            )));
+
+            assert!(r.is_match("12 3"));
+            assert!(!r.is_match("1a"));
+        }
+        {
+            // a\p{Nd}
+            let c = Concat::new(
+                Box::new(Literal::new('a')),
+                Box::new(Property::new(UnicodeProperty::new("Nd", false).unwrap())),
+            );
+
+            assert!(c.is_match("a7"));
+            assert!(c.is_match("a٣"));
+            assert!(!c.is_match("ab"));
+        }
+    }
+}
@@ -1,20 +1,22 @@
 #![allow(dead_code)]
 
-use crate::automaton::farule::{FARule, State};
+use crate::automaton::farule::{FARule, State, TransitionType};
 use crate::automaton::nfa::{NFADesign, NFARulebook};
 use crate::automaton::pattern::base::BasePattern;
-use crate::automaton::pattern::{empty::Empty, or::Or};
 use std::boxed::Box;
 
+// 新しい開始状態を受理状態とし、ε遷移でパターンの開始状態へ遷移する
 #[derive(Debug)]
 pub struct Question<T: BasePattern + ?Sized> {
-    pattern: Or<T, Empty>,
+    start_state: State,
+    pattern: Box<T>,
 }
 
 impl<T: BasePattern + ?Sized> Question<T> {
     pub fn new(pattern: Box<T>) -> Self {
         Question {
-            pattern: Or::new(pattern, Box::new(Empty::new())),
+            start_state: State::create_at_rnd(),
+            pattern,
         }
     }
 }
@@ -31,15 +33,23 @@ impl<T: BasePattern + ?Sized> BasePattern for Question<T> {
     }
 
     fn rules(&self) -> Vec<FARule> {
-        self.pattern.rules()
+        let mut rules = vec![FARule::new(
+            self.start_state,
+            TransitionType::Epsilon,
+            self.pattern.start_state(),
+        )];
+        rules.extend(self.pattern.rules());
+        rules
     }
 
     fn accept_state(&self) -> Vec<State> {
-        self.pattern.accept_state()
+        let mut accept = self.pattern.accept_state();
+        accept.push(self.start_state);
+        accept
     }
 
     fn start_state(&self) -> State {
-        self.pattern.start_state()
+        self.start_state
     }
 }
 
@@ -58,6 +68,7 @@ mod test {
             assert!(q.is_match("a"));
             assert!(q.is_match(""));
             assert!(!q.is_match("aa"));
+            assert!(!q.is_match("\0"));
         }
         // a?bのテスト
         {
@@ -0,0 +1,159 @@
+#![allow(dead_code)]
+
+use crate::automaton::dfa::{DFAProgram, DFARulebook};
+use crate::automaton::farule::{FARule, State, TransitionType};
+use crate::automaton::nfa::NFARulebook;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+// 複数のDFAを同時に動かす直積DFA
+// 遷移先がない場合も「死んだ状態」(None)として扱い、全ての文字で遷移する完全なDFAを作成する
+// acceptには各DFAが受理状態かどうかを渡し、直積の状態を受理するかを返す
+pub fn product<F>(dfas: &[&DFAProgram], accept: F) -> DFAProgram
+where
+    F: Fn(&[bool]) -> bool,
+{
+    product_limited(dfas, accept, usize::MAX).unwrap_or_else(|| panic!("[product] too many states"))
+}
+
+// 状態数の上限を指定して直積DFAを作成（超過時はNone）
+// 各DFAの状態数が上限未満でも、直積の状態数はその積まで増える
+pub fn product_limited<F>(dfas: &[&DFAProgram], accept: F, max_states: usize) -> Option<DFAProgram>
+where
+    F: Fn(&[bool]) -> bool,
+{
+    // いずれかのDFAに現れる文字と、それ以外の文字（全文字の遷移）
+    let chars: BTreeSet<char> = dfas.iter().flat_map(|d| d.alphabet()).collect();
+    let symbols: Vec<(TransitionType, char)> = chars
+        .into_iter()
+        .map(|c| (TransitionType::Character(c), c))
+        .chain(std::iter::once((
+            TransitionType::Everything,
+            NFARulebook::EVERYTHING_CHAR,
+        )))
+        .collect();
+
+    let start: Vec<Option<State>> = dfas.iter().map(|d| Some(d.start())).collect();
+    let mut ids: HashMap<Vec<Option<State>>, usize> = HashMap::new();
+    ids.insert(start.clone(), 0);
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+
+    let mut rules = vec![];
+    let mut accept_states = vec![];
+    while let Some(states) = queue.pop_front() {
+        let id = ids[&states];
+        let accepting: Vec<bool> = states
+            .iter()
+            .zip(dfas.iter())
+            .map(|(s, d)| s.is_some_and(|s| d.is_accept(&s)))
+            .collect();
+        if accept(&accepting) {
+            accept_states.push(State::new(id));
+        }
+
+        for (transition, c) in symbols.iter() {
+            let next: Vec<Option<State>> = states
+                .iter()
+                .zip(dfas.iter())
+                .map(|(s, d)| s.and_then(|s| d.next(s, *c)))
+                .collect();
+            let next_id = match ids.get(&next) {
+                Some(&next_id) => next_id,
+                None if ids.len() >= max_states => return None,
+                None => {
+                    let next_id = ids.len();
+                    ids.insert(next.clone(), next_id);
+                    queue.push_back(next);
+                    next_id
+                }
+            };
+            rules.push(FARule::new(
+                State::new(id),
+                *transition,
+                State::new(next_id),
+            ));
+        }
+    }
+
+    Some(DFAProgram::new(
+        State::new(0),
+        accept_states,
+        DFARulebook::new(rules),
+    ))
+}
+
+// 共通部分
+pub fn intersection(a: &DFAProgram, b: &DFAProgram) -> DFAProgram {
+    product(&[a, b], |acc| acc[0] && acc[1])
+}
+
+// 和集合
+pub fn union(a: &DFAProgram, b: &DFAProgram) -> DFAProgram {
+    product(&[a, b], |acc| acc[0] || acc[1])
+}
+
+// 差集合
+pub fn difference(a: &DFAProgram, b: &DFAProgram) -> DFAProgram {
+    product(&[a, b], |acc| acc[0] && !acc[1])
+}
+
+// 補集合（完全化した上で受理状態を反転）
+pub fn complement(a: &DFAProgram) -> DFAProgram {
+    product(&[a], |acc| !acc[0])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::automaton::pattern::build::Builder;
+
+    fn dfa(pattern: &str) -> DFAProgram {
+        Builder::new(pattern).to_nfa().to_dfa(100).unwrap()
+    }
+
+    #[test]
+    fn test_product_intersection() {
+        let d = intersection(&dfa("(a|b)*a"), &dfa("b.*"));
+
+        assert!(d.accept("ba"));
+        assert!(d.accept("bbba"));
+        assert!(!d.accept("aba"));
+        assert!(!d.accept("bab"));
+    }
+
+    #[test]
+    fn test_product_union_difference() {
+        let u = union(&dfa("ab"), &dfa("c+"));
+        assert!(u.accept("ab"));
+        assert!(u.accept("cc"));
+        assert!(!u.accept("abc"));
+
+        let d = difference(&dfa("a+"), &dfa("aa"));
+        assert!(d.accept("a"));
+        assert!(d.accept("aaa"));
+        assert!(!d.accept("aa"));
+    }
+
+    #[test]
+    fn test_product_limited() {
+        // 周期は最小公倍数の6になり、開始状態と死んだ状態を含めて8状態
+        let a = dfa("(aa)*");
+        let b = dfa("(aaa)*");
+        assert!(product_limited(&[&a, &b], |acc| acc[0] && acc[1], 7).is_none());
+
+        let d = product_limited(&[&a, &b], |acc| acc[0] && acc[1], 8).unwrap();
+        assert!(d.accept("aaaaaa"));
+        assert!(!d.accept("aaaa"));
+    }
+
+    #[test]
+    fn test_product_complement() {
+        let d = complement(&dfa("a.*"));
+
+        assert!(d.accept(""));
+        assert!(d.accept("ba"));
+        assert!(d.accept("xyz"));
+        assert!(!d.accept("a"));
+        assert!(!d.accept("abc"));
+    }
+}
@@ -0,0 +1,124 @@
+#![allow(dead_code)]
+
+use crate::automaton::dfa::DFAProgram;
+use crate::automaton::nfa::NFAProgram;
+
+// DFAによる一致範囲の検索
+// 逆向きDFAで入力を末尾から走査して最も左の開始位置を求め、
+// 前向きDFAで開始位置から走査して最も長い終了位置を求める
+#[derive(Debug)]
+pub struct DFASearcher {
+    forward: DFAProgram, // パターンそのもの
+    reverse: DFAProgram, // パターンの後に任意の文字列が続くものの逆向き
+}
+
+impl DFASearcher {
+    // patternはパターンのNFA、trailingはパターンの後に任意の文字列が続くNFA
+    // いずれかのDFAの状態数が上限を超えた場合はNone
+    pub fn new(pattern: &NFAProgram, trailing: &NFAProgram, max_states: usize) -> Option<Self> {
+        let forward = pattern.to_dfa(max_states)?;
+        let reverse = trailing.reverse().to_dfa(max_states)?;
+
+        Some(DFASearcher { forward, reverse })
+    }
+
+    // 最も左で始まり、その中で最も長い一致範囲（バイト位置）
+    pub fn find(&self, haystack: &str) -> Option<(usize, usize)> {
+        let start = self.leftmost_start(haystack)?;
+        let end = self.longest_end(haystack, start)?;
+        Some((start, end))
+    }
+
+    // 末尾から走査し、受理状態となった最も左の位置
+    // 表明の判定のため、受理状態はその位置の前後の文字に応じて判定する（逆向きでは直前の文字が直後となる）
+    fn leftmost_start(&self, haystack: &str) -> Option<usize> {
+        let before = |i: usize| haystack[..i].chars().next_back();
+        let mut state = self.reverse.start();
+        let mut start = if self
+            .reverse
+            .is_accept_before(&state, before(haystack.len()))
+        {
+            Some(haystack.len())
+        } else {
+            None
+        };
+
+        for (i, c) in haystack.char_indices().rev() {
+            match self.reverse.next(state, c) {
+                Some(next) => state = next,
+                None => break,
+            }
+            if self.reverse.is_accept_before(&state, before(i)) {
+                start = Some(i);
+            }
+        }
+        start
+    }
+
+    // 開始位置から走査し、受理状態となった最も右の位置
+    fn longest_end(&self, haystack: &str, start: usize) -> Option<usize> {
+        let after = |i: usize| haystack[i..].chars().next();
+        let mut state = self
+            .forward
+            .start_after(haystack[..start].chars().next_back());
+        let mut end = if self.forward.is_accept_before(&state, after(start)) {
+            Some(start)
+        } else {
+            None
+        };
+
+        for (i, c) in haystack[start..].char_indices() {
+            match self.forward.next(state, c) {
+                Some(next) => state = next,
+                None => break,
+            }
+            let i = start + i + c.len_utf8();
+            if self.forward.is_accept_before(&state, after(i)) {
+                end = Some(i);
+            }
+        }
+        end
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::automaton::pattern::build::Builder;
+    use crate::parse::lexer::Lexer;
+    use crate::parse::parser::Ast;
+
+    fn searcher(pattern: &str) -> DFASearcher {
+        let ast = Ast::new(&Lexer::new(pattern).scan()).parse();
+        let nfa = Builder::from_ast(ast.clone()).to_nfa();
+        let trailing = Builder::from_ast(ast.trailing_any()).to_nfa();
+        DFASearcher::new(&nfa, &trailing, 100).unwrap()
+    }
+
+    #[test]
+    fn test_dfa_searcher_find() {
+        assert_eq!(Some((2, 5)), searcher("ab+").find("xxabbx"));
+        assert_eq!(Some((0, 4)), searcher("abcd|c").find("abcd"));
+        assert_eq!(Some((1, 3)), searcher("a.").find("babab"));
+        assert_eq!(Some((4, 5)), searcher("b").find("ééb"));
+        assert_eq!(None, searcher("ab+").find("xxaax"));
+    }
+
+    #[test]
+    fn test_dfa_searcher_empty_match() {
+        assert_eq!(Some((0, 0)), searcher("a*").find("bbb"));
+        assert_eq!(Some((0, 0)), searcher("a*b?").find("xaa"));
+        assert_eq!(Some((1, 3)), searcher("a+b?").find("xaa"));
+        assert_eq!(Some((0, 0)), searcher("a?").find(""));
+    }
+
+    #[test]
+    fn test_dfa_searcher_assertion() {
+        assert_eq!(Some((5, 8)), searcher(r"\bfoo\b").find("xfoo foo foox"));
+        assert_eq!(Some((3, 6)), searcher(r"\Bfoo").find("foofoo"));
+        assert_eq!(Some((0, 2)), searcher(r"\Aab").find("abab"));
+        assert_eq!(Some((2, 4)), searcher(r"ab\z").find("abab"));
+        assert_eq!(Some((0, 3)), searcher(r"a+\b").find("aaa"));
+        assert_eq!(None, searcher(r"\ba\b").find("ab ba"));
+    }
+}
@@ -0,0 +1,528 @@
+#![allow(dead_code)]
+
+use crate::automaton::dfa::{DFAProgram, DFARulebook};
+use crate::automaton::farule::{FARule, State, TransitionType};
+use crate::automaton::nfa::NFARulebook;
+use crate::parse::parser::AstTree;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::rc::Rc;
+
+// 微分で扱う正規表現
+// スマートコンストラクタで正規化し、等価な式が同じ値になるようにする
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Re {
+    Empty,                  // 何にもマッチしない
+    Epsilon,                // 空文字列にマッチ
+    Char(char),             // 一文字
+    Any,                    // 任意の一文字
+    Concat(Rc<Re>, Rc<Re>), // 右結合の連結
+    Or(Vec<Rc<Re>>),        // 整列・重複除去済みの選択
+    Star(Rc<Re>),           // 0回以上の繰り返し
+    And(Vec<Rc<Re>>),       // 整列・重複除去済みの共通部分
+    Not(Rc<Re>),            // 補集合
+}
+
+impl Re {
+    pub fn from_ast(ast: &AstTree) -> Rc<Re> {
+        match ast {
+            AstTree::Literal(c) => Rc::new(Re::Char(*c)),
+            AstTree::Dot => Rc::new(Re::Any),
+            AstTree::String(s) => s.chars().rev().fold(Rc::new(Re::Epsilon), |acc, c| {
+                Self::concat(Rc::new(Re::Char(c)), acc)
+            }),
+            AstTree::Class(chars) => chars.iter().fold(Rc::new(Re::Empty), |acc, c| {
+                Self::or(acc, Rc::new(Re::Char(*c)))
+            }),
+            AstTree::Concat(l, r) => Self::concat(Self::from_ast(l), Self::from_ast(r)),
+            AstTree::Or(l, r) => Self::or(Self::from_ast(l), Self::from_ast(r)),
+            AstTree::Repeat(a) => Self::star(Self::from_ast(a)),
+            AstTree::Plus(a) => {
+                let a = Self::from_ast(a);
+                Self::concat(a.clone(), Self::star(a))
+            }
+            AstTree::Question(a) => Self::or(Self::from_ast(a), Rc::new(Re::Epsilon)),
+            AstTree::And(l, r) => Self::and(Self::from_ast(l), Self::from_ast(r)),
+            AstTree::Not(a) => Self::not(Self::from_ast(a)),
+            AstTree::Capture(_, _, a) => Self::from_ast(a),
+            AstTree::Look(_, _) => {
+                panic!("[Re::from_ast] lookaround is not supported ({:?})", ast)
+            }
+            AstTree::Assert(_) => {
+                panic!("[Re::from_ast] assertions are not supported ({:?})", ast)
+            }
+            AstTree::Property(_) => {
+                panic!(
+                    "[Re::from_ast] unicode properties are not supported ({:?})",
+                    ast
+                )
+            }
+            AstTree::Backref(_) => {
+                panic!(
+                    "[Re::from_ast] backreferences are not supported ({:?})",
+                    ast
+                )
+            }
+        }
+    }
+
+    // ASTへ変換（r|ε = r?、rr* = r*r = r+、r+|ε = (r|ε)* = r*）
+    // ∅とεは単独では書けないため、補集合で~.*と~.+と表す
+    pub fn to_ast(&self) -> AstTree {
+        let any = || Box::new(AstTree::Dot);
+        match self {
+            Re::Empty => AstTree::Not(Box::new(AstTree::Repeat(any()))),
+            Re::Epsilon => AstTree::Not(Box::new(AstTree::Plus(any()))),
+            Re::Char(c) => AstTree::Literal(*c),
+            Re::Any => AstTree::Dot,
+            Re::Concat(l, r) => {
+                let mut factors = Self::factors(l);
+                factors.extend(Self::factors(r));
+                Self::concat_to_ast(&factors)
+            }
+            Re::Or(items) => {
+                if let Some(re) = Self::factor_or(items) {
+                    return re.to_ast();
+                }
+                let rest: Vec<&Rc<Re>> = items.iter().filter(|r| ***r != Re::Epsilon).collect();
+                let ast = Self::fold(rest.iter().map(|r| r.to_ast()).collect(), AstTree::Or);
+                match ast {
+                    _ if rest.len() == items.len() || rest.iter().any(|r| r.nullable()) => ast,
+                    AstTree::Plus(a) => AstTree::Repeat(a),
+                    _ => AstTree::Question(Box::new(ast)),
+                }
+            }
+            Re::Star(a) => match &**a {
+                Re::Or(items) if items.iter().any(|r| **r == Re::Epsilon) => {
+                    let rest = items.iter().filter(|r| ***r != Re::Epsilon);
+                    let a = rest.fold(Rc::new(Re::Empty), |acc, r| Self::or(acc, r.clone()));
+                    AstTree::Repeat(Box::new(a.to_ast()))
+                }
+                _ => AstTree::Repeat(Box::new(a.to_ast())),
+            },
+            Re::And(items) => Self::fold(items.iter().map(|r| r.to_ast()).collect(), AstTree::And),
+            Re::Not(a) => AstTree::Not(Box::new(a.to_ast())),
+        }
+    }
+
+    // 選択肢に共通する接頭辞・接尾辞をくくり出す（ab|ac = a(b|c)、c|bc = (b|ε)c）
+    fn factor_or(items: &[Rc<Re>]) -> Option<Rc<Re>> {
+        let lists: Vec<Vec<Rc<Re>>> = items
+            .iter()
+            .map(|r| match **r {
+                Re::Epsilon => vec![],
+                _ => Self::factors(r),
+            })
+            .collect();
+        let first = &lists[0];
+        let min = lists.iter().map(|l| l.len()).min()?;
+        let prefix = (0..min)
+            .take_while(|&i| lists.iter().all(|l| l[i] == first[i]))
+            .count();
+        let suffix = (0..min - prefix)
+            .take_while(|&i| {
+                lists
+                    .iter()
+                    .all(|l| l[l.len() - 1 - i] == first[first.len() - 1 - i])
+            })
+            .count();
+        if prefix == 0 && suffix == 0 {
+            return None;
+        }
+
+        let middle = lists
+            .iter()
+            .map(|l| Self::concat_all(&l[prefix..l.len() - suffix]))
+            .fold(Rc::new(Re::Empty), Self::or);
+        let mut factors = first[..prefix].to_vec();
+        factors.push(middle);
+        factors.extend_from_slice(&first[first.len() - suffix..]);
+        Some(Self::concat_all(&factors))
+    }
+
+    fn concat_all(factors: &[Rc<Re>]) -> Rc<Re> {
+        factors
+            .iter()
+            .rev()
+            .fold(Rc::new(Re::Epsilon), |acc, f| Self::concat(f.clone(), acc))
+    }
+
+    // 連結の各要素（右結合を展開したもの）
+    fn factors(re: &Rc<Re>) -> Vec<Rc<Re>> {
+        match &**re {
+            Re::Concat(l, r) => {
+                let mut factors = Self::factors(l);
+                factors.extend(Self::factors(r));
+                factors
+            }
+            _ => vec![re.clone()],
+        }
+    }
+
+    // 繰り返しの前後に同じ式が連結されていればr+にまとめる
+    fn concat_to_ast(factors: &[Rc<Re>]) -> AstTree {
+        let mut items: Vec<(Option<&Rc<Re>>, AstTree)> = vec![];
+        let mut i = 0;
+        while i < factors.len() {
+            if let Re::Star(a) = &*factors[i] {
+                let body = Self::factors(a);
+                let n = body.len();
+                let before = items.len() >= n
+                    && items[items.len() - n..]
+                        .iter()
+                        .map(|(r, _)| *r)
+                        .eq(body.iter().map(Some));
+                let after = factors.len() > i + n && factors[i + 1..=i + n] == body[..];
+
+                if before || after {
+                    if before {
+                        items.truncate(items.len() - n);
+                    } else {
+                        i += n;
+                    }
+                    items.push((None, AstTree::Plus(Box::new(a.to_ast()))));
+                    i += 1;
+                    continue;
+                }
+            }
+            items.push((Some(&factors[i]), factors[i].to_ast()));
+            i += 1;
+        }
+        Self::fold(
+            items.into_iter().map(|(_, ast)| ast).collect(),
+            AstTree::Concat,
+        )
+    }
+
+    // 右結合で二項のASTにまとめる
+    fn fold(mut asts: Vec<AstTree>, node: fn(Box<AstTree>, Box<AstTree>) -> AstTree) -> AstTree {
+        let last = asts.pop().expect("[Re::fold] empty items");
+        asts.into_iter()
+            .rev()
+            .fold(last, |acc, ast| node(Box::new(ast), Box::new(acc)))
+    }
+
+    // 連結（∅・εを除去し、右結合に揃える）
+    pub fn concat(l: Rc<Re>, r: Rc<Re>) -> Rc<Re> {
+        match (&*l, &*r) {
+            (Re::Empty, _) | (_, Re::Empty) => Rc::new(Re::Empty),
+            (Re::Epsilon, _) => r,
+            (_, Re::Epsilon) => l,
+            (Re::Concat(a, b), _) => Self::concat(a.clone(), Self::concat(b.clone(), r)),
+            _ => Rc::new(Re::Concat(l, r)),
+        }
+    }
+
+    // 選択（入れ子を平坦化し、∅を除去して整列・重複除去）
+    pub fn or(l: Rc<Re>, r: Rc<Re>) -> Rc<Re> {
+        let mut set = BTreeSet::new();
+        for re in [l, r].iter() {
+            match &**re {
+                Re::Empty => {}
+                Re::Or(items) => set.extend(items.iter().cloned()),
+                _ => {
+                    set.insert(re.clone());
+                }
+            }
+        }
+
+        let mut items: Vec<Rc<Re>> = set.into_iter().collect();
+        match items.len() {
+            0 => Rc::new(Re::Empty),
+            1 => items.remove(0),
+            _ => Rc::new(Re::Or(items)),
+        }
+    }
+
+    // 共通部分（入れ子を平坦化し、∅があれば∅、整列・重複除去）
+    pub fn and(l: Rc<Re>, r: Rc<Re>) -> Rc<Re> {
+        let mut set = BTreeSet::new();
+        for re in [l, r].iter() {
+            match &**re {
+                Re::Empty => return Rc::new(Re::Empty),
+                Re::And(items) => set.extend(items.iter().cloned()),
+                _ => {
+                    set.insert(re.clone());
+                }
+            }
+        }
+
+        let mut items: Vec<Rc<Re>> = set.into_iter().collect();
+        match items.len() {
+            1 => items.remove(0),
+            _ => Rc::new(Re::And(items)),
+        }
+    }
+
+    // 補集合（~~a = a）
+    pub fn not(a: Rc<Re>) -> Rc<Re> {
+        match &*a {
+            Re::Not(b) => b.clone(),
+            _ => Rc::new(Re::Not(a)),
+        }
+    }
+
+    // 繰り返し（(a*)* = a*、∅* = ε* = ε）
+    pub fn star(a: Rc<Re>) -> Rc<Re> {
+        match &*a {
+            Re::Empty | Re::Epsilon => Rc::new(Re::Epsilon),
+            Re::Star(_) => a,
+            _ => Rc::new(Re::Star(a)),
+        }
+    }
+
+    // 空文字列にマッチするか
+    pub fn nullable(&self) -> bool {
+        match self {
+            Re::Empty | Re::Char(_) | Re::Any => false,
+            Re::Epsilon | Re::Star(_) => true,
+            Re::Concat(l, r) => l.nullable() && r.nullable(),
+            Re::Or(items) => items.iter().any(|re| re.nullable()),
+            Re::And(items) => items.iter().all(|re| re.nullable()),
+            Re::Not(a) => !a.nullable(),
+        }
+    }
+
+    // 文字cによる微分（cで始まる文字列からcを除いた残りにマッチする式）
+    pub fn derivative(&self, c: char) -> Rc<Re> {
+        match self {
+            Re::Empty | Re::Epsilon => Rc::new(Re::Empty),
+            Re::Char(d) if *d == c => Rc::new(Re::Epsilon),
+            Re::Char(_) => Rc::new(Re::Empty),
+            Re::Any => Rc::new(Re::Epsilon),
+            Re::Concat(l, r) => {
+                let head = Self::concat(l.derivative(c), r.clone());
+                if l.nullable() {
+                    Self::or(head, r.derivative(c))
+                } else {
+                    head
+                }
+            }
+            Re::Or(items) => items.iter().fold(Rc::new(Re::Empty), |acc, re| {
+                Self::or(acc, re.derivative(c))
+            }),
+            Re::Star(a) => Self::concat(a.derivative(c), Rc::new(self.clone())),
+            Re::And(items) => {
+                let mut derivatives = items.iter().map(|re| re.derivative(c));
+                let first = derivatives.next().unwrap_or_else(|| Rc::new(Re::Empty));
+                derivatives.fold(first, Self::and)
+            }
+            Re::Not(a) => Self::not(a.derivative(c)),
+        }
+    }
+
+    // 式に現れる文字
+    fn alphabet(&self, chars: &mut BTreeSet<char>) {
+        match self {
+            Re::Char(c) => {
+                chars.insert(*c);
+            }
+            Re::Concat(l, r) => {
+                l.alphabet(chars);
+                r.alphabet(chars);
+            }
+            Re::Or(items) | Re::And(items) => items.iter().for_each(|re| re.alphabet(chars)),
+            Re::Star(a) | Re::Not(a) => a.alphabet(chars),
+            Re::Empty | Re::Epsilon | Re::Any => {}
+        }
+    }
+}
+
+// 微分により照合するマシン
+// 式を状態として番号を振り、(状態, 文字)ごとの遷移先をメモ化する
+#[derive(Debug)]
+pub struct DerivativeMachine {
+    start: usize,
+    cache: RefCell<Cache>,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    states: Vec<Rc<Re>>,
+    ids: HashMap<Rc<Re>, usize>,
+    transitions: HashMap<(usize, char), usize>,
+}
+
+impl Cache {
+    // 式に対応する状態番号（未登録なら登録）
+    fn intern(&mut self, re: Rc<Re>) -> usize {
+        if let Some(&id) = self.ids.get(&re) {
+            return id;
+        }
+        let id = self.states.len();
+        self.states.push(re.clone());
+        self.ids.insert(re, id);
+        id
+    }
+
+    fn next(&mut self, state: usize, c: char) -> usize {
+        if let Some(&next) = self.transitions.get(&(state, c)) {
+            return next;
+        }
+        let derivative = self.states[state].derivative(c);
+        let next = self.intern(derivative);
+        self.transitions.insert((state, c), next);
+        next
+    }
+}
+
+impl DerivativeMachine {
+    pub fn new(ast: &AstTree) -> Self {
+        let mut cache = Cache::default();
+        let start = cache.intern(Re::from_ast(ast));
+        DerivativeMachine {
+            start,
+            cache: RefCell::new(cache),
+        }
+    }
+
+    // 入力全体がマッチするか
+    pub fn is_match(&self, s: &str) -> bool {
+        let mut cache = self.cache.borrow_mut();
+        let mut state = self.start;
+        for c in s.chars() {
+            state = cache.next(state, c);
+            // ∅になった時点で以降はマッチしない
+            if *cache.states[state] == Re::Empty {
+                return false;
+            }
+        }
+        cache.states[state].nullable()
+    }
+
+    // これまでに生成した状態数
+    pub fn states(&self) -> usize {
+        self.cache.borrow().states.len()
+    }
+
+    // 全ての微分を求めてDFAを構築（状態数が上限を超えた場合はNone）
+    // 式に現れない文字の微分は全て等しいため、全文字の遷移としてまとめる
+    pub fn to_dfa(&self, max_states: usize) -> Option<DFAProgram> {
+        let mut cache = self.cache.borrow_mut();
+        let mut chars = BTreeSet::new();
+        cache.states[self.start].alphabet(&mut chars);
+
+        let mut rules = vec![];
+        let mut accept_states = vec![];
+        let mut searched = BTreeSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(self.start);
+
+        while let Some(state) = queue.pop_front() {
+            if !searched.insert(state) {
+                continue;
+            }
+            if searched.len() > max_states {
+                return None;
+            }
+            if cache.states[state].nullable() {
+                accept_states.push(State::new(state));
+            }
+
+            let transitions = chars
+                .iter()
+                .map(|&c| (TransitionType::Character(c), c))
+                .chain(std::iter::once((
+                    TransitionType::Everything,
+                    NFARulebook::EVERYTHING_CHAR,
+                )));
+            for (transition, c) in transitions {
+                let next = cache.next(state, c);
+                rules.push(FARule::new(State::new(state), transition, State::new(next)));
+                queue.push_back(next);
+            }
+        }
+
+        Some(DFAProgram::new(
+            State::new(self.start),
+            accept_states,
+            DFARulebook::new(rules),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::lexer::Lexer;
+    use crate::parse::parser::Ast;
+
+    fn machine(pattern: &str) -> DerivativeMachine {
+        DerivativeMachine::new(&Ast::new(&Lexer::new(pattern).scan()).parse())
+    }
+
+    #[test]
+    fn test_re_smart_constructor() {
+        let a = Rc::new(Re::Char('a'));
+        let b = Rc::new(Re::Char('b'));
+
+        assert_eq!(Re::or(a.clone(), b.clone()), Re::or(b.clone(), a.clone()));
+        assert_eq!(a, Re::or(a.clone(), a.clone()));
+        assert_eq!(a, Re::or(Rc::new(Re::Empty), a.clone()));
+        assert_eq!(a, Re::concat(Rc::new(Re::Epsilon), a.clone()));
+        assert_eq!(
+            Rc::new(Re::Empty),
+            Re::concat(a.clone(), Rc::new(Re::Empty))
+        );
+        assert_eq!(Re::star(a.clone()), Re::star(Re::star(a)));
+    }
+
+    #[test]
+    fn test_re_derivative() {
+        let re = Re::from_ast(&Ast::new(&Lexer::new("ab*").scan()).parse());
+
+        assert!(!re.nullable());
+        assert!(re.derivative('a').nullable());
+        assert_eq!(re.derivative('a'), re.derivative('a').derivative('b'));
+        assert_eq!(Rc::new(Re::Empty), re.derivative('b'));
+    }
+
+    #[test]
+    fn test_derivative_machine() {
+        let m = machine("a(b|c)*d+");
+
+        assert!(m.is_match("ad"));
+        assert!(m.is_match("abccbdd"));
+        assert!(!m.is_match("abc"));
+        assert!(!m.is_match("xad"));
+        assert!(machine("a?").is_match(""));
+        assert!(machine("a.c").is_match("abc"));
+    }
+
+    #[test]
+    fn test_derivative_set_operator() {
+        let m = machine("(a|b)+&~(a*)");
+        assert!(m.is_match("ab"));
+        assert!(m.is_match("b"));
+        assert!(!m.is_match("aa"));
+        assert!(!m.is_match(""));
+
+        let dfa = machine("~(.*ab.*)").to_dfa(100).unwrap();
+        assert!(dfa.accept("ba"));
+        assert!(dfa.accept(""));
+        assert!(!dfa.accept("xaby"));
+    }
+
+    #[test]
+    fn test_derivative_machine_memoize() {
+        let m = machine("(a|b)*abb");
+        assert!(m.is_match("abababb"));
+
+        // 状態数は入力長によらず有限
+        let states = m.states();
+        assert!(m.is_match(&"aabb".repeat(100)));
+        assert_eq!(states, m.states());
+    }
+
+    #[test]
+    fn test_derivative_to_dfa() {
+        let dfa = machine("(a|b)*abb").to_dfa(100).unwrap();
+
+        assert!(dfa.accept("abb"));
+        assert!(dfa.accept("babb"));
+        assert!(!dfa.accept("abba"));
+        assert!(!dfa.accept("abxb"));
+        assert!(machine(".*ab.*").to_dfa(100).unwrap().accept("xxabzz"));
+        assert!(machine("(a|b)*abb").to_dfa(2).is_none());
+    }
+}
@@ -0,0 +1,69 @@
+#![allow(dead_code)]
+
+use crate::regex::Engine;
+use std::error;
+use std::fmt;
+use std::time::Duration;
+
+// 正規表現のコンパイル・実行時エラー
+#[derive(Debug, PartialEq, Clone)]
+pub enum Error {
+    // コンパイル結果が上限を超えた
+    ProgramTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    // DFA状態数が上限を超えた
+    TooManyDfaStates {
+        limit: usize,
+    },
+    // バックトラック回数が上限を超えた
+    BacktrackLimitExceeded {
+        limit: usize,
+    },
+    // 実行時間が上限を超えた
+    Timeout {
+        timeout: Duration,
+    },
+    // エンジンが未対応の機能
+    Unsupported {
+        engine: Engine,
+        feature: &'static str,
+    },
+    // 存在しないグループへの後方参照
+    InvalidBackreference {
+        group: usize,
+        groups: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ProgramTooLarge { size, limit } => write!(
+                f,
+                "compiled program size {} exceeds the limit of {}",
+                size, limit
+            ),
+            Error::TooManyDfaStates { limit } => {
+                write!(f, "number of DFA states exceeds the limit of {}", limit)
+            }
+            Error::BacktrackLimitExceeded { limit } => {
+                write!(f, "backtracking exceeds the limit of {} steps", limit)
+            }
+            Error::Timeout { timeout } => {
+                write!(f, "matching exceeds the timeout of {:?}", timeout)
+            }
+            Error::Unsupported { engine, feature } => {
+                write!(f, "{:?} engine does not support {}", engine, feature)
+            }
+            Error::InvalidBackreference { group, groups } => write!(
+                f,
+                "backreference \\{} refers to a missing group (the pattern has {} groups)",
+                group, groups
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {}
@@ -0,0 +1,195 @@
+#![allow(dead_code)]
+
+use crate::algebra;
+use crate::automaton::dfa::DFAProgram;
+use crate::automaton::product;
+use crate::error::Error;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+// 近い文字列を作る際の試行回数
+const NEAR_MISS_ATTEMPTS: usize = 100;
+
+// パターンからランダムな文字列を生成
+// DFA上で「残りの文字数で受理状態へ到達できるか」を求め、到達できる遷移のみを選んで進む
+pub struct Generator {
+    dfa: DFAProgram,
+    rng: StdRng,
+    min_len: usize,
+    max_len: usize,
+}
+
+impl Generator {
+    // 言語として扱えない機能を含む場合、DFA状態数が上限を超える場合はエラー
+    pub fn new(pattern: &str) -> Result<Self, Error> {
+        Ok(Generator {
+            dfa: algebra::compile_language(pattern, algebra::MAX_STATES)?,
+            rng: StdRng::from_entropy(),
+            min_len: 0,
+            max_len: 10,
+        })
+    }
+
+    // 乱数のシード（同じシードでは同じ列を生成）
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    // 生成する文字列の長さ（文字数）の範囲
+    pub fn length(mut self, min_len: usize, max_len: usize) -> Self {
+        self.min_len = min_len;
+        self.max_len = max_len;
+        self
+    }
+
+    // パターンにマッチする文字列（範囲内の長さで存在しない場合はNone）
+    pub fn generate(&mut self) -> Option<String> {
+        Self::walk(&self.dfa, &mut self.rng, self.min_len, self.max_len)
+    }
+
+    // パターンにマッチしない、マッチする文字列に近い文字列
+    // マッチする文字列を一文字だけ編集し、それでも見つからなければ補集合から生成する
+    pub fn near_miss(&mut self) -> Option<String> {
+        for _ in 0..NEAR_MISS_ATTEMPTS {
+            let accepted = match self.generate() {
+                Some(s) => s,
+                None => break,
+            };
+            let candidate = self.edit(&accepted);
+            if !self.dfa.accept(&candidate) {
+                return Some(candidate);
+            }
+        }
+
+        let complement = product::complement(&self.dfa);
+        Self::walk(&complement, &mut self.rng, self.min_len, self.max_len)
+    }
+
+    // 一文字の削除・挿入・置換
+    fn edit(&mut self, s: &str) -> String {
+        let mut chars: Vec<char> = s.chars().collect();
+        let c = random_char(&self.dfa, &mut self.rng);
+        let i = self.rng.gen_range(0..=chars.len());
+
+        match self.rng.gen_range(0..3) {
+            0 if i < chars.len() => {
+                chars.remove(i);
+            }
+            1 if i < chars.len() => chars[i] = c,
+            _ => chars.insert(i, c),
+        }
+        chars.into_iter().collect()
+    }
+
+    fn walk(dfa: &DFAProgram, rng: &mut StdRng, min_len: usize, max_len: usize) -> Option<String> {
+        let symbols = algebra::symbols(dfa);
+        let states = algebra::reachable(dfa, &symbols);
+
+        // feasible[n]: n文字で受理状態へ到達できる状態
+        let feasible = algebra::feasible(dfa, &states, &symbols, max_len);
+
+        let lengths: Vec<usize> = (min_len..=max_len)
+            .filter(|n| feasible[*n].contains(&dfa.start()))
+            .collect();
+        let len = *lengths.choose(rng)?;
+
+        let mut state = dfa.start();
+        let mut s = String::new();
+        for remain in (0..len).rev() {
+            let candidates: Vec<char> = symbols
+                .iter()
+                .filter(|c| {
+                    dfa.next(state, **c)
+                        .is_some_and(|t| feasible[remain].contains(&t))
+                })
+                .copied()
+                .collect();
+            let c = *candidates.choose(rng)?;
+            state = dfa.next(state, c)?;
+
+            // パターンに現れない文字の代表は、現れない文字からランダムに選ぶ
+            if dfa.alphabet().contains(&c) {
+                s.push(c);
+            } else {
+                s.push(random_other(dfa, rng));
+            }
+        }
+        Some(s)
+    }
+}
+
+// パターンに現れる文字、またはそれ以外の文字
+fn random_char(dfa: &DFAProgram, rng: &mut StdRng) -> char {
+    let alphabet: Vec<char> = dfa.alphabet().into_iter().collect();
+    match alphabet.choose(rng) {
+        Some(c) if rng.gen_bool(0.8) => *c,
+        _ => random_other(dfa, rng),
+    }
+}
+
+// パターンに現れない英数字
+fn random_other(dfa: &DFAProgram, rng: &mut StdRng) -> char {
+    let alphabet = dfa.alphabet();
+    let others: Vec<char> = ('a'..='z')
+        .chain('0'..='9')
+        .chain('A'..='Z')
+        .filter(|c| !alphabet.contains(c))
+        .collect();
+    *others.choose(rng).unwrap_or(&'_')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algebra::Language;
+
+    #[test]
+    fn test_generate_accepted() {
+        let pattern = "a(b|c)*d.";
+        let language = Language::new(pattern).unwrap();
+        let mut g = Generator::new(pattern).unwrap().seed(1).length(3, 8);
+
+        for _ in 0..50 {
+            let s = g.generate().unwrap();
+            let len = s.chars().count();
+            assert!(language.is_match(&s), "{}", s);
+            assert!((3..=8).contains(&len), "{}", s);
+        }
+        assert_eq!(None, Generator::new("abc").unwrap().length(4, 6).generate());
+    }
+
+    #[test]
+    fn test_generate_seed() {
+        let a: Vec<_> = {
+            let mut g = Generator::new("(a|b)+c?").unwrap().seed(42);
+            (0..10).map(|_| g.generate()).collect()
+        };
+        let b: Vec<_> = {
+            let mut g = Generator::new("(a|b)+c?").unwrap().seed(42);
+            (0..10).map(|_| g.generate()).collect()
+        };
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_generate_near_miss() {
+        let pattern = "ab+c";
+        let language = Language::new(pattern).unwrap();
+        let mut g = Generator::new(pattern).unwrap().seed(7).length(0, 6);
+
+        for _ in 0..50 {
+            let s = g.near_miss().unwrap();
+            assert!(!language.is_match(&s), "{}", s);
+        }
+        assert_eq!(None, Generator::new(".*").unwrap().near_miss());
+    }
+
+    #[test]
+    fn test_generate_unsupported() {
+        assert!(Generator::new(r"\bab").is_err());
+        assert!(Generator::new(r"(a)\1").is_err());
+        assert!(Generator::new("a(?<=a)").is_err());
+    }
+}
@@ -1,4 +1,14 @@
+pub mod algebra;
+pub mod audit;
 mod automaton;
+mod derivative;
+pub mod error;
+pub mod generate;
 mod parse;
+mod prefilter;
 pub mod regex;
+mod unicode;
 mod vm;
+
+pub use automaton::aho_corasick::{LiteralMatch, MultiLiteral};
+pub use automaton::overlap::OverlapMatch;
@@ -1,8 +1,14 @@
 #![allow(dead_code)]
 
+use std::fmt;
+use std::fs;
 use std::path::PathBuf;
+use std::process;
 use structopt::StructOpt;
-use toy_regex::regex::Regex;
+use toy_regex::algebra::Language;
+use toy_regex::audit;
+use toy_regex::generate::Generator;
+use toy_regex::regex::{self, Construction, Engine, RegexBuilder};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "toy-regex", about = "Regular expression tool for learning")]
@@ -23,6 +29,72 @@ struct Opt {
     #[structopt(long, help = "Use vm engine")]
     vm: bool,
 
+    // 正規表現エンジン指定（自動選択を上書き）
+    #[structopt(
+        long,
+        help = "Regex engine (auto, backtrack, pike, nfa, dfa, derivative, literal)"
+    )]
+    engine: Option<Engine>,
+
+    // NFAの構成法
+    #[structopt(
+        long,
+        help = "NFA construction (thompson, glushkov)",
+        default_value = "thompson"
+    )]
+    construction: Construction,
+
+    // 選択されたエンジンを表示
+    #[structopt(long, help = "Print the selected engine")]
+    show_engine: bool,
+
+    // ReDoS解析
+    #[structopt(long, help = "Audit the pattern for catastrophic backtracking")]
+    audit: bool,
+
+    // 二つのパターンの比較（指定したパターンと正規表現が同じ文字列の集合にマッチするか）
+    #[structopt(long, help = "Compare the language with another pattern")]
+    compare: Option<String>,
+
+    // パターンにマッチするランダムな文字列をN個生成
+    #[structopt(long, help = "Generate N random strings matched by the pattern")]
+    generate: Option<usize>,
+
+    // マッチしない、マッチする文字列に近い文字列を生成
+    #[structopt(long, help = "Generate near-miss strings rejected by the pattern")]
+    near_miss: bool,
+
+    // 生成に使用する乱数のシード
+    #[structopt(long, help = "Random seed for --generate")]
+    seed: Option<u64>,
+
+    // 生成する文字列の最大長
+    #[structopt(long, help = "Maximum length for --generate", default_value = "10")]
+    max_len: usize,
+
+    // パターンの解析結果と最適化の前後
+    #[structopt(long, help = "Print the parsed and optimized pattern")]
+    explain: bool,
+
+    // 括弧が最小の形へ正規化したパターン
+    #[structopt(long, help = "Print the pattern with minimal parentheses")]
+    normalize: bool,
+
+    // 最小化したDFAから書き出したパターン
+    #[structopt(
+        long,
+        help = "Print an equivalent pattern rebuilt from the minimized DFA"
+    )]
+    export: bool,
+
+    // 短い順にN個の文字列を列挙
+    #[structopt(long, help = "List the first N strings in shortlex order")]
+    enumerate: Option<usize>,
+
+    // 長さ0からLENまでの各長さの文字列の数と総数
+    #[structopt(long, help = "Count matched strings of each length up to LEN")]
+    count: Option<usize>,
+
     // 部分文字でマッチ
     #[structopt(
         long,
@@ -37,9 +109,134 @@ fn main() {
     // コマンドラインから正規表現エンジン作成
     let opt = Opt::from_args();
 
+    // パターンの解析結果のみ表示
+    if opt.audit {
+        println!("{}", audit::audit(&opt.regex, !opt.substring));
+        return;
+    }
+
+    // 二つのパターンの比較結果のみ表示
+    if let Some(other) = &opt.compare {
+        compare(other, &opt.regex);
+        return;
+    }
+
+    // 解析結果のみ表示
+    if opt.explain {
+        print!("{}", regex::explain(&opt.regex));
+        return;
+    }
+
+    // 正規化したパターンのみ表示
+    if opt.normalize {
+        println!("{}", regex::normalize(&opt.regex));
+        return;
+    }
+
+    // 書き出したパターンのみ表示
+    if opt.export {
+        println!("{}", language(&opt.regex).to_pattern());
+        return;
+    }
+
+    // 列挙した文字列のみ表示
+    if let Some(n) = opt.enumerate {
+        for s in language(&opt.regex).enumerate(n) {
+            println!("{}", s);
+        }
+        return;
+    }
+
+    // 各長さの文字列の数のみ表示
+    if let Some(max_len) = opt.count {
+        let language = language(&opt.regex);
+        for (len, n) in language.counts(max_len).iter().enumerate() {
+            println!("{}: {}", len, n);
+        }
+        println!("total: {}", language.size());
+        return;
+    }
+
+    // 生成した文字列のみ表示
+    if let Some(n) = opt.generate {
+        let mut generator = Generator::new(&opt.regex)
+            .unwrap_or_else(|e| exit_with(&opt.regex, e))
+            .length(0, opt.max_len);
+        if let Some(seed) = opt.seed {
+            generator = generator.seed(seed);
+        }
+        for _ in 0..n {
+            let s = if opt.near_miss {
+                generator.near_miss()
+            } else {
+                generator.generate()
+            };
+            match s {
+                Some(s) => println!("{}", s),
+                None => {
+                    println!("[main] no string within length {}", opt.max_len);
+                    return;
+                }
+            }
+        }
+        return;
+    }
+
+    // エンジン指定がない場合は--vmフラグに従い、それ以外は自動選択
+    let engine = match opt.engine {
+        Some(engine) => engine,
+        None if opt.vm => Engine::Backtrack,
+        None => Engine::Auto,
+    };
+
+    let re = RegexBuilder::new(&opt.regex)
+        .engine(engine)
+        .construction(opt.construction)
+        .anchored(!opt.substring)
+        .build()
+        .unwrap_or_else(|e| exit_with(&opt.regex, e));
+    if opt.show_engine {
+        println!("engine: {:?}", re.engine());
+    }
+
+    // 検索対象文字列読み込み
+    let input = match (&opt.input_file, &opt.input_str) {
+        (Some(f), _) => fs::read_to_string(f).unwrap_or_else(|e| exit_with(&opt.regex, e)),
+        (None, Some(s)) => s.clone(),
+        (None, None) => exit_with(&opt.regex, "no input string and file"),
+    };
+
     // 正規表現実行
-    println!(
-        "{:?}",
-        Regex::new(&opt.regex).exec(opt.input_str, opt.vm, opt.substring, opt.input_file)
-    );
+    match re.is_match(&input) {
+        Ok(result) => println!("{:?}", result),
+        Err(e) => exit_with(&opt.regex, e),
+    }
+}
+
+// 等価性と包含関係を表示
+fn compare(left: &str, right: &str) {
+    let (l, r) = (language(left), language(right));
+    match l.equivalent(&r) {
+        Ok(()) => println!("equivalent"),
+        Err(e) => {
+            println!("not equivalent: {}", e);
+            if l.is_subset(&r).is_ok() {
+                println!("{} is a subset of {}", left, right);
+            }
+            if r.is_subset(&l).is_ok() {
+                println!("{} is a subset of {}", right, left);
+            }
+        }
+    }
+}
+
+// 言語として扱えないパターンはエラーを表示して終了
+fn language(pattern: &str) -> Language {
+    Language::new(pattern).unwrap_or_else(|e| exit_with(pattern, e))
+}
+
+// エラーは標準エラー出力へ表示し、終了コード1で終了
+fn exit_with(pattern: &str, e: impl fmt::Display) -> ! {
+    eprintln!("[main] {}: {}", pattern, e);
+    process::exit(1)
 }
@@ -0,0 +1,136 @@
+#![allow(dead_code)]
+
+use crate::parse::parser::AstTree;
+
+// パターン解析結果（エンジンの自動選択に使用）
+#[derive(Debug, PartialEq)]
+pub struct Analysis {
+    pub literal: Option<String>, // リテラルのみで構成される場合はその文字列
+    pub alternation: Option<Vec<String>>, // リテラルの選択のみで構成される場合はその文字列群
+    pub positions: usize,        // 文字を消費するノード数
+}
+
+impl Analysis {
+    pub fn new(ast: &AstTree) -> Self {
+        Analysis {
+            literal: Self::literal(ast),
+            alternation: Self::alternation(ast),
+            positions: Self::positions(ast),
+        }
+    }
+
+    // DFA状態数の見積もり
+    // 各位置が高々一つのDFA状態に対応する場合の値で、最悪ケースは2^positions
+    pub fn dfa_state_estimate(&self) -> usize {
+        self.positions + 1
+    }
+
+    // リテラルの連結であれば、その文字列を返す
+    fn literal(ast: &AstTree) -> Option<String> {
+        match ast {
+            AstTree::Literal(c) => Some(c.to_string()),
+            AstTree::String(s) => Some(s.clone()),
+            AstTree::Capture(_, _, a) => Self::literal(a),
+            AstTree::Concat(l, r) => {
+                let mut s = Self::literal(l)?;
+                s.push_str(&Self::literal(r)?);
+                Some(s)
+            }
+            _ => None,
+        }
+    }
+
+    // リテラルの選択（a|b|...）であれば、各選択肢の文字列を返す
+    fn alternation(ast: &AstTree) -> Option<Vec<String>> {
+        match ast {
+            AstTree::Or(_, _) => {
+                let mut literals = vec![];
+                Self::collect_alternatives(ast, &mut literals)?;
+                Some(literals)
+            }
+            _ => None,
+        }
+    }
+
+    fn collect_alternatives(ast: &AstTree, literals: &mut Vec<String>) -> Option<()> {
+        match ast {
+            AstTree::Or(l, r) => {
+                Self::collect_alternatives(l, literals)?;
+                Self::collect_alternatives(r, literals)
+            }
+            _ => {
+                literals.push(Self::literal(ast)?);
+                Some(())
+            }
+        }
+    }
+
+    // 文字を消費するノード数
+    fn positions(ast: &AstTree) -> usize {
+        match ast {
+            AstTree::Literal(_)
+            | AstTree::Dot
+            | AstTree::Class(_)
+            | AstTree::Backref(_)
+            | AstTree::Property(_) => 1,
+            AstTree::String(s) => s.chars().count(),
+            AstTree::Concat(l, r) | AstTree::Or(l, r) => Self::positions(l) + Self::positions(r),
+            AstTree::Repeat(a)
+            | AstTree::Plus(a)
+            | AstTree::Question(a)
+            | AstTree::Not(a)
+            | AstTree::Capture(_, _, a)
+            | AstTree::Look(_, a) => Self::positions(a),
+            AstTree::And(l, r) => Self::positions(l) + Self::positions(r),
+            AstTree::Assert(_) => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::lexer::Lexer;
+    use crate::parse::parser::Ast;
+
+    fn analyze(pattern: &str) -> Analysis {
+        Analysis::new(&Ast::new(&Lexer::new(pattern).scan()).parse())
+    }
+
+    #[test]
+    fn test_analysis_literal() {
+        assert_eq!(Some("a".to_string()), analyze("a").literal);
+        assert_eq!(Some("abc".to_string()), analyze("abc").literal);
+        assert_eq!(Some("abc".to_string()), analyze("(ab)c").literal);
+        assert_eq!(None, analyze("ab*").literal);
+        assert_eq!(None, analyze("a|b").literal);
+        assert_eq!(None, analyze("a.").literal);
+    }
+
+    #[test]
+    fn test_analysis_alternation() {
+        assert_eq!(
+            Some(vec![
+                "error".to_string(),
+                "warn".to_string(),
+                "fatal".to_string()
+            ]),
+            analyze("error|warn|fatal").alternation
+        );
+        assert_eq!(
+            Some(vec!["ab".to_string(), "c".to_string()]),
+            analyze("(ab)|c").alternation
+        );
+        assert_eq!(None, analyze("abc").alternation);
+        assert_eq!(None, analyze("ab|c*").alternation);
+        assert_eq!(None, analyze("(ab|c)d").alternation);
+    }
+
+    #[test]
+    fn test_analysis_positions() {
+        assert_eq!(1, analyze("a").positions);
+        assert_eq!(3, analyze("a(b|c)").positions);
+        assert_eq!(4, analyze(".*ab+c?").positions);
+        assert_eq!(5, analyze(".*ab+c?").dfa_state_estimate());
+    }
+}
@@ -11,6 +11,30 @@ pub enum Token {
     Question,
     LeftParen,
     RightParen,
+    And,
+    Not,
+    LeftBracket,
+    RightBracket,
+    Backref(usize),                           // 後方参照（\1〜\9）
+    LookAhead,                                // (?=
+    NegativeLookAhead,                        // (?!
+    LookBehind,                               // (?<=
+    NegativeLookBehind,                       // (?<!
+    NonCapturing,                             // (?:
+    NamedGroup(String),                       // (?P<name> または (?<name>
+    WordBoundary,                             // \b
+    NotWordBoundary,                          // \B
+    StartText,                                // \A
+    EndText,                                  // \z
+    Property { name: String, negated: bool }, // \p{name} または \P{name}
+    PosixClass(String),                       // 括弧内の[:name:]
+    WordChar,                                 // \w
+    Hyphen,                                   // 括弧内の範囲（a-z）
+}
+
+// 文字としてそのまま書ける文字（英数字・空白・'_'）
+pub fn is_literal(c: char) -> bool {
+    c.is_alphanumeric() || c == ' ' || c == '_'
 }
 
 pub struct Lexer<'a> {
@@ -24,11 +48,33 @@ impl<'a> Lexer<'a> {
 
     // 与えられた文字列を解析し、トークン列を返す
     pub fn scan(&self) -> Vec<Token> {
-        self.str
-            .chars()
-            .filter(|c| *c != '\n')
-            .map(|c| match c {
-                s if s.is_alphanumeric() => Token::Character(c),
+        let mut tokens = vec![];
+        let mut chars = self.str.chars().filter(|c| *c != '\n').peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '(' if chars.peek() == Some(&'?') => {
+                    chars.next();
+                    match (chars.next(), chars.peek()) {
+                        (Some('='), _) => Token::LookAhead,
+                        (Some('!'), _) => Token::NegativeLookAhead,
+                        (Some(':'), _) => Token::NonCapturing,
+                        (Some('<'), Some('=')) => {
+                            chars.next();
+                            Token::LookBehind
+                        }
+                        (Some('<'), Some('!')) => {
+                            chars.next();
+                            Token::NegativeLookBehind
+                        }
+                        (Some('P'), Some('<')) => {
+                            chars.next();
+                            Token::NamedGroup(Self::group_name(&mut chars))
+                        }
+                        (Some('<'), _) => Token::NamedGroup(Self::group_name(&mut chars)),
+                        (c, _) => panic!("[Lexer::scan] not support group: (?{:?}", c),
+                    }
+                }
+                s if is_literal(s) => Token::Character(c),
                 '*' => Token::Asterisk,
                 '.' => Token::Dot,
                 '+' => Token::Plus,
@@ -36,9 +82,82 @@ impl<'a> Lexer<'a> {
                 '?' => Token::Question,
                 '(' => Token::LeftParen,
                 ')' => Token::RightParen,
+                '&' => Token::And,
+                '~' => Token::Not,
+                // POSIX EREのアンカー
+                '^' => Token::StartText,
+                '$' => Token::EndText,
+                '[' if chars.peek() == Some(&':') => {
+                    chars.next();
+                    Token::PosixClass(Self::posix_class_name(&mut chars))
+                }
+                '[' => Token::LeftBracket,
+                '-' => Token::Hyphen,
+                ']' => Token::RightBracket,
+                '\\' => match chars.next() {
+                    Some(n @ '1'..='9') => Token::Backref(n as usize - '0' as usize),
+                    Some('b') => Token::WordBoundary,
+                    Some('B') => Token::NotWordBoundary,
+                    Some('A') => Token::StartText,
+                    Some('z') => Token::EndText,
+                    Some('w') => Token::WordChar,
+                    Some(p @ 'p') | Some(p @ 'P') => Token::Property {
+                        name: Self::property_name(&mut chars),
+                        negated: p == 'P',
+                    },
+                    n => panic!("[Lexer::scan] not support escape: {:?}", n),
+                },
                 _ => panic!("[Lexer::scan] not support char: {:?}", c),
-            })
-            .collect()
+            };
+            tokens.push(token);
+        }
+        tokens
+    }
+
+    // '>'までのグループ名（英数字と'_'）
+    fn group_name<I: Iterator<Item = char>>(chars: &mut I) -> String {
+        let mut name = String::new();
+        for c in chars {
+            match c {
+                '>' if !name.is_empty() => return name,
+                c if c.is_alphanumeric() || c == '_' => name.push(c),
+                _ => panic!("[Lexer::group_name] invalid group name: {:?}", c),
+            }
+        }
+        panic!("[Lexer::group_name] cannot find '>' ({:?})", name)
+    }
+
+    // ':]'までのPOSIXクラス名（英字）
+    fn posix_class_name<I: Iterator<Item = char>>(chars: &mut I) -> String {
+        let mut name = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                ':' if !name.is_empty() => match chars.next() {
+                    Some(']') => return name,
+                    c => panic!("[Lexer::posix_class_name] expect ']', but {:?}", c),
+                },
+                c if c.is_ascii_alphabetic() => name.push(c),
+                _ => panic!("[Lexer::posix_class_name] invalid class name: {:?}", c),
+            }
+        }
+        panic!("[Lexer::posix_class_name] cannot find ':]' ({:?})", name)
+    }
+
+    // '{'から'}'までのプロパティ名（英数字と'_'）
+    fn property_name<I: Iterator<Item = char>>(chars: &mut I) -> String {
+        match chars.next() {
+            Some('{') => {}
+            c => panic!("[Lexer::property_name] expect '{{', but {:?}", c),
+        }
+        let mut name = String::new();
+        for c in chars {
+            match c {
+                '}' if !name.is_empty() => return name,
+                c if c.is_alphanumeric() || c == '_' => name.push(c),
+                _ => panic!("[Lexer::property_name] invalid property name: {:?}", c),
+            }
+        }
+        panic!("[Lexer::property_name] cannot find '}}' ({:?})", name)
     }
 }
 
@@ -67,6 +186,203 @@ mod test {
         assert_eq!(Token::Character('d'), tokens[3]);
     }
 
+    #[test]
+    fn test_scan_set_operator() {
+        let tokens = Lexer::new("a&~b").scan();
+
+        assert_eq!(
+            vec![
+                Token::Character('a'),
+                Token::And,
+                Token::Not,
+                Token::Character('b')
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_scan_bracket() {
+        let tokens = Lexer::new("[ab]").scan();
+
+        assert_eq!(
+            vec![
+                Token::LeftBracket,
+                Token::Character('a'),
+                Token::Character('b'),
+                Token::RightBracket
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_scan_backref() {
+        let tokens = Lexer::new(r"(a)\1").scan();
+
+        assert_eq!(
+            vec![
+                Token::LeftParen,
+                Token::Character('a'),
+                Token::RightParen,
+                Token::Backref(1)
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_scan_lookaround() {
+        let tokens = Lexer::new("(?=a)(?!b)(?<=c)(?<!d)").scan();
+
+        assert_eq!(
+            vec![
+                Token::LookAhead,
+                Token::Character('a'),
+                Token::RightParen,
+                Token::NegativeLookAhead,
+                Token::Character('b'),
+                Token::RightParen,
+                Token::LookBehind,
+                Token::Character('c'),
+                Token::RightParen,
+                Token::NegativeLookBehind,
+                Token::Character('d'),
+                Token::RightParen,
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_scan_assertion() {
+        let tokens = Lexer::new(r"\A\ba\B\z").scan();
+
+        assert_eq!(
+            vec![
+                Token::StartText,
+                Token::WordBoundary,
+                Token::Character('a'),
+                Token::NotWordBoundary,
+                Token::EndText,
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_scan_anchor() {
+        let tokens = Lexer::new("^a$").scan();
+
+        assert_eq!(
+            vec![Token::StartText, Token::Character('a'), Token::EndText],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_scan_range() {
+        let tokens = Lexer::new("[a-c]").scan();
+
+        assert_eq!(
+            vec![
+                Token::LeftBracket,
+                Token::Character('a'),
+                Token::Hyphen,
+                Token::Character('c'),
+                Token::RightBracket,
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_scan_word_char() {
+        let tokens = Lexer::new(r"(\w+) \1").scan();
+
+        assert_eq!(
+            vec![
+                Token::LeftParen,
+                Token::WordChar,
+                Token::Plus,
+                Token::RightParen,
+                Token::Character(' '),
+                Token::Backref(1)
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_scan_property() {
+        let tokens = Lexer::new(r"\p{Greek}[a\P{White_Space}]").scan();
+
+        assert_eq!(
+            vec![
+                Token::Property {
+                    name: "Greek".to_string(),
+                    negated: false
+                },
+                Token::LeftBracket,
+                Token::Character('a'),
+                Token::Property {
+                    name: "White_Space".to_string(),
+                    negated: true
+                },
+                Token::RightBracket,
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "[Lexer::property_name] cannot find '}'")]
+    fn test_scan_property_unclosed() {
+        Lexer::new(r"\p{Greek").scan();
+    }
+
+    #[test]
+    fn test_scan_posix_class() {
+        let tokens = Lexer::new("[[:alpha:]x[:digit:]]").scan();
+
+        assert_eq!(
+            vec![
+                Token::LeftBracket,
+                Token::PosixClass("alpha".to_string()),
+                Token::Character('x'),
+                Token::PosixClass("digit".to_string()),
+                Token::RightBracket,
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "[Lexer::posix_class_name] cannot find ':]'")]
+    fn test_scan_posix_class_unclosed() {
+        Lexer::new("[[:alpha").scan();
+    }
+
+    #[test]
+    fn test_scan_group() {
+        let tokens = Lexer::new("(?:a)(?P<year>b)(?<m_1>c)").scan();
+
+        assert_eq!(
+            vec![
+                Token::NonCapturing,
+                Token::Character('a'),
+                Token::RightParen,
+                Token::NamedGroup("year".to_string()),
+                Token::Character('b'),
+                Token::RightParen,
+                Token::NamedGroup("m_1".to_string()),
+                Token::Character('c'),
+                Token::RightParen,
+            ],
+            tokens
+        );
+    }
+
     #[test]
     fn test_scan_number() {
         let tokens = Lexer::new("a1c0").scan();
@@ -0,0 +1,213 @@
+#![allow(dead_code)]
+
+use crate::parse::parser::AstTree;
+
+// マッチする文字列が必ず含むリテラル
+#[derive(Debug, PartialEq, Clone)]
+pub struct Literals {
+    pub exact: Option<String>, // 一つの文字列にのみマッチする場合はその文字列
+    pub prefix: String,        // 必ず先頭に現れる文字列
+    pub suffix: String,        // 必ず末尾に現れる文字列
+    pub required: Vec<String>, // 必ずどこかに現れる文字列
+}
+
+impl Literals {
+    pub fn new(ast: &AstTree) -> Self {
+        let mut literals = Self::extract(ast);
+
+        // 先頭・末尾も必須リテラルとして扱い、他の必須リテラルに含まれるものは除く
+        let mut required = literals.required.clone();
+        required.push(literals.prefix.clone());
+        required.push(literals.suffix.clone());
+        required.retain(|s| !s.is_empty());
+        required.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        required.dedup();
+
+        let mut minimal: Vec<String> = vec![];
+        for r in required {
+            if !minimal.iter().any(|m| m.contains(r.as_str())) {
+                minimal.push(r);
+            }
+        }
+        literals.required = minimal;
+        literals
+    }
+
+    // 何もわからない場合
+    fn empty() -> Self {
+        Literals {
+            exact: None,
+            prefix: String::new(),
+            suffix: String::new(),
+            required: vec![],
+        }
+    }
+
+    fn extract(ast: &AstTree) -> Self {
+        match ast {
+            AstTree::Literal(c) => Self::extract(&AstTree::String(c.to_string())),
+            AstTree::String(s) => Literals {
+                exact: Some(s.clone()),
+                prefix: s.clone(),
+                suffix: s.clone(),
+                required: vec![],
+            },
+            AstTree::Class(chars) if chars.len() == 1 => Self::extract(&AstTree::Literal(chars[0])),
+            AstTree::Concat(l, r) => {
+                let l = Self::extract(l);
+                let r = Self::extract(r);
+
+                // 左の末尾と右の先頭は連続して現れる
+                let mut required = l.required.clone();
+                required.extend(r.required.clone());
+                required.push(format!("{}{}", l.suffix, r.prefix));
+
+                Literals {
+                    exact: match (&l.exact, &r.exact) {
+                        (Some(a), Some(b)) => Some(format!("{}{}", a, b)),
+                        _ => None,
+                    },
+                    prefix: match &l.exact {
+                        Some(a) => format!("{}{}", a, r.prefix),
+                        None => l.prefix,
+                    },
+                    suffix: match &r.exact {
+                        Some(b) => format!("{}{}", l.suffix, b),
+                        None => r.suffix,
+                    },
+                    required,
+                }
+            }
+            AstTree::Or(l, r) => {
+                let l = Self::extract(l);
+                let r = Self::extract(r);
+
+                Literals {
+                    exact: if l.exact == r.exact { l.exact } else { None },
+                    prefix: common_prefix(&l.prefix, &r.prefix),
+                    suffix: common_suffix(&l.suffix, &r.suffix),
+                    required: vec![],
+                }
+            }
+            AstTree::Plus(a) => {
+                // 一回以上の繰り返しのため、先頭・末尾・必須リテラルは保たれる
+                let a = Self::extract(a);
+                Literals { exact: None, ..a }
+            }
+            AstTree::And(l, r) => {
+                // 両方にマッチするため、双方の先頭・末尾は同じ文字列の先頭・末尾となる
+                // （一方が他方を含まない場合はマッチする文字列が存在しない）
+                let l = Self::extract(l);
+                let r = Self::extract(r);
+
+                let mut required = l.required;
+                required.extend(r.required);
+                Literals {
+                    exact: None,
+                    prefix: longer(l.prefix, r.prefix),
+                    suffix: longer(l.suffix, r.suffix),
+                    required,
+                }
+            }
+            AstTree::Capture(_, _, a) => Self::extract(a),
+            // 参照先の文字列は実行時まで決まらない
+            AstTree::Repeat(_)
+            | AstTree::Question(_)
+            | AstTree::Dot
+            | AstTree::Not(_)
+            | AstTree::Class(_)
+            | AstTree::Backref(_)
+            | AstTree::Look(_, _)
+            | AstTree::Assert(_)
+            | AstTree::Property(_) => Self::empty(),
+        }
+    }
+}
+
+// 長い方の文字列
+fn longer(a: String, b: String) -> String {
+    if b.len() > a.len() {
+        b
+    } else {
+        a
+    }
+}
+
+// 共通の先頭文字列
+fn common_prefix(a: &str, b: &str) -> String {
+    a.chars()
+        .zip(b.chars())
+        .take_while(|(x, y)| x == y)
+        .map(|(x, _)| x)
+        .collect()
+}
+
+// 共通の末尾文字列
+fn common_suffix(a: &str, b: &str) -> String {
+    let rev: String = a
+        .chars()
+        .rev()
+        .zip(b.chars().rev())
+        .take_while(|(x, y)| x == y)
+        .map(|(x, _)| x)
+        .collect();
+    rev.chars().rev().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::lexer::Lexer;
+    use crate::parse::parser::Ast;
+
+    fn literals(pattern: &str) -> Literals {
+        Literals::new(&Ast::new(&Lexer::new(pattern).scan()).parse())
+    }
+
+    #[test]
+    fn test_literals_prefix_suffix() {
+        {
+            let l = literals("abc");
+
+            assert_eq!(Some("abc".to_string()), l.exact);
+            assert_eq!("abc", l.prefix);
+            assert_eq!("abc", l.suffix);
+            assert_eq!(vec!["abc".to_string()], l.required);
+        }
+        {
+            let l = literals("ab*c");
+
+            assert_eq!(None, l.exact);
+            assert_eq!("a", l.prefix);
+            assert_eq!("c", l.suffix);
+        }
+        {
+            let l = literals("hello(world|word)");
+
+            assert_eq!("hellowor", l.prefix);
+            assert_eq!("d", l.suffix);
+        }
+        {
+            let l = literals("(ab)+c");
+
+            assert_eq!("ab", l.prefix);
+            assert_eq!("abc", l.suffix);
+        }
+    }
+
+    #[test]
+    fn test_literals_required() {
+        assert_eq!(
+            vec!["error".to_string(), "x".to_string()],
+            literals(".*error.*x").required
+        );
+        assert_eq!(vec!["foo".to_string()], literals("a*foo(b|c)*").required);
+        assert!(literals("a*|b").required.is_empty());
+        assert!(literals("(a|b)c?").required.is_empty());
+        assert_eq!(
+            vec!["abc".to_string(), "xy".to_string()],
+            literals("(ab.*xy)&(abc.*)").required
+        );
+        assert!(literals("~(abc)").required.is_empty());
+    }
+}
@@ -1,2 +1,7 @@
+pub mod analysis;
 pub mod lexer;
+pub mod literal;
+pub mod optimize;
 pub mod parser;
+pub mod posix;
+pub mod printer;
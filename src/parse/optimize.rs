@@ -0,0 +1,225 @@
+#![allow(dead_code)]
+
+use crate::parse::parser::AstTree;
+
+impl AstTree {
+    // コンパイル前の最適化（マッチする文字列と、選択肢の優先順位は変えない）
+    // - 連結を右結合の一列にまとめ、連続するリテラルを文字列にする
+    // - 隣り合う選択肢の共通の接頭辞をくくり出す（abc|abd = ab[cd]）
+    // - 隣り合う一文字の選択肢を文字クラスにする（a|b|c = [abc]）
+    // - 繰り返しの入れ子をまとめる（(a*)* = (a+)* = (a?)* = a*）
+    pub fn optimize(self) -> AstTree {
+        match self {
+            AstTree::Concat(_, _) => {
+                let mut factors = vec![];
+                for factor in self.factors() {
+                    factors.extend(factor.optimize().factors());
+                }
+                concat(factors)
+            }
+            AstTree::Or(_, _) => {
+                let alternatives = self
+                    .alternatives()
+                    .into_iter()
+                    .map(|a| a.optimize())
+                    .collect();
+                alternate(alternatives)
+            }
+            AstTree::Repeat(a) => match a.optimize() {
+                AstTree::Repeat(x) | AstTree::Plus(x) | AstTree::Question(x) => AstTree::Repeat(x),
+                x => AstTree::Repeat(Box::new(x)),
+            },
+            AstTree::Plus(a) => match a.optimize() {
+                AstTree::Repeat(x) | AstTree::Question(x) => AstTree::Repeat(x),
+                AstTree::Plus(x) => AstTree::Plus(x),
+                x => AstTree::Plus(Box::new(x)),
+            },
+            AstTree::Question(a) => match a.optimize() {
+                AstTree::Repeat(x) | AstTree::Plus(x) => AstTree::Repeat(x),
+                AstTree::Question(x) => AstTree::Question(x),
+                x => AstTree::Question(Box::new(x)),
+            },
+            AstTree::And(l, r) => AstTree::And(Box::new(l.optimize()), Box::new(r.optimize())),
+            AstTree::Not(a) => AstTree::Not(Box::new(a.optimize())),
+            AstTree::Capture(n, name, a) => AstTree::Capture(n, name, Box::new(a.optimize())),
+            AstTree::Look(look, a) => AstTree::Look(look, Box::new(a.optimize())),
+            AstTree::Class(chars) if chars.len() == 1 => AstTree::Literal(chars[0]),
+            ast => ast,
+        }
+    }
+
+    // 連結の各要素（文字列は一文字ずつに分ける）
+    fn factors(self) -> Vec<AstTree> {
+        match self {
+            AstTree::Concat(l, r) => {
+                let mut factors = l.factors();
+                factors.extend(r.factors());
+                factors
+            }
+            AstTree::String(s) => s.chars().map(AstTree::Literal).collect(),
+            ast => vec![ast],
+        }
+    }
+
+    // 選択の各選択肢
+    fn alternatives(self) -> Vec<AstTree> {
+        match self {
+            AstTree::Or(l, r) => {
+                let mut alternatives = l.alternatives();
+                alternatives.extend(r.alternatives());
+                alternatives
+            }
+            ast => vec![ast],
+        }
+    }
+
+    // 一文字にのみマッチする場合はその文字の集合
+    fn chars(&self) -> Option<Vec<char>> {
+        match self {
+            AstTree::Literal(c) => Some(vec![*c]),
+            AstTree::Class(chars) => Some(chars.clone()),
+            _ => None,
+        }
+    }
+}
+
+// 連続するリテラルを文字列にまとめ、右結合の連結にする
+fn concat(factors: Vec<AstTree>) -> AstTree {
+    let mut merged: Vec<AstTree> = vec![];
+    for factor in factors {
+        match (merged.last_mut(), factor) {
+            (Some(AstTree::String(s)), AstTree::Literal(c)) => s.push(c),
+            (Some(last @ AstTree::Literal(_)), AstTree::Literal(c)) => {
+                if let AstTree::Literal(prev) = *last {
+                    *last = AstTree::String([prev, c].iter().collect());
+                }
+            }
+            (_, factor) => merged.push(factor),
+        }
+    }
+    fold(merged, AstTree::Concat)
+}
+
+// 選択肢を整理して右結合の選択にする
+fn alternate(alternatives: Vec<AstTree>) -> AstTree {
+    // 後に現れる重複した選択肢は選ばれないため除く
+    let mut unique: Vec<AstTree> = vec![];
+    for a in alternatives {
+        if !unique.contains(&a) {
+            unique.push(a);
+        }
+    }
+
+    // 先頭の要素が等しい隣り合う選択肢をまとめ、共通の接頭辞をくくり出す
+    let mut factored: Vec<AstTree> = vec![];
+    let mut group: Vec<Vec<AstTree>> = vec![];
+    for a in unique {
+        let factors = a.factors();
+        if group.last().is_some_and(|g| g[0] != factors[0]) {
+            factored.extend(factor_prefix(std::mem::take(&mut group)));
+        }
+        group.push(factors);
+    }
+    factored.extend(factor_prefix(group));
+
+    // 隣り合う一文字の選択肢を文字クラスにまとめる
+    let mut merged: Vec<AstTree> = vec![];
+    for a in factored {
+        match (merged.last().and_then(|m| m.chars()), a.chars()) {
+            (Some(mut chars), Some(other)) => {
+                chars.extend(other);
+                chars.sort_unstable();
+                chars.dedup();
+                *merged.last_mut().unwrap() = AstTree::Class(chars);
+            }
+            _ => merged.push(a),
+        }
+    }
+    fold(merged, AstTree::Or)
+}
+
+// 共通の接頭辞をくくり出す（残りが空になる選択肢がある場合はくくり出さない）
+fn factor_prefix(group: Vec<Vec<AstTree>>) -> Vec<AstTree> {
+    let min = group.iter().map(|g| g.len()).min().unwrap_or(0);
+    let prefix = (0..min)
+        .take_while(|&i| group.iter().all(|g| g[i] == group[0][i]))
+        .count();
+    if group.len() < 2 || prefix == min {
+        return group.into_iter().map(concat).collect();
+    }
+
+    let mut factors = group[0][..prefix].to_vec();
+    let rest = group
+        .into_iter()
+        .map(|g| concat(g[prefix..].to_vec()))
+        .collect();
+    factors.extend(alternate(rest).factors());
+    vec![concat(factors)]
+}
+
+fn fold(mut asts: Vec<AstTree>, node: fn(Box<AstTree>, Box<AstTree>) -> AstTree) -> AstTree {
+    let last = asts.pop().expect("[optimize::fold] empty items");
+    asts.into_iter()
+        .rev()
+        .fold(last, |acc, ast| node(Box::new(ast), Box::new(acc)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::lexer::Lexer;
+    use crate::parse::parser::{Ast, AstTree};
+
+    fn optimize(pattern: &str) -> String {
+        Ast::new(&Lexer::new(pattern).scan())
+            .parse()
+            .optimize()
+            .to_string()
+    }
+
+    #[test]
+    fn test_optimize_concat() {
+        let ast = Ast::new(&Lexer::new("(?:ab)(?:cd)e*").scan())
+            .parse()
+            .optimize();
+        assert_eq!(
+            AstTree::Concat(
+                Box::new(AstTree::String("abcd".to_string())),
+                Box::new(AstTree::Repeat(Box::new(AstTree::Literal('e'))))
+            ),
+            ast
+        );
+        assert_eq!("abc.d", optimize("a(?:bc).d"));
+    }
+
+    #[test]
+    fn test_optimize_alternation() {
+        assert_eq!("ab[cd]", optimize("abc|abd"));
+        assert_eq!("[abc]", optimize("a|b|c"));
+        assert_eq!("[ab]|cd|e", optimize("a|b|cd|e"));
+        assert_eq!("a(?:bc|d+)|x", optimize("abc|ad+|x"));
+        assert_eq!("ab", optimize("ab|ab"));
+        // 順序が変わる場合はくくり出さない
+        assert_eq!("ab|c|ad", optimize("ab|c|ad"));
+        // 残りが空になる場合はくくり出さない
+        assert_eq!("ab|abc", optimize("ab|abc"));
+    }
+
+    #[test]
+    fn test_optimize_capture() {
+        // グループの範囲は変えない
+        assert_eq!("(ab)(cd)e*", optimize("(ab)(cd)e*"));
+        assert_eq!("(a*)*", optimize("(a*)*"));
+        assert_eq!("(?P<x>[ab])|c", optimize("(?P<x>a|b)|c"));
+    }
+
+    #[test]
+    fn test_optimize_repeat() {
+        assert_eq!("a*", optimize("(?:a*)*"));
+        assert_eq!("a*", optimize("(?:a+)*"));
+        assert_eq!("a*", optimize("(?:a?)*"));
+        assert_eq!("a*", optimize("(?:a*)+"));
+        assert_eq!("a+", optimize("(?:a+)+"));
+        assert_eq!("a*", optimize("(?:a+)?"));
+        assert_eq!("[ab]*c", optimize("(?:(?:a|b)*)*c"));
+    }
+}
@@ -1,10 +1,13 @@
 #![allow(dead_code)]
 
-use crate::parse::lexer::Token;
+use crate::error::Error;
+use crate::parse::lexer::{is_literal, Token};
+use crate::parse::posix;
+use crate::unicode::Property;
 use std::boxed::Box;
 
 // AST
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum AstTree {
     Concat(Box<AstTree>, Box<AstTree>),
     Or(Box<AstTree>, Box<AstTree>),
@@ -13,6 +16,300 @@ pub enum AstTree {
     Dot,
     Plus(Box<AstTree>),
     Question(Box<AstTree>),
+    And(Box<AstTree>, Box<AstTree>),              // 共通部分
+    Not(Box<AstTree>),                            // 補集合
+    String(String),                               // 連続するリテラル（最適化で作成）
+    Class(Vec<char>),                             // 文字クラス（整列・重複除去済み）
+    Capture(usize, Option<String>, Box<AstTree>), // グループ（番号は左括弧の順、名前付きの場合は名前）
+    Backref(usize),                               // 後方参照
+    Look(Look, Box<AstTree>),                     // 先読み・後読み（文字を消費しない）
+    Assert(Assertion),                            // 単語境界・入力の先頭と末尾（文字を消費しない）
+    Property(Property),                           // Unicodeプロパティ（\p{..}・\P{..}）
+}
+
+// 先読み・後読みの種類
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Look {
+    Ahead,          // (?=r)
+    NegativeAhead,  // (?!r)
+    Behind,         // (?<=r)
+    NegativeBehind, // (?<!r)
+}
+
+impl Look {
+    pub fn is_behind(&self) -> bool {
+        matches!(self, Look::Behind | Look::NegativeBehind)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        matches!(self, Look::NegativeAhead | Look::NegativeBehind)
+    }
+}
+
+// 前後の文字のみを参照する表明
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Assertion {
+    WordBoundary,    // \b
+    NotWordBoundary, // \B
+    StartText,       // \A
+    EndText,         // \z
+}
+
+impl Assertion {
+    // 前後の文字の種類で成り立つか
+    pub fn is_match(&self, prev: CharKind, next: CharKind) -> bool {
+        match self {
+            Assertion::WordBoundary => (prev == CharKind::Word) != (next == CharKind::Word),
+            Assertion::NotWordBoundary => (prev == CharKind::Word) == (next == CharKind::Word),
+            Assertion::StartText => prev == CharKind::Edge,
+            Assertion::EndText => next == CharKind::Edge,
+        }
+    }
+
+    // 逆向きに照合する場合の表明（前後の文字が入れ替わる）
+    pub fn reverse(&self) -> Assertion {
+        match self {
+            Assertion::StartText => Assertion::EndText,
+            Assertion::EndText => Assertion::StartText,
+            a => *a,
+        }
+    }
+
+    // 直前の文字を参照するか
+    pub fn looks_behind(&self) -> bool {
+        !matches!(self, Assertion::EndText)
+    }
+}
+
+// 位置の前後にある文字の種類
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum CharKind {
+    Edge,  // 入力の先頭・末尾（文字がない）
+    Word,  // 単語構成文字（英数字と'_'）
+    Other, // それ以外の文字
+}
+
+impl CharKind {
+    pub const ALL: [CharKind; 3] = [CharKind::Edge, CharKind::Word, CharKind::Other];
+
+    pub fn of(c: Option<char>) -> CharKind {
+        match c {
+            None => CharKind::Edge,
+            Some(c) if c.is_alphanumeric() || c == '_' => CharKind::Word,
+            Some(_) => CharKind::Other,
+        }
+    }
+}
+
+impl AstTree {
+    // 大文字・小文字を区別しないASTへ変換
+    pub fn ignore_case(&self) -> AstTree {
+        match self {
+            AstTree::Concat(l, r) => {
+                AstTree::Concat(Box::new(l.ignore_case()), Box::new(r.ignore_case()))
+            }
+            AstTree::Or(l, r) => AstTree::Or(Box::new(l.ignore_case()), Box::new(r.ignore_case())),
+            AstTree::Repeat(a) => AstTree::Repeat(Box::new(a.ignore_case())),
+            AstTree::Plus(a) => AstTree::Plus(Box::new(a.ignore_case())),
+            AstTree::Question(a) => AstTree::Question(Box::new(a.ignore_case())),
+            AstTree::Literal(c) => {
+                // 大文字・小文字が一文字ずつ存在する場合のみ、選択へ展開
+                let mut lower = c.to_lowercase();
+                let mut upper = c.to_uppercase();
+                match (lower.next(), lower.next(), upper.next(), upper.next()) {
+                    (Some(l), None, Some(u), None) if l != u => {
+                        AstTree::Or(Box::new(AstTree::Literal(l)), Box::new(AstTree::Literal(u)))
+                    }
+                    _ => AstTree::Literal(*c),
+                }
+            }
+            AstTree::Dot => AstTree::Dot,
+            AstTree::And(l, r) => {
+                AstTree::And(Box::new(l.ignore_case()), Box::new(r.ignore_case()))
+            }
+            AstTree::Not(a) => AstTree::Not(Box::new(a.ignore_case())),
+            AstTree::String(_) => self.expand().ignore_case(),
+            AstTree::Capture(n, name, a) => {
+                AstTree::Capture(*n, name.clone(), Box::new(a.ignore_case()))
+            }
+            // 参照先の文字列と完全に一致する必要がある
+            AstTree::Backref(n) => AstTree::Backref(*n),
+            AstTree::Look(look, a) => AstTree::Look(*look, Box::new(a.ignore_case())),
+            AstTree::Assert(a) => AstTree::Assert(*a),
+            // プロパティの集合はそのまま（大文字・小文字の対応は加えない）
+            AstTree::Property(p) => AstTree::Property(*p),
+            AstTree::Class(chars) => {
+                // 各文字の大文字・小文字をクラスに加える
+                let mut folded: Vec<char> = chars
+                    .iter()
+                    .flat_map(|c| match AstTree::Literal(*c).ignore_case() {
+                        AstTree::Or(l, u) => vec![*c, l.literal(), u.literal()],
+                        _ => vec![*c],
+                    })
+                    .collect();
+                folded.sort_unstable();
+                folded.dedup();
+                AstTree::Class(folded)
+            }
+        }
+    }
+
+    // 文字列・文字クラスを、リテラルの連結・選択へ展開（それ以外はそのまま）
+    pub fn expand(&self) -> AstTree {
+        let fold = |chars: Vec<char>, node: fn(Box<AstTree>, Box<AstTree>) -> AstTree| {
+            chars
+                .into_iter()
+                .rev()
+                .map(AstTree::Literal)
+                .reduce(|acc, a| node(Box::new(a), Box::new(acc)))
+                .expect("[AstTree::expand] empty string or class")
+        };
+        match self {
+            AstTree::String(s) => fold(s.chars().collect(), AstTree::Concat),
+            AstTree::Class(chars) => fold(chars.clone(), AstTree::Or),
+            _ => self.clone(),
+        }
+    }
+
+    fn literal(&self) -> char {
+        match self {
+            AstTree::Literal(c) => *c,
+            _ => panic!("[AstTree::literal] not a literal ({:?})", self),
+        }
+    }
+
+    // 共通部分・補集合を含むか（オートマトン上の演算が必要）
+    pub fn has_set_operator(&self) -> bool {
+        match self {
+            AstTree::And(_, _) | AstTree::Not(_) => true,
+            AstTree::Concat(l, r) | AstTree::Or(l, r) => {
+                l.has_set_operator() || r.has_set_operator()
+            }
+            AstTree::Repeat(a)
+            | AstTree::Plus(a)
+            | AstTree::Question(a)
+            | AstTree::Capture(_, _, a)
+            | AstTree::Look(_, a) => a.has_set_operator(),
+            AstTree::Literal(_)
+            | AstTree::Dot
+            | AstTree::String(_)
+            | AstTree::Class(_)
+            | AstTree::Backref(_)
+            | AstTree::Assert(_)
+            | AstTree::Property(_) => false,
+        }
+    }
+
+    // 子ノード
+    pub fn children(&self) -> Vec<&AstTree> {
+        match self {
+            AstTree::Concat(l, r) | AstTree::Or(l, r) | AstTree::And(l, r) => vec![l, r],
+            AstTree::Repeat(a)
+            | AstTree::Plus(a)
+            | AstTree::Question(a)
+            | AstTree::Not(a)
+            | AstTree::Capture(_, _, a)
+            | AstTree::Look(_, a) => vec![a],
+            AstTree::Literal(_)
+            | AstTree::Dot
+            | AstTree::String(_)
+            | AstTree::Class(_)
+            | AstTree::Backref(_)
+            | AstTree::Assert(_)
+            | AstTree::Property(_) => vec![],
+        }
+    }
+
+    // グループの番号と名前（番号順）
+    pub fn groups(&self) -> Vec<(usize, Option<String>)> {
+        let mut groups = match self {
+            AstTree::Capture(n, name, _) => vec![(*n, name.clone())],
+            _ => vec![],
+        };
+        for a in self.children() {
+            groups.extend(a.groups());
+        }
+        groups.sort();
+        groups
+    }
+
+    // 後方参照を含むか（バックトラック型VMでのみ扱える）
+    pub fn has_backref(&self) -> bool {
+        matches!(self, AstTree::Backref(_)) || self.children().iter().any(|a| a.has_backref())
+    }
+
+    // 先読み・後読みを含むか（バックトラック型VMでのみ扱える）
+    pub fn has_lookaround(&self) -> bool {
+        matches!(self, AstTree::Look(_, _)) || self.children().iter().any(|a| a.has_lookaround())
+    }
+
+    // 単語境界・入力の先頭と末尾の表明を含むか
+    pub fn has_assertion(&self) -> bool {
+        matches!(self, AstTree::Assert(_)) || self.children().iter().any(|a| a.has_assertion())
+    }
+
+    // Unicodeプロパティを含むか
+    pub fn has_property(&self) -> bool {
+        matches!(self, AstTree::Property(_)) || self.children().iter().any(|a| a.has_property())
+    }
+
+    // マッチ位置より前の文字を参照するか（入力を途中から照合できない）
+    pub fn looks_behind(&self) -> bool {
+        match self {
+            AstTree::Look(look, _) if look.is_behind() => true,
+            AstTree::Assert(a) => a.looks_behind(),
+            _ => self.children().iter().any(|a| a.looks_behind()),
+        }
+    }
+
+    // マッチする文字列の長さ（文字数）の範囲（上限がない場合はNone）
+    pub fn width(&self) -> (usize, Option<usize>) {
+        match self {
+            AstTree::Literal(_) | AstTree::Dot | AstTree::Class(_) | AstTree::Property(_) => {
+                (1, Some(1))
+            }
+            AstTree::String(s) => (s.chars().count(), Some(s.chars().count())),
+            AstTree::Concat(l, r) => {
+                let (l, r) = (l.width(), r.width());
+                (l.0 + r.0, l.1.zip(r.1).map(|(a, b)| a + b))
+            }
+            AstTree::Or(l, r) => {
+                let (l, r) = (l.width(), r.width());
+                (l.0.min(r.0), l.1.zip(r.1).map(|(a, b)| a.max(b)))
+            }
+            AstTree::Repeat(a) => match a.width() {
+                (_, Some(0)) => (0, Some(0)),
+                _ => (0, None),
+            },
+            AstTree::Plus(a) => match a.width() {
+                (min, Some(0)) => (min, Some(0)),
+                (min, _) => (min, None),
+            },
+            AstTree::Question(a) => (0, a.width().1),
+            AstTree::Capture(_, _, a) => a.width(),
+            AstTree::Look(_, _) | AstTree::Assert(_) => (0, Some(0)),
+            // 参照先の長さは実行時まで決まらない
+            AstTree::Backref(_) | AstTree::And(_, _) | AstTree::Not(_) => (0, None),
+        }
+    }
+
+    // 部分文字列へマッチするよう、前後に.*を付与
+    pub fn unanchored(self) -> AstTree {
+        let any = Box::new(AstTree::Repeat(Box::new(AstTree::Dot)));
+        AstTree::Concat(any, Box::new(self.trailing_any()))
+    }
+
+    // 先頭からの部分文字列へマッチするよう、後ろに.*を付与
+    pub fn trailing_any(self) -> AstTree {
+        let any = Box::new(AstTree::Repeat(Box::new(AstTree::Dot)));
+        AstTree::Concat(Box::new(self), any)
+    }
+
+    // 末尾までの部分文字列へマッチするよう、前に.*を付与
+    pub fn leading_any(self) -> AstTree {
+        let any = Box::new(AstTree::Repeat(Box::new(AstTree::Dot)));
+        AstTree::Concat(any, Box::new(self))
+    }
 }
 
 // VMエンジンで使用
@@ -20,25 +317,54 @@ pub enum AstTree {
 pub struct Ast<'a> {
     tokens: &'a [Token],
     index: usize,
+    groups: usize,          // 読み取ったグループ数
+    names: Vec<String>,     // 読み取ったグループ名
+    referenced: Vec<usize>, // 後方参照されるグループ番号
 }
 
 impl<'a> Ast<'a> {
     pub fn new(tokens: &'a [Token]) -> Self {
-        Ast { tokens, index: 0 }
+        let referenced = tokens
+            .iter()
+            .filter_map(|t| match t {
+                Token::Backref(n) => Some(*n),
+                _ => None,
+            })
+            .collect();
+        Ast {
+            tokens,
+            index: 0,
+            groups: 0,
+            names: vec![],
+            referenced,
+        }
     }
 
-    // トークンをパースし、ASTツリー生成
+    // トークンをパースし、ASTツリー生成（存在しないグループへの後方参照はパニック）
     pub fn parse(&mut self) -> AstTree {
-        self.expr()
+        self.try_parse()
+            .unwrap_or_else(|e| panic!("[Parser::parse] {}", e))
+    }
+
+    // トークンをパースし、ASTツリー生成（存在しないグループへの後方参照はエラー）
+    pub fn try_parse(&mut self) -> Result<AstTree, Error> {
+        let ast = self.expr();
+        match self.referenced.iter().find(|n| **n > self.groups) {
+            Some(n) => Err(Error::InvalidBackreference {
+                group: *n,
+                groups: self.groups,
+            }),
+            None => Ok(ast),
+        }
     }
 
     fn expr(&mut self) -> AstTree {
         self.sub_expr()
     }
 
-    // seq '|' seq
+    // and_expr '|' sub_expr
     fn sub_expr(&mut self) -> AstTree {
-        let a1 = self.seq();
+        let a1 = self.and_expr();
         if self.index >= self.tokens.len() {
             return a1;
         }
@@ -46,13 +372,30 @@ impl<'a> Ast<'a> {
         match self.tokens[self.index] {
             Token::Or => {
                 self.next();
-                let a2 = self.seq();
+                let a2 = self.sub_expr();
                 AstTree::Or(Box::new(a1), Box::new(a2))
             }
             _ => a1,
         }
     }
 
+    // seq '&' and_expr
+    fn and_expr(&mut self) -> AstTree {
+        let a1 = self.seq();
+        if self.index >= self.tokens.len() {
+            return a1;
+        }
+
+        match self.tokens[self.index] {
+            Token::And => {
+                self.next();
+                let a2 = self.and_expr();
+                AstTree::And(Box::new(a1), Box::new(a2))
+            }
+            _ => a1,
+        }
+    }
+
     // sub_seq seq
     fn seq(&mut self) -> AstTree {
         let f1 = self.sub_seq();
@@ -61,7 +404,25 @@ impl<'a> Ast<'a> {
         }
 
         match self.tokens[self.index] {
-            Token::Character(_) | Token::Dot | Token::LeftParen => {
+            Token::Character(_)
+            | Token::Dot
+            | Token::LeftParen
+            | Token::LeftBracket
+            | Token::Backref(_)
+            | Token::LookAhead
+            | Token::NegativeLookAhead
+            | Token::LookBehind
+            | Token::NegativeLookBehind
+            | Token::NonCapturing
+            | Token::NamedGroup(_)
+            | Token::WordBoundary
+            | Token::NotWordBoundary
+            | Token::StartText
+            | Token::EndText
+            | Token::Property { .. }
+            | Token::PosixClass(_)
+            | Token::WordChar
+            | Token::Not => {
                 let f2 = self.seq();
                 AstTree::Concat(Box::new(f1), Box::new(f2))
             }
@@ -69,8 +430,13 @@ impl<'a> Ast<'a> {
         }
     }
 
-    // factor ('*'|'+'|'.'|'?') | factor
+    // '~' sub_seq | factor ('*'|'+'|'.'|'?') | factor
     fn sub_seq(&mut self) -> AstTree {
+        if self.tokens[self.index] == Token::Not {
+            self.next();
+            return AstTree::Not(Box::new(self.sub_seq()));
+        }
+
         let f = self.factor();
         if self.index >= self.tokens.len() {
             return f;
@@ -96,9 +462,65 @@ impl<'a> Ast<'a> {
         }
     }
 
-    // Literal | '.' | '(' sub_expr ')'
+    // Literal | '.' | '[' (Literal | Literal '-' Literal | Property | PosixClass | WordChar)+ ']'
+    // | Backref | Assertion
+    // | Property | WordChar
+    // | ('(' | '(?:' | '(?P<name>' | LookAround) sub_expr ')'
     fn factor(&mut self) -> AstTree {
         match self.tokens[self.index] {
+            Token::LeftBracket => {
+                self.next();
+                let mut chars = vec![];
+                let mut properties = vec![];
+                loop {
+                    match self.tokens.get(self.index) {
+                        Some(Token::Character(c)) => match self.tokens.get(self.index + 1..) {
+                            Some([Token::Hyphen, Token::Character(d), ..]) => {
+                                chars.extend(Self::range(*c, *d));
+                                self.index += 2;
+                            }
+                            _ => chars.push(*c),
+                        },
+                        Some(Token::PosixClass(name)) => chars.extend(Self::posix_class(name)),
+                        Some(Token::Property { name, negated }) => {
+                            properties.push(Self::property(name, *negated))
+                        }
+                        Some(Token::WordChar) => {
+                            chars.push('_');
+                            properties.extend(Self::word_properties());
+                        }
+                        _ => break,
+                    }
+                    self.next();
+                }
+                if (chars.is_empty() && properties.is_empty())
+                    || self.tokens.get(self.index) != Some(&Token::RightBracket)
+                {
+                    panic!(
+                        "[Parser::factor] invalid character class ({:?})",
+                        self.tokens.get(self.index)
+                    )
+                }
+
+                self.next();
+                Self::class(chars, properties)
+            }
+            Token::Property { ref name, negated } => {
+                let property = Self::property(name, negated);
+                self.next();
+                AstTree::Property(property)
+            }
+            // 括弧内の\wのみの文字クラスと同じ
+            Token::WordChar => {
+                self.next();
+                Self::class(vec!['_'], Self::word_properties())
+            }
+            Token::PosixClass(ref name) => {
+                panic!(
+                    "[Parser::factor] POSIX class must be inside brackets: [:{}:]",
+                    name
+                )
+            }
             Token::Dot => {
                 self.next();
                 AstTree::Dot
@@ -107,23 +529,68 @@ impl<'a> Ast<'a> {
                 self.next();
                 AstTree::Literal(c)
             }
-            Token::LeftParen => {
+            Token::Backref(n) => {
                 self.next();
-                let p = self.sub_expr();
-                if Token::RightParen != self.tokens[self.index] {
-                    panic!(
-                        "[Parser::factor] cannot find right-paren ({:?})",
-                        self.tokens[self.index]
-                    )
-                }
-
+                AstTree::Backref(n)
+            }
+            Token::WordBoundary | Token::NotWordBoundary | Token::StartText | Token::EndText => {
+                let assertion = match self.tokens[self.index] {
+                    Token::WordBoundary => Assertion::WordBoundary,
+                    Token::NotWordBoundary => Assertion::NotWordBoundary,
+                    Token::StartText => Assertion::StartText,
+                    _ => Assertion::EndText,
+                };
                 self.next();
-                p
+                AstTree::Assert(assertion)
+            }
+            Token::LookAhead
+            | Token::NegativeLookAhead
+            | Token::LookBehind
+            | Token::NegativeLookBehind => {
+                let look = match self.tokens[self.index] {
+                    Token::LookAhead => Look::Ahead,
+                    Token::NegativeLookAhead => Look::NegativeAhead,
+                    Token::LookBehind => Look::Behind,
+                    _ => Look::NegativeBehind,
+                };
+                AstTree::Look(look, Box::new(self.group()))
+            }
+            Token::NonCapturing => self.group(),
+            Token::LeftParen | Token::NamedGroup(_) => {
+                let name = match &self.tokens[self.index] {
+                    Token::NamedGroup(name) if self.names.contains(name) => {
+                        panic!("[Parser::factor] duplicate group name: {}", name)
+                    }
+                    Token::NamedGroup(name) => {
+                        self.names.push(name.clone());
+                        Some(name.clone())
+                    }
+                    _ => None,
+                };
+                self.groups += 1;
+                let group = self.groups;
+                AstTree::Capture(group, name, Box::new(self.group()))
             }
+            Token::Hyphen => panic!("[Parser::factor] '-' must be a range inside brackets"),
             _ => self.sub_expr(),
         }
     }
 
+    // 開き括弧の後の sub_expr ')'
+    fn group(&mut self) -> AstTree {
+        self.next();
+        let p = self.sub_expr();
+        if self.tokens.get(self.index) != Some(&Token::RightParen) {
+            panic!(
+                "[Parser::group] cannot find right-paren ({:?})",
+                self.tokens.get(self.index)
+            )
+        }
+
+        self.next();
+        p
+    }
+
     // プラス演算子作成
     fn plus(&mut self, ast: AstTree) -> AstTree {
         AstTree::Plus(Box::new(ast))
@@ -138,11 +605,69 @@ impl<'a> Ast<'a> {
     fn next(&mut self) {
         self.index += 1;
     }
+
+    // 括弧内の範囲の文字（逆順の範囲と、字句解析で書けない文字を含む範囲はパニック）
+    fn range(start: char, end: char) -> Vec<char> {
+        if start > end || !(start..=end).all(is_literal) {
+            panic!("[Parser::range] invalid range: {}-{}", start, end)
+        }
+        (start..=end).collect()
+    }
+
+    // POSIXクラスの文字を取得（未知の名前はパニック）
+    fn posix_class(name: &str) -> Vec<char> {
+        posix::class(name)
+            .unwrap_or_else(|| panic!("[Parser::posix_class] unknown POSIX class: [:{}:]", name))
+    }
+
+    // 文字クラスとプロパティの選択（どちらも空でないこと）
+    fn class(mut chars: Vec<char>, properties: Vec<Property>) -> AstTree {
+        chars.sort_unstable();
+        chars.dedup();
+        let class = Some(chars).filter(|c| !c.is_empty()).map(AstTree::Class);
+        class
+            .into_iter()
+            .chain(properties.into_iter().map(AstTree::Property))
+            .reduce(|acc, a| AstTree::Or(Box::new(acc), Box::new(a)))
+            .unwrap()
+    }
+
+    // \wの文字（英字・数字と'_'、\bの単語構成文字と同じ）のうちプロパティで表す部分
+    fn word_properties() -> Vec<Property> {
+        vec![
+            Self::property("Alphabetic", false),
+            Self::property("N", false),
+        ]
+    }
+
+    // プロパティ名を解決（未知の名前はパニック）
+    fn property(name: &str, negated: bool) -> Property {
+        Property::new(name, negated)
+            .unwrap_or_else(|| panic!("[Parser::property] unknown property: {:?}", name))
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::parse::lexer::Lexer;
+
+    #[test]
+    fn test_ast_ignore_case() {
+        let tokens = vec![Token::Character('a'), Token::Character('1')];
+        let ast = Ast::new(&tokens).parse().ignore_case();
+
+        assert_eq!(
+            AstTree::Concat(
+                Box::new(AstTree::Or(
+                    Box::new(AstTree::Literal('a')),
+                    Box::new(AstTree::Literal('A')),
+                )),
+                Box::new(AstTree::Literal('1'))
+            ),
+            ast
+        );
+    }
 
     #[test]
     fn test_ast_literal() {
@@ -237,8 +762,303 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_ast_set_operator() {
+        // ~ は直後の繰り返しまで、& は | より強く連結より弱く結合する
+        let tokens = Lexer::new("a+&~(?:ab)c|b").scan();
+        let ast = Ast::new(&tokens).parse();
+
+        assert_eq!(
+            AstTree::Or(
+                Box::new(AstTree::And(
+                    Box::new(AstTree::Plus(Box::new(AstTree::Literal('a')))),
+                    Box::new(AstTree::Concat(
+                        Box::new(AstTree::Not(Box::new(AstTree::Concat(
+                            Box::new(AstTree::Literal('a')),
+                            Box::new(AstTree::Literal('b')),
+                        )))),
+                        Box::new(AstTree::Literal('c')),
+                    )),
+                )),
+                Box::new(AstTree::Literal('b')),
+            ),
+            ast
+        );
+        assert!(ast.has_set_operator());
+        assert!(!Ast::new(&Lexer::new("a(b|c)*").scan())
+            .parse()
+            .has_set_operator());
+    }
+
+    #[test]
+    fn test_ast_class() {
+        let ast = Ast::new(&Lexer::new("[cab]x*").scan()).parse();
+
+        assert_eq!(
+            AstTree::Concat(
+                Box::new(AstTree::Class(vec!['a', 'b', 'c'])),
+                Box::new(AstTree::Repeat(Box::new(AstTree::Literal('x'))))
+            ),
+            ast
+        );
+        assert_eq!(
+            AstTree::Class(vec!['A', 'B', 'a', 'b']),
+            Ast::new(&Lexer::new("[ab]").scan()).parse().ignore_case()
+        );
+    }
+
+    #[test]
+    fn test_ast_backref() {
+        // (?:...)はグループ番号を持たない
+        let ast = Ast::new(&Lexer::new(r"(?:a)(b)\1").scan()).parse();
+
+        assert_eq!(
+            AstTree::Concat(
+                Box::new(AstTree::Literal('a')),
+                Box::new(AstTree::Concat(
+                    Box::new(AstTree::Capture(1, None, Box::new(AstTree::Literal('b')))),
+                    Box::new(AstTree::Backref(1))
+                ))
+            ),
+            ast
+        );
+    }
+
+    #[test]
+    fn test_ast_capture() {
+        // 番号は左括弧の順で、(?:...)と先読み・後読みは数えない
+        let ast = Ast::new(&Lexer::new("(?:a)(?P<x>b(c))(?=d)(?<y>e)").scan()).parse();
+
+        assert_eq!(
+            vec![
+                (1, Some("x".to_string())),
+                (2, None),
+                (3, Some("y".to_string()))
+            ],
+            ast.groups()
+        );
+        assert_eq!("a(?P<x>b(c))(?=d)(?P<y>e)", ast.to_string());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ast_capture_duplicate_name() {
+        Ast::new(&Lexer::new("(?P<x>a)(?P<x>b)").scan()).parse();
+    }
+
+    #[test]
+    fn test_ast_lookaround() {
+        let ast = Ast::new(&Lexer::new("(?<!a)b(?=c|d)").scan()).parse();
+
+        assert_eq!(
+            AstTree::Concat(
+                Box::new(AstTree::Look(
+                    Look::NegativeBehind,
+                    Box::new(AstTree::Literal('a'))
+                )),
+                Box::new(AstTree::Concat(
+                    Box::new(AstTree::Literal('b')),
+                    Box::new(AstTree::Look(
+                        Look::Ahead,
+                        Box::new(AstTree::Or(
+                            Box::new(AstTree::Literal('c')),
+                            Box::new(AstTree::Literal('d'))
+                        ))
+                    ))
+                ))
+            ),
+            ast
+        );
+        assert!(ast.has_lookaround());
+        assert!(ast.looks_behind());
+    }
+
+    #[test]
+    fn test_ast_width() {
+        let width = |pattern: &str| Ast::new(&Lexer::new(pattern).scan()).parse().width();
+
+        assert_eq!((3, Some(3)), width("abc"));
+        assert_eq!((1, Some(3)), width("a|b.c"));
+        assert_eq!((1, None), width("ab*"));
+        assert_eq!((0, Some(2)), width("(ab)?(?=c+)"));
+    }
+
+    #[test]
+    fn test_ast_assertion() {
+        let ast = Ast::new(&Lexer::new(r"\Ba\b*").scan()).parse();
+        assert_eq!(
+            AstTree::Concat(
+                Box::new(AstTree::Assert(Assertion::NotWordBoundary)),
+                Box::new(AstTree::Concat(
+                    Box::new(AstTree::Literal('a')),
+                    Box::new(AstTree::Repeat(Box::new(AstTree::Assert(
+                        Assertion::WordBoundary
+                    ))))
+                ))
+            ),
+            ast
+        );
+        assert!(ast.has_assertion());
+        assert!(ast.looks_behind());
+        assert_eq!((1, Some(1)), ast.width());
+
+        let ast = Ast::new(&Lexer::new(r"a\z").scan()).parse();
+        assert!(ast.has_assertion());
+        assert!(!ast.looks_behind());
+    }
+
+    #[test]
+    fn test_ast_property() {
+        let greek = Property::new("Greek", false).unwrap();
+        let not_nd = Property::new("Nd", true).unwrap();
+
+        let ast = Ast::new(&Lexer::new(r"\p{Greek}+").scan()).parse();
+        assert_eq!(AstTree::Plus(Box::new(AstTree::Property(greek))), ast);
+        assert!(ast.has_property());
+        assert_eq!((1, None), ast.width());
+
+        let ast = Ast::new(&Lexer::new(r"[b\P{Nd}a]").scan()).parse();
+        assert_eq!(
+            AstTree::Or(
+                Box::new(AstTree::Class(vec!['a', 'b'])),
+                Box::new(AstTree::Property(not_nd))
+            ),
+            ast
+        );
+
+        let ast = Ast::new(&Lexer::new(r"[\p{Greek}]").scan()).parse();
+        assert_eq!(AstTree::Property(greek), ast);
+    }
+
+    #[test]
+    fn test_ast_word_char() {
+        let word = AstTree::Or(
+            Box::new(AstTree::Or(
+                Box::new(AstTree::Class(vec!['_'])),
+                Box::new(AstTree::Property(
+                    Property::new("Alphabetic", false).unwrap(),
+                )),
+            )),
+            Box::new(AstTree::Property(Property::new("N", false).unwrap())),
+        );
+        assert_eq!(word, Ast::new(&Lexer::new(r"\w").scan()).parse());
+        assert_eq!(word, Ast::new(&Lexer::new(r"[\w]").scan()).parse());
+    }
+
+    #[test]
+    #[should_panic(expected = "[Parser::property] unknown property")]
+    fn test_ast_unknown_property() {
+        Ast::new(&Lexer::new(r"\p{Klingon}").scan()).parse();
+    }
+
+    #[test]
+    fn test_ast_posix_class() {
+        let ast = Ast::new(&Lexer::new("[[:digit:]a[:xdigit:]]").scan()).parse();
+        let mut chars: Vec<char> = ('0'..='9').chain('A'..='F').chain('a'..='f').collect();
+        chars.sort_unstable();
+        assert_eq!(AstTree::Class(chars), ast);
+
+        let ast = Ast::new(&Lexer::new("[[:blank:]]+").scan()).parse();
+        assert_eq!(
+            AstTree::Plus(Box::new(AstTree::Class(vec!['\t', ' ']))),
+            ast
+        );
+    }
+
+    #[test]
+    fn test_ast_range() {
+        let ast = Ast::new(&Lexer::new("[a-c[:digit:]x-z]").scan()).parse();
+        let chars: Vec<char> = ('0'..='9').chain('a'..='c').chain('x'..='z').collect();
+        assert_eq!(AstTree::Class(chars), ast);
+    }
+
+    #[test]
+    #[should_panic(expected = "[Parser::range] invalid range: z-a")]
+    fn test_ast_invalid_range() {
+        Ast::new(&Lexer::new("[z-a]").scan()).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "[Parser::range] invalid range:  -z")]
+    fn test_ast_range_with_punctuation() {
+        Ast::new(&Lexer::new("[ -z]").scan()).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "[Parser::factor] '-' must be a range inside brackets")]
+    fn test_ast_hyphen_outside_brackets() {
+        Ast::new(&Lexer::new("a|-").scan()).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "[Parser::factor] POSIX class must be inside brackets")]
+    fn test_ast_posix_class_outside_brackets() {
+        Ast::new(&Lexer::new("a[:alpha:]").scan()).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "[Parser::posix_class] unknown POSIX class")]
+    fn test_ast_unknown_posix_class() {
+        Ast::new(&Lexer::new("[[:word:]]").scan()).parse();
+    }
+
+    #[test]
+    fn test_assertion_is_match() {
+        use CharKind::*;
+
+        assert!(Assertion::WordBoundary.is_match(Edge, Word));
+        assert!(Assertion::WordBoundary.is_match(Word, Other));
+        assert!(!Assertion::WordBoundary.is_match(Other, Edge));
+        assert!(Assertion::NotWordBoundary.is_match(Word, Word));
+        assert!(Assertion::StartText.is_match(Edge, Word));
+        assert!(!Assertion::StartText.is_match(Other, Word));
+        assert!(Assertion::EndText.is_match(Word, Edge));
+        assert_eq!(Assertion::StartText, Assertion::EndText.reverse());
+        assert_eq!(Word, CharKind::of(Some('_')));
+        assert_eq!(Word, CharKind::of(Some('あ')));
+        assert_eq!(Other, CharKind::of(Some('-')));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ast_backref_invalid() {
+        Ast::new(&Lexer::new(r"(a)\2").scan()).parse();
+    }
+
+    #[test]
+    fn test_ast_try_parse_backref_invalid() {
+        assert_eq!(
+            Err(Error::InvalidBackreference {
+                group: 3,
+                groups: 1
+            }),
+            Ast::new(&Lexer::new(r"(a)\3").scan()).try_parse()
+        );
+        assert!(Ast::new(&Lexer::new(r"(a)\1").scan()).try_parse().is_ok());
+    }
+
     #[test]
     fn test_ast_or() {
+        {
+            let tokens = vec![
+                Token::Character('a'),
+                Token::Or,
+                Token::Character('b'),
+                Token::Or,
+                Token::Character('c'),
+            ];
+            let ast = Ast::new(&tokens).parse();
+            assert_eq!(
+                AstTree::Or(
+                    Box::new(AstTree::Literal('a')),
+                    Box::new(AstTree::Or(
+                        Box::new(AstTree::Literal('b')),
+                        Box::new(AstTree::Literal('c')),
+                    )),
+                ),
+                ast
+            )
+        }
         {
             let tokens = vec![Token::Character('a'), Token::Or, Token::Character('b')];
             let ast = Ast::new(&tokens).parse();
@@ -327,13 +1147,17 @@ mod test {
     #[test]
     fn test_ast_paren() {
         {
-            let tokens = vec![Token::LeftParen, Token::Character('a'), Token::RightParen];
+            let tokens = vec![
+                Token::NonCapturing,
+                Token::Character('a'),
+                Token::RightParen,
+            ];
             let ast = Ast::new(&tokens).parse();
             assert_eq!(AstTree::Literal('a'), ast)
         }
         {
             let tokens = vec![
-                Token::LeftParen,
+                Token::NonCapturing,
                 Token::Character('a'),
                 Token::Character('b'),
                 Token::RightParen,
@@ -349,12 +1173,12 @@ mod test {
         }
         {
             let tokens = vec![
-                Token::LeftParen,
+                Token::NonCapturing,
                 Token::Character('a'),
                 Token::Character('b'),
                 Token::RightParen,
                 Token::Or,
-                Token::LeftParen,
+                Token::NonCapturing,
                 Token::Character('c'),
                 Token::Character('d'),
                 Token::RightParen,
@@ -376,7 +1200,7 @@ mod test {
         }
         {
             let tokens = vec![
-                Token::LeftParen,
+                Token::NonCapturing,
                 Token::Character('a'),
                 Token::Character('b'),
                 Token::RightParen,
@@ -393,7 +1217,7 @@ mod test {
         }
         {
             let tokens = vec![
-                Token::LeftParen,
+                Token::NonCapturing,
                 Token::Character('a'),
                 Token::Character('b'),
                 Token::Character('c'),
@@ -414,7 +1238,7 @@ mod test {
         }
         {
             let tokens = vec![
-                Token::LeftParen,
+                Token::NonCapturing,
                 Token::Character('a'),
                 Token::Character('b'),
                 Token::RightParen,
@@ -431,7 +1255,7 @@ mod test {
         }
         {
             let tokens = vec![
-                Token::LeftParen,
+                Token::NonCapturing,
                 Token::Character('a'),
                 Token::Character('b'),
                 Token::RightParen,
@@ -448,7 +1272,7 @@ mod test {
         }
         {
             let tokens = vec![
-                Token::LeftParen,
+                Token::NonCapturing,
                 Token::Character('a'),
                 Token::Character('b'),
                 Token::RightParen,
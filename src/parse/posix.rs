@@ -0,0 +1,92 @@
+#![allow(dead_code)]
+
+use crate::parse::lexer::is_literal;
+
+// クラス名と所属判定
+type Class = (&'static str, fn(char) -> bool);
+
+// POSIXの文字クラス（括弧内の[:name:]、POSIXロケールのためASCIIの文字のみ）
+const CLASSES: [Class; 12] = [
+    ("alpha", |c| c.is_ascii_alphabetic()),
+    ("digit", |c| c.is_ascii_digit()),
+    ("alnum", |c| c.is_ascii_alphanumeric()),
+    ("upper", |c| c.is_ascii_uppercase()),
+    ("lower", |c| c.is_ascii_lowercase()),
+    ("xdigit", |c| c.is_ascii_hexdigit()),
+    ("space", |c| {
+        matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0B' | '\x0C')
+    }),
+    ("blank", |c| c == ' ' || c == '\t'),
+    ("punct", |c| c.is_ascii_punctuation()),
+    ("cntrl", |c| c.is_ascii_control()),
+    ("graph", |c| c.is_ascii_graphic()),
+    ("print", |c| c.is_ascii_graphic() || c == ' '),
+];
+
+// クラスに含まれる文字（昇順、未知の名前はNone）
+pub fn class(name: &str) -> Option<Vec<char>> {
+    CLASSES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, is_member)| {
+            (0..=0x7F_u8)
+                .map(char::from)
+                .filter(|c| is_member(*c))
+                .collect()
+        })
+}
+
+// 文字クラスを書き出す際に使用するPOSIXクラスと、残りの文字
+// 英数字・空白・'_'以外の文字は字句解析で読めないため、それらを含むPOSIXクラスを大きい順に選ぶ
+// （選んだクラスは全ての文字が元の文字クラスに含まれる）
+pub fn cover(chars: &[char]) -> (Vec<&'static str>, Vec<char>) {
+    let mut candidates: Vec<(&'static str, Vec<char>)> = CLASSES
+        .iter()
+        .filter_map(|(name, _)| Some((*name, class(name)?)))
+        .filter(|(_, members)| members.iter().all(|c| chars.contains(c)))
+        .collect();
+    candidates.sort_by_key(|(_, members)| std::cmp::Reverse(members.len()));
+
+    let mut names = vec![];
+    let mut rest: Vec<char> = chars.to_vec();
+    for (name, members) in candidates {
+        let covers = |c: &char| !is_literal(*c) && members.contains(c);
+        if rest.iter().any(covers) {
+            names.push(name);
+            rest.retain(|c| !members.contains(c));
+        }
+    }
+    (names, rest)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_class() {
+        assert_eq!(Some(('0'..='9').collect()), class("digit"));
+        assert_eq!(Some(vec!['\t', ' ']), class("blank"));
+        assert_eq!(52, class("alpha").unwrap().len());
+        assert_eq!(95, class("print").unwrap().len());
+        assert_eq!(None, class("word"));
+    }
+
+    #[test]
+    fn test_cover() {
+        let mut chars = class("punct").unwrap();
+        chars.extend(vec!['a', 'b']);
+        chars.sort_unstable();
+        assert_eq!((vec!["punct"], vec!['a', 'b']), cover(&chars));
+
+        assert_eq!((vec![], vec!['a', 'b']), cover(&['a', 'b']));
+        assert_eq!(
+            (vec![], ('0'..='9').collect()),
+            cover(&class("digit").unwrap())
+        );
+        assert_eq!((vec!["space"], vec![]), cover(&class("space").unwrap()));
+        assert_eq!((vec!["print"], vec![]), cover(&class("print").unwrap()));
+        // 字句解析で読めない文字を含まないクラスのみでは書き出せない
+        assert_eq!((vec![], vec!['-', 'a']), cover(&['-', 'a']));
+    }
+}
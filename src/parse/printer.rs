@@ -0,0 +1,318 @@
+#![allow(dead_code)]
+
+use crate::parse::lexer::is_literal;
+use crate::parse::parser::{Assertion, AstTree, Look};
+use crate::parse::posix;
+use std::fmt;
+
+// 結合の強さ（パーサの文法の階層に対応）
+const OR: u8 = 0; // sub_expr
+const AND: u8 = 1; // and_expr
+const SEQ: u8 = 2; // seq
+const SUB_SEQ: u8 = 3; // sub_seq（~と後置演算子）
+const FACTOR: u8 = 4; // factor
+
+// パーサで同じASTへ戻る、括弧が最小のパターン文字列
+// 二項演算はパーサと同じく右結合のため、左側に同じ演算が来る場合のみ括弧を付ける
+// グループはその位置に括弧を付け、それ以外の括弧はグループにしない(?:...)とする
+impl fmt::Display for AstTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, OR)
+    }
+}
+
+impl AstTree {
+    // 木構造を一行一ノードで表示（--explainで使用）
+    pub fn dump(&self) -> String {
+        let mut s = String::new();
+        self.dump_node(&mut s, 0);
+        s
+    }
+
+    fn dump_node(&self, s: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let children = self.children();
+        let label = match self {
+            AstTree::Concat(_, _) => "Concat".to_string(),
+            AstTree::Or(_, _) => "Or".to_string(),
+            AstTree::And(_, _) => "And".to_string(),
+            AstTree::Repeat(_) => "Repeat".to_string(),
+            AstTree::Plus(_) => "Plus".to_string(),
+            AstTree::Question(_) => "Question".to_string(),
+            AstTree::Not(_) => "Not".to_string(),
+            AstTree::Capture(n, None, _) => format!("Capture({})", n),
+            AstTree::Capture(n, Some(name), _) => format!("Capture({}, {})", n, name),
+            AstTree::Look(look, _) => format!("Look({:?})", look),
+            leaf => format!("{:?}", leaf),
+        };
+        s.push_str(&format!("{}{}\n", indent, label));
+        for child in children {
+            child.dump_node(s, depth + 1);
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, level: u8) -> fmt::Result {
+        // 優先順位のための括弧はグループにしない
+        if self.level() < level {
+            return write!(f, "(?:{})", self);
+        }
+
+        match self {
+            AstTree::Or(l, r) => {
+                l.write(f, AND)?;
+                write!(f, "|")?;
+                r.write(f, OR)
+            }
+            AstTree::And(l, r) => {
+                l.write(f, SEQ)?;
+                write!(f, "&")?;
+                r.write(f, AND)
+            }
+            AstTree::Concat(l, r) => {
+                // 文字列は連結の左側でも括弧が不要（同じ文字列にマッチする）
+                match **l {
+                    AstTree::String(_) => l.write(f, SEQ)?,
+                    _ => l.write(f, SUB_SEQ)?,
+                }
+                r.write(f, SEQ)
+            }
+            AstTree::Not(a) => {
+                write!(f, "~")?;
+                a.write(f, SUB_SEQ)
+            }
+            AstTree::Repeat(a) => {
+                a.write(f, FACTOR)?;
+                write!(f, "*")
+            }
+            AstTree::Plus(a) => {
+                a.write(f, FACTOR)?;
+                write!(f, "+")
+            }
+            AstTree::Question(a) => {
+                a.write(f, FACTOR)?;
+                write!(f, "?")
+            }
+            AstTree::Literal(c) => write_char(f, *c),
+            AstTree::Dot => write!(f, "."),
+            AstTree::String(s) => s.chars().try_for_each(|c| write_char(f, c)),
+            AstTree::Class(chars) => {
+                // 英数字以外の文字はPOSIXクラスとして書き出す
+                let (classes, rest) = posix::cover(chars);
+                write!(f, "[")?;
+                classes
+                    .iter()
+                    .try_for_each(|name| write!(f, "[:{}:]", name))?;
+                rest.iter().try_for_each(|c| write_char(f, *c))?;
+                write!(f, "]")
+            }
+            AstTree::Capture(_, None, a) => write!(f, "({})", a),
+            AstTree::Capture(_, Some(name), a) => write!(f, "(?P<{}>{})", name, a),
+            AstTree::Backref(n) => write!(f, "\\{}", n),
+            AstTree::Look(look, a) => {
+                let open = match look {
+                    Look::Ahead => "(?=",
+                    Look::NegativeAhead => "(?!",
+                    Look::Behind => "(?<=",
+                    Look::NegativeBehind => "(?<!",
+                };
+                write!(f, "{}{})", open, a)
+            }
+            AstTree::Assert(a) => match a {
+                Assertion::WordBoundary => write!(f, "\\b"),
+                Assertion::NotWordBoundary => write!(f, "\\B"),
+                Assertion::StartText => write!(f, "\\A"),
+                Assertion::EndText => write!(f, "\\z"),
+            },
+            AstTree::Property(p) => write!(f, "{:?}", p),
+        }
+    }
+
+    fn level(&self) -> u8 {
+        match self {
+            AstTree::Or(_, _) => OR,
+            AstTree::And(_, _) => AND,
+            AstTree::Concat(_, _) => SEQ,
+            AstTree::Not(_) | AstTree::Repeat(_) | AstTree::Plus(_) | AstTree::Question(_) => {
+                SUB_SEQ
+            }
+            AstTree::String(s) if s.chars().count() > 1 => SEQ,
+            AstTree::Literal(_)
+            | AstTree::Dot
+            | AstTree::String(_)
+            | AstTree::Class(_)
+            | AstTree::Capture(_, _, _)
+            | AstTree::Backref(_)
+            | AstTree::Look(_, _)
+            | AstTree::Assert(_)
+            | AstTree::Property(_) => FACTOR,
+        }
+    }
+}
+
+// 字句解析で文字として読めるのは英数字・空白・'_'のみ
+fn write_char(f: &mut fmt::Formatter, c: char) -> fmt::Result {
+    if !is_literal(c) {
+        panic!("[AstTree::fmt] not support char: {:?}", c)
+    }
+    write!(f, "{}", c)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::lexer::Lexer;
+    use crate::parse::parser::{Ast, AstTree};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn parse(pattern: &str) -> AstTree {
+        Ast::new(&Lexer::new(pattern).scan()).parse()
+    }
+
+    #[test]
+    fn test_display() {
+        for pattern in [
+            "abc", "a|b|c", "(a|b)c*", "(ab)+d?", "a&~b*c", "~a|.b", "(~a)*", "(a*)*", "(a|b)|c",
+            "(ab)c", r"(a|b)\1",
+        ]
+        .iter()
+        {
+            assert_eq!(*pattern, parse(pattern).to_string());
+        }
+    }
+
+    #[test]
+    fn test_display_parenthesize() {
+        let a = || Box::new(AstTree::Literal('a'));
+        let b = || Box::new(AstTree::Literal('b'));
+
+        let or = Box::new(AstTree::Or(a(), b()));
+        assert_eq!("(?:a|b)b", AstTree::Concat(or.clone(), b()).to_string());
+        assert_eq!("(?:a|b)&b", AstTree::And(or.clone(), b()).to_string());
+        assert_eq!("~(?:a|b)", AstTree::Not(or).to_string());
+
+        let not = AstTree::Repeat(Box::new(AstTree::Not(a())));
+        assert_eq!(not, parse(&not.to_string()));
+    }
+
+    #[test]
+    fn test_display_group() {
+        for pattern in ["(a|b)c", "(?P<x>a)b*", "(?:a|b)(c)", "((a)|b)*"].iter() {
+            assert_eq!(*pattern, parse(pattern).to_string());
+        }
+        assert_eq!("(?P<x>a)", parse("(?<x>a)").to_string());
+    }
+
+    #[test]
+    fn test_display_assertion() {
+        for pattern in [r"\bab\b", r"\A(a|\B)*\z", r"(?:\ba)+"].iter() {
+            assert_eq!(*pattern, parse(pattern).to_string());
+        }
+        assert_eq!("Assert(WordBoundary)\n", parse(r"\b").dump());
+    }
+
+    #[test]
+    fn test_display_posix_class() {
+        for pattern in ["[[:punct:]]+", "[[:space:]ab]", "a[[:print:]]*"].iter() {
+            assert_eq!(*pattern, parse(pattern).to_string());
+        }
+        // 英数字のみのクラスは文字として書き出す
+        assert_eq!("[0123456789x]", parse("[x[:digit:]]").to_string());
+        assert_eq!("[[:blank:]]", parse("[[:blank:]]").to_string());
+    }
+
+    #[test]
+    fn test_display_property() {
+        for pattern in [r"\p{Greek}+", r"a\P{Nd}*", r"(?:[ab]|\p{L})b"].iter() {
+            assert_eq!(*pattern, parse(pattern).to_string());
+        }
+        assert_eq!(r"[a]|\p{Lu}", parse(r"[\p{Lu}a]").to_string());
+        assert_eq!(r"(?:[a]|\p{Lu})*", parse(r"[\p{Lu}a]*").to_string());
+    }
+
+    #[test]
+    fn test_dump() {
+        assert_eq!(
+            "Concat\n  Literal('a')\n  Repeat\n    Class(['b', 'c'])\n",
+            parse("a[bc]*").dump()
+        );
+    }
+
+    #[test]
+    fn test_display_redundant_paren() {
+        assert_eq!("a|b", parse("(?:(?:a))|(?:b)").to_string());
+        assert_eq!("ab*", parse("(?:a)(?:b)*").to_string());
+        assert_eq!("~a*", parse("~(?:a*)").to_string());
+        // グループの括弧は残す
+        assert_eq!("((a))|(b)", parse("((a))|(b)").to_string());
+    }
+
+    // 深さdepthまでのランダムなAST
+    fn random_ast(rng: &mut StdRng, depth: usize) -> AstTree {
+        let sub = |rng: &mut StdRng| Box::new(random_ast(rng, depth - 1));
+        if depth == 0 || rng.gen_bool(0.2) {
+            return match rng.gen_range(0..6) {
+                0 => AstTree::Dot,
+                5 => AstTree::Class(vec!['0', 'a', 'b']),
+                n => AstTree::Literal(['a', 'b', 'X', '0'][n - 1]),
+            };
+        }
+        match rng.gen_range(0..7) {
+            0 => AstTree::Concat(sub(rng), sub(rng)),
+            1 => AstTree::Or(sub(rng), sub(rng)),
+            2 => AstTree::And(sub(rng), sub(rng)),
+            3 => AstTree::Repeat(sub(rng)),
+            4 => AstTree::Plus(sub(rng)),
+            5 => AstTree::Question(sub(rng)),
+            _ => AstTree::Not(sub(rng)),
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        // parse(print(ast)) == ast
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..2000 {
+            let ast = random_ast(&mut rng, 6);
+            let printed = ast.to_string();
+            assert_eq!(ast, parse(&printed), "{}", printed);
+        }
+    }
+
+    #[test]
+    fn test_display_minimal_paren() {
+        // 括弧を一組でも外すと、別のASTになるかパースできない
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..300 {
+            let ast = random_ast(&mut rng, 4);
+            let printed = ast.to_string();
+            for (open, _) in printed.match_indices("(?:") {
+                let close = matching_paren(&printed, open);
+                let removed: String = printed
+                    .char_indices()
+                    .filter(|(i, _)| !(open..open + 3).contains(i) && *i != close)
+                    .map(|(_, c)| c)
+                    .collect();
+                let reparsed = std::panic::catch_unwind(|| parse(&removed));
+                assert!(
+                    reparsed.map_or(true, |r| r != ast),
+                    "{} -> {}",
+                    printed,
+                    removed
+                );
+            }
+        }
+    }
+
+    fn matching_paren(s: &str, open: usize) -> usize {
+        let mut depth = 0;
+        for (i, c) in s.char_indices().skip_while(|(i, _)| *i < open) {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 1 => return i,
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+        panic!("[matching_paren] unbalanced: {}", s)
+    }
+}
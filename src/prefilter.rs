@@ -0,0 +1,98 @@
+#![allow(dead_code)]
+
+use crate::parse::literal::Literals;
+use crate::parse::parser::AstTree;
+
+// リテラルによる事前フィルタ
+// 必須リテラルを含まない入力を即座に除外し、先頭リテラルが最初に現れる位置まで照合を読み飛ばす
+#[derive(Debug)]
+pub struct Prefilter {
+    prefix: String,
+    required: Vec<String>,
+}
+
+impl Prefilter {
+    // リテラルが抽出できない場合はNone
+    pub fn new(ast: &AstTree) -> Option<Self> {
+        let literals = Literals::new(ast);
+        if literals.prefix.is_empty() && literals.required.is_empty() {
+            return None;
+        }
+
+        Some(Prefilter {
+            prefix: literals.prefix,
+            required: literals.required,
+        })
+    }
+
+    // 先頭リテラルの有無
+    pub fn has_prefix(&self) -> bool {
+        !self.prefix.is_empty()
+    }
+
+    // 必須リテラルが全て含まれるか
+    pub fn may_match(&self, haystack: &str) -> bool {
+        self.required.iter().all(|r| haystack.contains(r.as_str()))
+    }
+
+    // 先頭リテラルの出現位置（バイト位置、重なりを含む）
+    pub fn candidates<'h>(&'h self, haystack: &'h str) -> Candidates<'h> {
+        Candidates {
+            needle: &self.prefix,
+            haystack,
+            pos: 0,
+        }
+    }
+}
+
+pub struct Candidates<'h> {
+    needle: &'h str,
+    haystack: &'h str,
+    pos: usize,
+}
+
+impl<'h> Iterator for Candidates<'h> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let found = self.pos + self.haystack.get(self.pos..)?.find(self.needle)?;
+
+        // 重なった出現も拾うため、一文字分だけ進める
+        let step = self.haystack[found..]
+            .chars()
+            .next()
+            .map_or(1, |c| c.len_utf8());
+        self.pos = found + step;
+        Some(found)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::lexer::Lexer;
+    use crate::parse::parser::Ast;
+
+    fn prefilter(pattern: &str) -> Option<Prefilter> {
+        Prefilter::new(&Ast::new(&Lexer::new(pattern).scan()).parse())
+    }
+
+    #[test]
+    fn test_prefilter_may_match() {
+        let p = prefilter("a*foo.*bar").unwrap();
+
+        assert!(!p.has_prefix());
+        assert!(p.may_match("xxfooxxbar"));
+        assert!(!p.may_match("xxfooxx"));
+        assert!(prefilter("a*|b").is_none());
+    }
+
+    #[test]
+    fn test_prefilter_candidates() {
+        let p = prefilter("aab*").unwrap();
+
+        assert!(p.has_prefix());
+        assert_eq!(vec![0, 1, 5], p.candidates("aaabxaa").collect::<Vec<_>>());
+        assert_eq!(vec![4], p.candidates("ééaa").collect::<Vec<_>>());
+    }
+}
@@ -1,19 +1,375 @@
 #![allow(dead_code)]
 
+use crate::algebra;
+use crate::automaton::aho_corasick::MultiLiteral;
+use crate::automaton::dfa::DFAProgram;
+use crate::automaton::glushkov::Glushkov;
+use crate::automaton::nfa::NFAProgram;
+use crate::automaton::overlap::{OverlapSearcher, Overlapping};
 use crate::automaton::pattern::build::Builder;
-use crate::vm::machine::Machine;
-use std::path::PathBuf;
+use crate::automaton::search::DFASearcher;
+use crate::derivative::DerivativeMachine;
+use crate::error::Error;
+use crate::parse::analysis::Analysis;
+use crate::parse::lexer::Lexer;
+use crate::parse::parser::{Ast, AstTree};
+use crate::prefilter::Prefilter;
+use crate::vm::build::{Builder as VmBuilder, RegexIR};
+use crate::vm::machine::{Machine, Slots};
+use crate::vm::optimize;
+use crate::vm::pike::PikeMachine;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+// 正規表現エンジン
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    Backtrack,  // バックトラック型VM
+    PikeVm,     // Pike VM
+    Nfa,        // NFAシミュレーション
+    Dfa,        // 部分集合構成法で変換したDFA
+    Derivative, // Brzozowskiの微分
+    Literal,    // 文字列検索（リテラル、またはリテラルの選択のみのパターン）
+    Auto,       // パターンから自動選択
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "backtrack" => Ok(Engine::Backtrack),
+            "pike" => Ok(Engine::PikeVm),
+            "nfa" => Ok(Engine::Nfa),
+            "dfa" => Ok(Engine::Dfa),
+            "derivative" => Ok(Engine::Derivative),
+            "literal" => Ok(Engine::Literal),
+            "auto" => Ok(Engine::Auto),
+            _ => Err(format!("unknown engine: {}", s)),
+        }
+    }
+}
+
+// NFAの構成法（NFA・DFAエンジンで使用）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Construction {
+    Thompson, // ε遷移を用いるThompson構成法
+    Glushkov, // ε遷移のない位置オートマトン
+}
+
+impl FromStr for Construction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "thompson" => Ok(Construction::Thompson),
+            "glushkov" => Ok(Construction::Glushkov),
+            _ => Err(format!("unknown construction: {}", s)),
+        }
+    }
+}
+
+// 一致範囲の選び方（find・capturesで使用）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchKind {
+    LeftmostFirst,   // 最も左で始まり、選択肢の左・繰り返しの多い方を優先（Perl互換）
+    LeftmostLongest, // 最も左で始まり、その中で最長（POSIX互換）
+}
+
+impl FromStr for MatchKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "leftmost-first" => Ok(MatchKind::LeftmostFirst),
+            "leftmost-longest" => Ok(MatchKind::LeftmostLongest),
+            _ => Err(format!("unknown match kind: {}", s)),
+        }
+    }
+}
+
+// 自動選択時に許容するDFA状態数（超過時はPike VMへフォールバック）
+const AUTO_DFA_MAX_STATES: usize = 64;
+
+// エンジンごとのコンパイル結果
+#[derive(Debug)]
+enum Program {
+    Backtrack(Machine),
+    PikeVm(PikeMachine),
+    Nfa(NFAProgram),
+    Dfa {
+        dfa: DFAProgram,
+        searcher: Option<DFASearcher>, // 共通部分・補集合を含む場合はNone
+    },
+    Derivative(DerivativeMachine),
+    Literal {
+        literal: String,
+        anchored: bool,
+    },
+    MultiLiteral {
+        searcher: MultiLiteral,
+        anchored: bool,
+    },
+}
+
+// 一致範囲（バイト位置）
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Match {
+    // 一致した部分文字列
+    pub fn as_str<'h>(&self, haystack: &'h str) -> &'h str {
+        &haystack[self.start..self.end]
+    }
+}
+
+// グループごとの一致範囲（0番はマッチ全体）
+#[derive(Debug, PartialEq, Clone)]
+pub struct Captures {
+    spans: Vec<Option<Match>>,
+    names: HashMap<String, usize>,
+}
+
+impl Captures {
+    // n番のグループの一致範囲（マッチしていない場合はNone）
+    pub fn get(&self, n: usize) -> Option<Match> {
+        self.spans.get(n).copied().flatten()
+    }
+
+    // 名前付きグループの一致範囲
+    pub fn name(&self, name: &str) -> Option<Match> {
+        self.get(*self.names.get(name)?)
+    }
+
+    // グループ数（マッチ全体を含む）
+    pub fn group_count(&self) -> usize {
+        self.spans.len()
+    }
+}
+
+// パターンを括弧が最小の形へ正規化（同じASTになるパターンは同じ文字列になる）
+pub fn normalize(pattern: &str) -> String {
+    Ast::new(&Lexer::new(pattern).scan()).parse().to_string()
+}
+
+// パターンの解析結果と最適化の前後（--explainで表示）
+pub fn explain(pattern: &str) -> String {
+    let ast = Ast::new(&Lexer::new(pattern).scan()).parse();
+    let optimized = ast.clone().optimize();
+    let groups: Vec<String> = ast
+        .groups()
+        .into_iter()
+        .map(|(n, name)| match name {
+            Some(name) => format!("{}={}", n, name),
+            None => n.to_string(),
+        })
+        .collect();
+    format!(
+        "parsed:    {}\noptimized: {}\ngroups:    {}\n\nbefore:\n{}\nafter:\n{}",
+        ast,
+        optimized,
+        if groups.is_empty() {
+            "(none)".to_string()
+        } else {
+            groups.join(", ")
+        },
+        ast.dump(),
+        optimized.dump()
+    )
+}
 
 #[derive(Debug)]
 pub struct Regex<'a> {
     pattern: &'a str,
+    engine: Engine,
+    program: Program,
+    prefilter: Option<Prefilter>,
+    anchored: bool,
+    captures: Option<Machine>,  // グループの範囲を求めるバックトラック型VM
+    spans: Option<PikeMachine>, // 一致範囲とグループを求めるPike VM（バックトラック型VM以外）
+    match_kind: MatchKind,      // 一致範囲の選び方
+    names: HashMap<String, usize>, // グループ名から番号
+    group_count: usize,         // グループ数（マッチ全体を除く）
 }
 
 impl<'a> Regex<'a> {
+    // 既定の設定でコンパイル（コンパイルできないパターンではpanicする）
+    // エラーとして扱う場合はtry_new、設定を変える場合はRegexBuilderを使用
     pub fn new(pattern: &'a str) -> Self {
-        Regex { pattern }
+        Self::try_new(pattern).unwrap_or_else(|e| panic!("[Regex::new] {:?}", e))
+    }
+
+    // 既定の設定でコンパイル（長さの上限がない後読みなど、コンパイルできない場合はエラー）
+    pub fn try_new(pattern: &'a str) -> Result<Self, Error> {
+        RegexBuilder::new(pattern).build()
+    }
+
+    // マッチ判定（上限超過時はエラー）
+    pub fn is_match(&self, s: &str) -> Result<bool, Error> {
+        match &self.prefilter {
+            // 必須リテラルを含まない場合はエンジンを実行しない
+            Some(prefilter) if !prefilter.may_match(s) => Ok(false),
+            // 先頭リテラルが最初に現れる位置まで読み飛ばし、そこから一度だけ部分文字列として照合
+            // （候補位置ごとに照合し直すと入力長の二乗の時間がかかる）
+            Some(prefilter) if !self.anchored && prefilter.has_prefix() => {
+                match prefilter.candidates(s).next() {
+                    Some(start) => self.run(&s[start..]),
+                    None => Ok(false),
+                }
+            }
+            _ => self.run(s),
+        }
+    }
+
+    // コンパイル結果を実行
+    fn run(&self, s: &str) -> Result<bool, Error> {
+        match &self.program {
+            Program::Backtrack(m) => m.try_match(s),
+            Program::PikeVm(m) => Ok(m.is_match(s)),
+            Program::Nfa(nfa) => Ok(nfa.accept(s)),
+            Program::Dfa { dfa, .. } => Ok(dfa.accept(s)),
+            Program::Derivative(m) => Ok(m.is_match(s)),
+            Program::Literal { literal, anchored } if *anchored => Ok(s == literal),
+            Program::Literal { literal, .. } => Ok(s.contains(literal.as_str())),
+            Program::MultiLiteral { searcher, anchored } if *anchored => {
+                Ok(searcher.is_full_match(s))
+            }
+            Program::MultiLiteral { searcher, .. } => Ok(searcher.is_match(s)),
+        }
+    }
+
+    // 最も左で始まる一致範囲（同じ開始位置での選び方はMatchKindに従う）
+    // 最長一致はDFAで、リテラルは文字列検索で求める
+    // それ以外はPike VM（バックトラック型VMのエンジンと、後方参照・先読み・後読みを含む場合はバックトラック型VM）で求める
+    pub fn find(&self, s: &str) -> Result<Option<Match>, Error> {
+        if let Some(prefilter) = &self.prefilter {
+            if !prefilter.may_match(s) {
+                return Ok(None);
+            }
+        }
+        if self.anchored {
+            let full = Match {
+                start: 0,
+                end: s.len(),
+            };
+            return Ok(if self.run(s)? { Some(full) } else { None });
+        }
+
+        let longest = self.match_kind == MatchKind::LeftmostLongest;
+        let span = match &self.program {
+            Program::Dfa {
+                searcher: Some(searcher),
+                ..
+            } if longest => searcher.find(s),
+            // リテラルは一致範囲の長さが一定
+            Program::Literal { literal, .. } => {
+                s.find(literal.as_str()).map(|i| (i, i + literal.len()))
+            }
+            Program::MultiLiteral { searcher, .. } if longest => searcher
+                .find_iter(s)
+                .map(|m| (m.start, m.end))
+                .min_by_key(|&(start, end)| (start, std::cmp::Reverse(end))),
+            // 最左優先では同じ開始位置で先に書かれたリテラル
+            Program::MultiLiteral { searcher, .. } => searcher
+                .find_iter(s)
+                .min_by_key(|m| (m.start, m.pattern))
+                .map(|m| (m.start, m.end)),
+            _ => return self.find_by_vm(s),
+        };
+        Ok(span.map(|(start, end)| Match { start, end }))
+    }
+
+    // グループの範囲を求めるVMで一致範囲を求める
+    fn find_by_vm(&self, s: &str) -> Result<Option<Match>, Error> {
+        let machine = self.captures.as_ref().ok_or(Error::Unsupported {
+            engine: self.engine,
+            feature: "match spans",
+        })?;
+        let first = |slots: Slots| Some((slots.first().copied()??, slots.get(1).copied()??));
+        let span = match (&self.spans, self.match_kind) {
+            (Some(pike), MatchKind::LeftmostLongest) => pike.longest(s),
+            (Some(pike), MatchKind::LeftmostFirst) => pike.captures(s).and_then(first),
+            (None, MatchKind::LeftmostLongest) => machine.longest(s)?,
+            (None, MatchKind::LeftmostFirst) => machine.captures(s)?.and_then(first),
+        };
+
+        // 文字位置からバイト位置へ変換
+        let offsets = char_offsets(s);
+        Ok(span.map(|(start, end)| Match {
+            start: offsets[start],
+            end: offsets[end],
+        }))
+    }
+
+    // 最も左のマッチと各グループの一致範囲（グループ内の選択肢は左を優先する）
+    // 照合に使用するエンジンに関わらず、Pike VM（後方参照・先読み・後読みを含む場合はバックトラック型VM）で求める
+    // 最長一致の場合は、findで求めた範囲に収まるマッチのグループをバックトラック型VMで求める
+    pub fn captures(&self, s: &str) -> Result<Option<Captures>, Error> {
+        let machine = self.captures.as_ref().ok_or(Error::Unsupported {
+            engine: self.engine,
+            feature: "captures",
+        })?;
+        if let Some(prefilter) = &self.prefilter {
+            if !prefilter.may_match(s) {
+                return Ok(None);
+            }
+        }
+        let slots = if self.match_kind == MatchKind::LeftmostLongest {
+            let m = match self.find(s)? {
+                Some(m) => m,
+                None => return Ok(None),
+            };
+            let start = s[..m.start].chars().count();
+            let end = start + s[m.start..m.end].chars().count();
+            machine.captures_at(s, start, end)?
+        } else if let Some(pike) = &self.spans {
+            pike.captures(s)
+        } else {
+            machine.captures(s)?
+        };
+        let slots = match slots {
+            Some(slots) => slots,
+            None => return Ok(None),
+        };
+
+        // 文字位置からバイト位置へ変換
+        let offsets = char_offsets(s);
+        let slot = |i: usize| slots.get(i).copied().flatten();
+        let spans = (0..=self.group_count)
+            .map(|n| match (slot(2 * n), slot(2 * n + 1)) {
+                (Some(start), Some(end)) => Some(Match {
+                    start: offsets[start],
+                    end: offsets[end],
+                }),
+                _ => None,
+            })
+            .collect();
+        Ok(Some(Captures {
+            spans,
+            names: self.names.clone(),
+        }))
+    }
+
+    // グループ名に対応する番号
+    pub fn group_index(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    // グループ数（マッチ全体を除く）
+    pub fn group_count(&self) -> usize {
+        self.group_count
+    }
+
+    // 使用しているエンジン（Autoの場合は選択結果）
+    pub fn engine(&self) -> Engine {
+        self.engine
     }
 
     // 正規表現実行
@@ -65,10 +421,1443 @@ impl<'a> Regex<'a> {
     }
 }
 
+// 複数パターンの和を一つのDFAへ変換し、重なりを含む全ての一致箇所を求める
+// （パターンが一つの場合も使用できる）
+#[derive(Debug)]
+pub struct RegexSet {
+    patterns: Vec<String>,
+    searcher: OverlapSearcher,
+}
+
+impl RegexSet {
+    // 後方参照・先読み・後読み・共通部分・補集合はDFAの和として扱えない
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Result<Self, Error> {
+        RegexSetBuilder::new(patterns).build()
+    }
+
+    // パターンの数
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    // 番号に対応するパターン
+    pub fn pattern(&self, id: usize) -> &str {
+        &self.patterns[id]
+    }
+
+    // 重なりを含む全ての一致箇所（終了位置、パターンの番号、開始位置の順）
+    pub fn find_overlapping<'s, 'h>(&'s self, haystack: &'h str) -> Overlapping<'s, 'h> {
+        self.searcher.find_iter(haystack)
+    }
+
+    // いずれかのパターンが終わる位置（バイト位置）と、そこで終わる全てのパターンの番号
+    // 和のDFAで一度走査するのみで、開始位置は求めない
+    pub fn ends(&self, haystack: &str) -> Vec<(usize, Vec<usize>)> {
+        self.searcher.ends(haystack)
+    }
+}
+
+pub struct RegexSetBuilder {
+    patterns: Vec<String>,
+    max_dfa_states: Option<usize>,
+}
+
+impl RegexSetBuilder {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        RegexSetBuilder {
+            patterns: patterns.iter().map(|p| p.as_ref().to_string()).collect(),
+            max_dfa_states: None,
+        }
+    }
+
+    // 和のDFAおよび各パターンの逆向きDFAの状態数の上限
+    pub fn max_dfa_states(mut self, limit: usize) -> Self {
+        self.max_dfa_states = Some(limit);
+        self
+    }
+
+    // 後方参照・先読み・後読み・共通部分・補集合はDFAの和として扱えない
+    pub fn build(&self) -> Result<RegexSet, Error> {
+        let mut nfas = vec![];
+        let mut leading = vec![];
+        for pattern in self.patterns.iter() {
+            let ast = Ast::new(&Lexer::new(pattern).scan())
+                .try_parse()?
+                .optimize();
+            for (used, feature) in [
+                (ast.has_backref(), "backreferences"),
+                (ast.has_lookaround(), "lookaround"),
+                (ast.has_set_operator(), "set operators"),
+            ]
+            .iter()
+            {
+                if *used {
+                    return Err(Error::Unsupported {
+                        engine: Engine::Dfa,
+                        feature,
+                    });
+                }
+            }
+            nfas.push(Builder::from_ast(ast.clone()).to_nfa());
+            leading.push(Builder::from_ast(ast.leading_any()).to_nfa());
+        }
+
+        let limit = self.max_dfa_states.unwrap_or(usize::MAX);
+        let searcher =
+            OverlapSearcher::new(&nfas, leading, limit).ok_or(Error::TooManyDfaStates { limit })?;
+        Ok(RegexSet {
+            patterns: self.patterns.clone(),
+            searcher,
+        })
+    }
+}
+
+pub struct RegexBuilder<'a> {
+    pattern: &'a str,
+    engine: Engine,
+    anchored: bool,
+    case_insensitive: bool,
+    construction: Construction,
+    max_program_size: Option<usize>,
+    max_dfa_states: Option<usize>,
+    max_backtrack_steps: Option<usize>,
+    timeout: Option<Duration>,
+    prefilter: bool,
+    optimize: bool,
+    posix: bool,
+    match_kind: Option<MatchKind>,
+}
+
+impl<'a> RegexBuilder<'a> {
+    pub fn new(pattern: &'a str) -> Self {
+        RegexBuilder {
+            pattern,
+            engine: Engine::Auto,
+            anchored: false,
+            case_insensitive: false,
+            construction: Construction::Thompson,
+            max_program_size: None,
+            max_dfa_states: None,
+            max_backtrack_steps: None,
+            timeout: None,
+            prefilter: true,
+            optimize: true,
+            posix: false,
+            match_kind: None,
+        }
+    }
+
+    // 使用するエンジン
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    // trueの場合は入力全体、falseの場合は部分文字列へマッチ
+    pub fn anchored(mut self, yes: bool) -> Self {
+        self.anchored = yes;
+        self
+    }
+
+    // 大文字・小文字を区別しない
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.case_insensitive = yes;
+        self
+    }
+
+    // NFAの構成法
+    pub fn construction(mut self, construction: Construction) -> Self {
+        self.construction = construction;
+        self
+    }
+
+    // コンパイル結果（VM命令数・NFAルール数）の上限
+    pub fn max_program_size(mut self, limit: usize) -> Self {
+        self.max_program_size = Some(limit);
+        self
+    }
+
+    // DFA状態数の上限
+    pub fn max_dfa_states(mut self, limit: usize) -> Self {
+        self.max_dfa_states = Some(limit);
+        self
+    }
+
+    // バックトラック型VMの実行ステップ数の上限
+    pub fn max_backtrack_steps(mut self, limit: usize) -> Self {
+        self.max_backtrack_steps = Some(limit);
+        self
+    }
+
+    // バックトラック型VMの実行時間の上限
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    // リテラルによる事前フィルタを使用するか
+    pub fn prefilter(mut self, yes: bool) -> Self {
+        self.prefilter = yes;
+        self
+    }
+
+    // ASTとVM命令列を最適化するか
+    pub fn optimize(mut self, yes: bool) -> Self {
+        self.optimize = yes;
+        self
+    }
+
+    // POSIX ERE互換モード（findは常に最も左で始まる最長の一致範囲を返す）
+    // 一致範囲をDFAで求めるため、エンジンは自動選択・DFA・文字列検索のみ
+    // POSIXの規則に従うのは一致範囲全体のみで、グループはその範囲に収まるマッチのうち
+    // 選択肢の左を優先するものから求める（部分式ごとの最長の規則には従わない）
+    pub fn posix(mut self, yes: bool) -> Self {
+        self.posix = yes;
+        self
+    }
+
+    // 一致範囲の選び方（既定は最左優先、POSIXモードとDFAエンジンでは最長一致）
+    // DFAは最左優先の一致範囲を求められないため、DFAエンジンで最左優先を指定した場合はエラー
+    pub fn match_kind(mut self, kind: MatchKind) -> Self {
+        self.match_kind = Some(kind);
+        self
+    }
+
+    // 設定に従ってコンパイル
+    pub fn build(&self) -> Result<Regex<'a>, Error> {
+        if self.posix && !matches!(self.engine, Engine::Auto | Engine::Dfa | Engine::Literal) {
+            return Err(Error::Unsupported {
+                engine: self.engine,
+                feature: "POSIX mode",
+            });
+        }
+        let match_kind = match self.match_kind {
+            Some(kind) => kind,
+            None if self.posix => MatchKind::LeftmostLongest,
+            // DFAは選択肢の優先順位を区別しないため、最長一致とする
+            None if self.engine == Engine::Dfa => MatchKind::LeftmostLongest,
+            None => MatchKind::LeftmostFirst,
+        };
+        if self.posix && match_kind == MatchKind::LeftmostFirst {
+            return Err(Error::Unsupported {
+                engine: self.engine,
+                feature: "leftmost-first in POSIX mode",
+            });
+        }
+        if self.engine == Engine::Dfa && match_kind == MatchKind::LeftmostFirst {
+            return Err(Error::Unsupported {
+                engine: Engine::Dfa,
+                feature: "leftmost-first match kind",
+            });
+        }
+        let mut ast = Ast::new(&Lexer::new(self.pattern).scan()).try_parse()?;
+        if self.case_insensitive {
+            ast = ast.ignore_case();
+        }
+        let groups = ast.groups();
+        let names: HashMap<String, usize> = groups
+            .iter()
+            .filter_map(|(n, name)| Some((name.clone()?, *n)))
+            .collect();
+        let inst = self.compile_captures(&ast);
+        let captures = inst
+            .clone()
+            .map(|inst| self.backtrack_machine(inst).full_match(self.anchored));
+        // 後方参照・先読み・後読みはPike VMで扱えない
+        let spans = match inst {
+            Some(inst) if !ast.has_backref() && !ast.has_lookaround() => {
+                Some(PikeMachine::from_inst(inst).full_match(self.anchored))
+            }
+            _ => None,
+        };
+
+        // リテラル、またはリテラルの選択のみのパターンは文字列検索で処理
+        let analysis = Analysis::new(&ast);
+        let program = match (analysis.literal, analysis.alternation) {
+            (Some(literal), _) => Some(Program::Literal {
+                literal,
+                anchored: self.anchored,
+            }),
+            (_, Some(literals)) => Some(Program::MultiLiteral {
+                searcher: MultiLiteral::new(&literals),
+                anchored: self.anchored,
+            }),
+            _ => None,
+        };
+        match (self.engine, program) {
+            (Engine::Auto, Some(program)) | (Engine::Literal, Some(program)) => {
+                return Ok(Regex {
+                    pattern: self.pattern,
+                    engine: Engine::Literal,
+                    prefilter: None,
+                    anchored: self.anchored,
+                    program,
+                    captures,
+                    spans,
+                    match_kind,
+                    names,
+                    group_count: groups.len(),
+                })
+            }
+            (Engine::Literal, None) => {
+                return Err(Error::Unsupported {
+                    engine: Engine::Literal,
+                    feature: "non-literal pattern",
+                })
+            }
+            _ => {}
+        }
+
+        // リテラル以外はエンジンへ渡す前に最適化
+        if self.optimize {
+            ast = ast.optimize();
+        }
+
+        // 一致範囲の検索には元のパターンを使用
+        let pattern = ast.clone();
+
+        // 部分文字列検索で先頭リテラルがある場合は、その最初の出現位置から照合
+        // 後読みは入力の途中から照合すると前の文字を参照できないため、使用しない
+        let prefilter = if self.prefilter && !ast.looks_behind() {
+            Prefilter::new(&ast)
+        } else {
+            None
+        };
+        if !self.anchored {
+            ast = ast.unanchored();
+        }
+
+        let (engine, program) = match self.engine {
+            _ if self.posix => self.build_posix(ast, &pattern)?,
+            // 後方参照・先読み・後読みはバックトラック型VMでのみ扱える
+            _ if ast.has_backref() => self.build_backtrack_only(ast, "backreferences")?,
+            _ if ast.has_lookaround() => self.build_backtrack_only(ast, "lookaround")?,
+            // 共通部分・補集合はDFAか微分でのみ扱える
+            _ if ast.has_set_operator() => self.build_set_operator(ast)?,
+            Engine::Backtrack => self.build_backtrack(ast)?,
+            Engine::PikeVm => {
+                let inst = self.compile_vm(ast)?;
+                let m = PikeMachine::from_inst(inst).full_match(true);
+                (Engine::PikeVm, Program::PikeVm(m))
+            }
+            Engine::Nfa => (Engine::Nfa, Program::Nfa(self.compile_nfa(ast)?)),
+            Engine::Dfa => {
+                let limit = self.max_dfa_states.unwrap_or(usize::MAX);
+                let program = self
+                    .compile_dfa(ast, &pattern, limit)?
+                    .ok_or(Error::TooManyDfaStates { limit })?;
+                (Engine::Dfa, program)
+            }
+            Engine::Derivative if ast.has_assertion() => {
+                return Err(Error::Unsupported {
+                    engine: Engine::Derivative,
+                    feature: "assertions",
+                })
+            }
+            Engine::Derivative if ast.has_property() => {
+                return Err(Error::Unsupported {
+                    engine: Engine::Derivative,
+                    feature: "unicode properties",
+                })
+            }
+            Engine::Derivative => (
+                Engine::Derivative,
+                Program::Derivative(DerivativeMachine::new(&ast)),
+            ),
+            _ => self.build_auto(ast, &pattern)?,
+        };
+
+        Ok(Regex {
+            pattern: self.pattern,
+            engine,
+            program,
+            prefilter,
+            anchored: self.anchored,
+            captures,
+            spans: if engine == Engine::Backtrack {
+                None
+            } else {
+                spans
+            },
+            match_kind,
+            names,
+            group_count: groups.len(),
+        })
+    }
+
+    // POSIXモードでは一致範囲を求めるDFAへコンパイル（状態数が上限を超えてもフォールバックしない）
+    fn build_posix(&self, ast: AstTree, pattern: &AstTree) -> Result<(Engine, Program), Error> {
+        for (used, feature) in [
+            (ast.has_backref(), "backreferences"),
+            (ast.has_lookaround(), "lookaround"),
+            (ast.has_set_operator(), "set operators"),
+        ]
+        .iter()
+        {
+            if *used {
+                return Err(Error::Unsupported {
+                    engine: Engine::Dfa,
+                    feature,
+                });
+            }
+        }
+        let limit = self.max_dfa_states.unwrap_or(usize::MAX);
+        let program = self
+            .compile_dfa(ast, pattern, limit)?
+            .ok_or(Error::TooManyDfaStates { limit })?;
+        Ok((Engine::Dfa, program))
+    }
+
+    // バックトラック型VMへコンパイル
+    fn build_backtrack(&self, ast: AstTree) -> Result<(Engine, Program), Error> {
+        let inst = self.compile_vm(ast)?;
+        let m = self.backtrack_machine(inst).full_match(true);
+        Ok((Engine::Backtrack, Program::Backtrack(m)))
+    }
+
+    // 上限を設定したバックトラック型VM
+    fn backtrack_machine(&self, inst: Vec<RegexIR>) -> Machine {
+        let mut m = Machine::from_inst(inst);
+        if let Some(limit) = self.max_backtrack_steps {
+            m = m.max_steps(limit);
+        }
+        if let Some(timeout) = self.timeout {
+            m = m.timeout(timeout);
+        }
+        m
+    }
+
+    // グループの範囲を求めるVMの命令列（マッチ全体を0番のグループとする）
+    // 共通部分・補集合、長さの上限がない後読みを含む場合と、命令数が上限を超える場合はNone
+    fn compile_captures(&self, ast: &AstTree) -> Option<Vec<RegexIR>> {
+        if ast.has_set_operator() || has_unbounded_lookbehind(ast) {
+            return None;
+        }
+        let mut ast = ast.clone();
+        if self.optimize {
+            ast = ast.optimize();
+        }
+        self.compile_vm(AstTree::Capture(0, None, Box::new(ast)))
+            .ok()
+    }
+
+    // バックトラック型VMでのみ扱える機能を含むパターンをコンパイル
+    fn build_backtrack_only(
+        &self,
+        ast: AstTree,
+        feature: &'static str,
+    ) -> Result<(Engine, Program), Error> {
+        match self.engine {
+            // 共通部分・補集合とは併用できない
+            _ if ast.has_set_operator() => Err(Error::Unsupported {
+                engine: Engine::Backtrack,
+                feature: "set operators",
+            }),
+            _ if has_unbounded_lookbehind(&ast) => Err(Error::Unsupported {
+                engine: Engine::Backtrack,
+                feature: "unbounded lookbehind",
+            }),
+            Engine::Backtrack | Engine::Auto => self.build_backtrack(ast),
+            engine => Err(Error::Unsupported { engine, feature }),
+        }
+    }
+
+    // 共通部分・補集合を含むパターンをコンパイル
+    fn build_set_operator(&self, ast: AstTree) -> Result<(Engine, Program), Error> {
+        // 表明・プロパティは直積・微分で扱えない
+        if ast.has_assertion() {
+            return Err(Error::Unsupported {
+                engine: self.engine,
+                feature: "assertions with set operators",
+            });
+        }
+        if ast.has_property() {
+            return Err(Error::Unsupported {
+                engine: self.engine,
+                feature: "unicode properties with set operators",
+            });
+        }
+        let limit = match self.engine {
+            Engine::Dfa => self.max_dfa_states.unwrap_or(usize::MAX),
+            Engine::Auto => self
+                .max_dfa_states
+                .map_or(AUTO_DFA_MAX_STATES, |l| l.min(AUTO_DFA_MAX_STATES)),
+            Engine::Derivative => {
+                let m = DerivativeMachine::new(&ast);
+                return Ok((Engine::Derivative, Program::Derivative(m)));
+            }
+            engine => {
+                return Err(Error::Unsupported {
+                    engine,
+                    feature: "set operators",
+                })
+            }
+        };
+
+        match algebra::compile(&ast, limit) {
+            Some(dfa) => Ok((
+                Engine::Dfa,
+                Program::Dfa {
+                    dfa,
+                    searcher: None,
+                },
+            )),
+            // 自動選択で状態数が増えすぎる場合は微分で照合
+            None if self.engine == Engine::Auto => {
+                let m = DerivativeMachine::new(&ast);
+                Ok((Engine::Derivative, Program::Derivative(m)))
+            }
+            None => Err(Error::TooManyDfaStates { limit }),
+        }
+    }
+
+    // パターン解析に基づきエンジンを選択してコンパイル
+    fn build_auto(&self, ast: AstTree, pattern: &AstTree) -> Result<(Engine, Program), Error> {
+        // DFA状態数の見積もりが上限以下であればDFAを試み、実際の状態数が上限を超える場合はPike VMを使用
+        let limit = self
+            .max_dfa_states
+            .map_or(AUTO_DFA_MAX_STATES, |l| l.min(AUTO_DFA_MAX_STATES));
+        if Analysis::new(&ast).dfa_state_estimate() <= limit {
+            if let Some(program) = self.compile_dfa(ast.clone(), pattern, limit)? {
+                return Ok((Engine::Dfa, program));
+            }
+        }
+
+        let inst = self.compile_vm(ast)?;
+        let m = PikeMachine::from_inst(inst).full_match(true);
+        Ok((Engine::PikeVm, Program::PikeVm(m)))
+    }
+
+    // VM命令列へコンパイル
+    fn compile_vm(&self, ast: AstTree) -> Result<Vec<RegexIR>, Error> {
+        let mut inst = VmBuilder::from_ast(ast).compile();
+        if self.optimize {
+            inst = optimize::optimize(inst);
+        }
+        self.check_program_size(inst.len())?;
+        Ok(inst)
+    }
+
+    // NFAへコンパイル
+    fn compile_nfa(&self, ast: AstTree) -> Result<NFAProgram, Error> {
+        let nfa = match self.construction {
+            Construction::Glushkov if ast.has_assertion() => {
+                return Err(Error::Unsupported {
+                    engine: self.engine,
+                    feature: "assertions in Glushkov construction",
+                })
+            }
+            Construction::Thompson => Builder::from_ast(ast).to_nfa(),
+            Construction::Glushkov => Glushkov::to_nfa(&ast),
+        };
+        self.check_program_size(nfa.size())?;
+        Ok(nfa)
+    }
+
+    // DFAと一致範囲検索用のDFAへコンパイル（状態数が上限を超えた場合はNone）
+    fn compile_dfa(
+        &self,
+        ast: AstTree,
+        pattern: &AstTree,
+        limit: usize,
+    ) -> Result<Option<Program>, Error> {
+        let dfa = match self.compile_nfa(ast)?.to_dfa(limit) {
+            Some(dfa) => dfa,
+            None => return Ok(None),
+        };
+        let nfa = self.compile_nfa(pattern.clone())?;
+        let trailing = self.compile_nfa(pattern.clone().trailing_any())?;
+        Ok(
+            DFASearcher::new(&nfa, &trailing, limit).map(|searcher| Program::Dfa {
+                dfa,
+                searcher: Some(searcher),
+            }),
+        )
+    }
+
+    // コンパイル結果のサイズを確認
+    fn check_program_size(&self, size: usize) -> Result<(), Error> {
+        match self.max_program_size {
+            Some(limit) if size > limit => Err(Error::ProgramTooLarge { size, limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+// 各文字位置（と末尾）のバイト位置
+fn char_offsets(s: &str) -> Vec<usize> {
+    s.char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(s.len()))
+        .collect()
+}
+
+// 長さの上限がない後読みを含むか
+fn has_unbounded_lookbehind(ast: &AstTree) -> bool {
+    match ast {
+        AstTree::Look(look, a) if look.is_behind() && a.width().1.is_none() => true,
+        _ => ast.children().into_iter().any(has_unbounded_lookbehind),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    const ENGINES: [Engine; 6] = [
+        Engine::Backtrack,
+        Engine::PikeVm,
+        Engine::Nfa,
+        Engine::Dfa,
+        Engine::Derivative,
+        Engine::Auto,
+    ];
+
+    #[test]
+    fn test_builder_engine() {
+        for engine in ENGINES.iter() {
+            let re = RegexBuilder::new("a+b").engine(*engine).build().unwrap();
+
+            assert_eq!(Ok(true), re.is_match("ab"));
+            assert_eq!(Ok(true), re.is_match("zaabz"));
+            assert_eq!(Ok(false), re.is_match("ba"));
+        }
+    }
+
+    #[test]
+    fn test_builder_anchored() {
+        for engine in ENGINES.iter() {
+            let re = RegexBuilder::new("a+b")
+                .engine(*engine)
+                .anchored(true)
+                .build()
+                .unwrap();
+
+            assert_eq!(Ok(true), re.is_match("aab"));
+            assert_eq!(Ok(false), re.is_match("aabz"));
+            assert_eq!(Ok(false), re.is_match("zab"));
+        }
+    }
+
+    #[test]
+    fn test_builder_case_insensitive() {
+        for engine in ENGINES.iter() {
+            let re = RegexBuilder::new("ab")
+                .engine(*engine)
+                .case_insensitive(true)
+                .build()
+                .unwrap();
+
+            assert_eq!(Ok(true), re.is_match("xAbx"));
+            assert_eq!(Ok(true), re.is_match("aB"));
+            assert_eq!(Ok(false), re.is_match("AC"));
+        }
+    }
+
+    #[test]
+    fn test_builder_auto() {
+        {
+            let re = RegexBuilder::new("abc").build().unwrap();
+
+            assert_eq!(Engine::Literal, re.engine());
+            assert_eq!(Ok(true), re.is_match("zabcz"));
+            assert_eq!(Ok(false), re.is_match("ab"));
+        }
+        {
+            let re = RegexBuilder::new("abc").anchored(true).build().unwrap();
+
+            assert_eq!(Engine::Literal, re.engine());
+            assert_eq!(Ok(true), re.is_match("abc"));
+            assert_eq!(Ok(false), re.is_match("zabc"));
+        }
+        {
+            let re = RegexBuilder::new("ab+c").build().unwrap();
+
+            assert_eq!(Engine::Dfa, re.engine());
+            assert_eq!(Ok(true), re.is_match("zabbcz"));
+        }
+        {
+            // 大文字・小文字を区別しない場合はリテラルではない
+            let re = RegexBuilder::new("abc")
+                .case_insensitive(true)
+                .build()
+                .unwrap();
+
+            assert_eq!(Engine::Dfa, re.engine());
+            assert_eq!(Ok(true), re.is_match("ABC"));
+        }
+        {
+            let re = RegexBuilder::new("(a|b)*a(a|b)(a|b)(a|b)(a|b)(a|b)")
+                .build()
+                .unwrap();
+
+            assert_eq!(Engine::PikeVm, re.engine());
+            assert_eq!(Ok(true), re.is_match("abbbbb"));
+        }
+        {
+            // 状態数の見積もり（前後の.*を含めて6）が上限を超える場合はDFAを試みない
+            let re = RegexBuilder::new("ab+c").max_dfa_states(5).build().unwrap();
+
+            assert_eq!(Engine::PikeVm, re.engine());
+            assert_eq!(Ok(true), re.is_match("zabbcz"));
+        }
+    }
+
+    #[test]
+    fn test_builder_literal() {
+        let re = RegexBuilder::new("ab")
+            .engine(Engine::Literal)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(true), re.is_match("cab"));
+
+        assert_eq!(
+            Some(Error::Unsupported {
+                engine: Engine::Literal,
+                feature: "non-literal pattern",
+            }),
+            RegexBuilder::new("ab*")
+                .engine(Engine::Literal)
+                .build()
+                .err()
+        );
+    }
+
+    #[test]
+    fn test_builder_backref() {
+        let re = RegexBuilder::new(r"(ab|cd)x\1").build().unwrap();
+        assert_eq!(Engine::Backtrack, re.engine());
+        assert_eq!(Ok(true), re.is_match("zcdxcdz"));
+        assert_eq!(Ok(false), re.is_match("abxcd"));
+
+        for engine in [Engine::PikeVm, Engine::Nfa, Engine::Dfa, Engine::Derivative].iter() {
+            assert_eq!(
+                Some(Error::Unsupported {
+                    engine: *engine,
+                    feature: "backreferences",
+                }),
+                RegexBuilder::new(r"(a)\1").engine(*engine).build().err()
+            );
+        }
+        assert_eq!(
+            Some(Error::Unsupported {
+                engine: Engine::Backtrack,
+                feature: "set operators",
+            }),
+            RegexBuilder::new(r"(a)\1&~b").build().err()
+        );
+    }
+
+    #[test]
+    fn test_builder_lookaround() {
+        let re = RegexBuilder::new("(?<=x)ab(?!c)").build().unwrap();
+        assert_eq!(Engine::Backtrack, re.engine());
+        assert_eq!(Ok(true), re.is_match("zxabd"));
+        assert_eq!(Ok(false), re.is_match("zxabc"));
+        assert_eq!(Ok(false), re.is_match("zyabd"));
+
+        assert_eq!(
+            Some(Error::Unsupported {
+                engine: Engine::Dfa,
+                feature: "lookaround",
+            }),
+            RegexBuilder::new("a(?=b)")
+                .engine(Engine::Dfa)
+                .build()
+                .err()
+        );
+        assert_eq!(
+            Some(Error::Unsupported {
+                engine: Engine::Backtrack,
+                feature: "unbounded lookbehind",
+            }),
+            RegexBuilder::new("(?<=a+)b").build().err()
+        );
+    }
+
+    #[test]
+    fn test_builder_assertion() {
+        let patterns = [r"\bab\b", r"\Ba+", r"\Aab|b\z", r"a\b.", r"(?:\b.)+\z"];
+        let inputs = [
+            "", "ab", "xab", "ab.", "a.b", "aab", "ba", "b", "b.a", "a.", "..ab..", "ab_ab",
+        ];
+        let engines = [Engine::Backtrack, Engine::PikeVm, Engine::Nfa, Engine::Dfa];
+        for pattern in patterns.iter() {
+            for anchored in [false, true].iter() {
+                let expected = RegexBuilder::new(pattern)
+                    .engine(Engine::Backtrack)
+                    .anchored(*anchored)
+                    .build()
+                    .unwrap();
+                let regexes: Vec<Regex> = engines
+                    .iter()
+                    .chain([Engine::Auto].iter())
+                    .map(|engine| {
+                        RegexBuilder::new(pattern)
+                            .engine(*engine)
+                            .anchored(*anchored)
+                            .build()
+                            .unwrap()
+                    })
+                    .collect();
+                for input in inputs.iter() {
+                    let matched = expected.is_match(input);
+                    for re in regexes.iter() {
+                        assert_eq!(matched, re.is_match(input), "{} {}", pattern, input);
+                    }
+                }
+            }
+        }
+
+        // 一致範囲はマッチ位置の前後の文字から判定する
+        let re = RegexBuilder::new(r"\bab\b")
+            .engine(Engine::Dfa)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(Some(Match { start: 5, end: 7 })), re.find("abc, ab."));
+
+        for (engine, construction, pattern, feature) in [
+            (
+                Engine::Derivative,
+                Construction::Thompson,
+                r"\ba",
+                "assertions",
+            ),
+            (
+                Engine::Nfa,
+                Construction::Glushkov,
+                r"\ba",
+                "assertions in Glushkov construction",
+            ),
+            (
+                Engine::Dfa,
+                Construction::Thompson,
+                r"\ba&a",
+                "assertions with set operators",
+            ),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Some(Error::Unsupported {
+                    engine: *engine,
+                    feature,
+                }),
+                RegexBuilder::new(pattern)
+                    .engine(*engine)
+                    .construction(*construction)
+                    .build()
+                    .err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_builder_property() {
+        let patterns = [
+            r"\p{Greek}+",
+            r"[a\p{Nd}]+b",
+            r"\P{L}\p{Lu}",
+            r"\p{L}*\p{Greek}",
+            r"\b\p{Han}+\b",
+            r"(?:\p{White_Space}|x)\P{White_Space}",
+        ];
+        let inputs = [
+            "",
+            "αβγ",
+            "aα",
+            "a1b",
+            "٣٣b",
+            "x Ab",
+            "1Σ",
+            "abc",
+            "ωa",
+            "漢字",
+            "a漢",
+            "x\u{3000}y",
+            "  ",
+        ];
+        let builders = [
+            (Engine::Backtrack, Construction::Thompson),
+            (Engine::PikeVm, Construction::Thompson),
+            (Engine::Nfa, Construction::Thompson),
+            (Engine::Nfa, Construction::Glushkov),
+            (Engine::Dfa, Construction::Thompson),
+            (Engine::Dfa, Construction::Glushkov),
+            (Engine::Auto, Construction::Thompson),
+        ];
+        for pattern in patterns.iter() {
+            for anchored in [false, true].iter() {
+                let expected = RegexBuilder::new(pattern)
+                    .engine(Engine::Backtrack)
+                    .anchored(*anchored)
+                    .build()
+                    .unwrap();
+                for (engine, construction) in builders.iter() {
+                    let re = match RegexBuilder::new(pattern)
+                        .engine(*engine)
+                        .construction(*construction)
+                        .anchored(*anchored)
+                        .build()
+                    {
+                        Ok(re) => re,
+                        // Glushkov構成は表明を扱えない
+                        Err(Error::Unsupported { .. }) if pattern.contains(r"\b") => continue,
+                        Err(e) => panic!("{} {:?}", pattern, e),
+                    };
+                    for input in inputs.iter() {
+                        assert_eq!(
+                            expected.is_match(input),
+                            re.is_match(input),
+                            "{} {:?} {:?}",
+                            pattern,
+                            engine,
+                            input
+                        );
+                    }
+                }
+            }
+        }
+
+        // 一致範囲はバイト単位
+        for engine in [Engine::Dfa, Engine::Auto].iter() {
+            let re = RegexBuilder::new(r"\p{Greek}+")
+                .engine(*engine)
+                .build()
+                .unwrap();
+            assert_eq!(Ok(Some(Match { start: 3, end: 7 })), re.find("ab αβ c"));
+        }
+
+        for (engine, pattern, feature) in [
+            (Engine::Derivative, r"\p{L}", "unicode properties"),
+            (
+                Engine::Dfa,
+                r"\p{L}&a",
+                "unicode properties with set operators",
+            ),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Some(Error::Unsupported {
+                    engine: *engine,
+                    feature,
+                }),
+                RegexBuilder::new(pattern).engine(*engine).build().err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_builder_posix() {
+        // 選択肢の順序に関わらず最長の範囲
+        let re = RegexBuilder::new("(a|ab)c?").posix(true).build().unwrap();
+        assert_eq!(Engine::Dfa, re.engine());
+        assert_eq!(Ok(Some(Match { start: 1, end: 4 })), re.find("xabc"));
+        let caps = re.captures("xabc").unwrap().unwrap();
+        assert_eq!(Some(Match { start: 1, end: 4 }), caps.get(0));
+        assert_eq!(Some(Match { start: 1, end: 3 }), caps.get(1));
+
+        // 通常は左の選択肢を優先する
+        let caps = RegexBuilder::new("(a|ab)c?")
+            .build()
+            .unwrap()
+            .captures("xabc")
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(Match { start: 1, end: 2 }), caps.get(0));
+
+        // 自動選択でPike VMとなるパターンも、DFAで一致範囲を求める
+        let pattern = "(abcd|efgh|ijkl|mnop|qrst)+z";
+        assert_eq!(
+            Engine::PikeVm,
+            RegexBuilder::new(pattern).build().unwrap().engine()
+        );
+        let re = RegexBuilder::new(pattern).posix(true).build().unwrap();
+        assert_eq!(
+            Ok(Some(Match { start: 2, end: 11 })),
+            re.find("..ijklabcdz.")
+        );
+
+        for (engine, pattern, expected) in [
+            (
+                Engine::PikeVm,
+                "ab",
+                Error::Unsupported {
+                    engine: Engine::PikeVm,
+                    feature: "POSIX mode",
+                },
+            ),
+            (
+                Engine::Auto,
+                "(a)b\\1",
+                Error::Unsupported {
+                    engine: Engine::Dfa,
+                    feature: "backreferences",
+                },
+            ),
+            (
+                Engine::Dfa,
+                "a&b",
+                Error::Unsupported {
+                    engine: Engine::Dfa,
+                    feature: "set operators",
+                },
+            ),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Some(expected.clone()),
+                RegexBuilder::new(pattern)
+                    .engine(*engine)
+                    .posix(true)
+                    .build()
+                    .err()
+            );
+        }
+        assert_eq!(
+            Some(Error::TooManyDfaStates { limit: 2 }),
+            RegexBuilder::new("(a|b)*a(a|b)")
+                .posix(true)
+                .max_dfa_states(2)
+                .build()
+                .err()
+        );
+    }
+
+    #[test]
+    fn test_regex_captures() {
+        let re = RegexBuilder::new("(?P<year>....)m(?<month>..)(?:d(..))?")
+            .build()
+            .unwrap();
+        assert_eq!(3, re.group_count());
+        assert_eq!(Some(1), re.group_index("year"));
+        assert_eq!(Some(2), re.group_index("month"));
+        assert_eq!(None, re.group_index("day"));
+
+        let caps = re.captures("on 2024m05!").unwrap().unwrap();
+        assert_eq!(4, caps.group_count());
+        assert_eq!(Some(Match { start: 3, end: 10 }), caps.get(0));
+        assert_eq!(Some(Match { start: 3, end: 7 }), caps.name("year"));
+        assert_eq!(Some(Match { start: 8, end: 10 }), caps.get(2));
+        assert_eq!(None, caps.get(3));
+        assert_eq!(None, caps.name("day"));
+
+        let caps = re.captures("2024m05d17").unwrap().unwrap();
+        assert_eq!(Some(Match { start: 8, end: 10 }), caps.get(3));
+        assert_eq!(Ok(None), re.captures("2024"));
+
+        // 位置はバイト単位
+        let re = RegexBuilder::new("あ(い+)").build().unwrap();
+        let caps = re.captures("xあいい").unwrap().unwrap();
+        assert_eq!(Some(Match { start: 4, end: 10 }), caps.get(1));
+
+        // 全体一致
+        let re = RegexBuilder::new("(a+)(b*)")
+            .anchored(true)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(None), re.captures("aabc"));
+        let caps = re.captures("aab").unwrap().unwrap();
+        assert_eq!(Some(Match { start: 0, end: 2 }), caps.get(1));
+
+        assert_eq!(
+            Some(Error::Unsupported {
+                engine: Engine::Dfa,
+                feature: "captures",
+            }),
+            RegexBuilder::new("(a)&b")
+                .build()
+                .unwrap()
+                .captures("a")
+                .err()
+        );
+    }
+
+    #[test]
+    fn test_builder_multi_literal() {
+        let re = RegexBuilder::new("error|warn|fatal").build().unwrap();
+        assert_eq!(Engine::Literal, re.engine());
+        assert_eq!(Ok(true), re.is_match("[x] warning"));
+        assert_eq!(Ok(false), re.is_match("info"));
+
+        let re = RegexBuilder::new("ab|abc").anchored(true).build().unwrap();
+        assert_eq!(Ok(true), re.is_match("abc"));
+        assert_eq!(Ok(false), re.is_match("abcd"));
+
+        // 大文字・小文字を区別しない場合も選択として扱える
+        let re = RegexBuilder::new("ok|ng")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        assert_eq!(Engine::Dfa, re.engine());
+        assert_eq!(Ok(true), re.is_match("NG"));
+    }
+
+    #[test]
+    fn test_regex_try_new() {
+        assert_eq!(Ok(true), Regex::try_new("a+b").unwrap().is_match("xaab"));
+        assert_eq!(
+            Some(Error::Unsupported {
+                engine: Engine::Backtrack,
+                feature: "unbounded lookbehind",
+            }),
+            Regex::try_new("(?<=a+)b").err()
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        for engine in [Engine::Dfa, Engine::Auto].iter() {
+            let re = RegexBuilder::new("ab+|b").engine(*engine).build().unwrap();
+
+            let m = re.find("xxabbbx").unwrap().unwrap();
+            assert_eq!(Match { start: 2, end: 6 }, m);
+            assert_eq!("abbb", m.as_str("xxabbbx"));
+            assert_eq!(Ok(Some(Match { start: 1, end: 2 })), re.find("cbab"));
+            assert_eq!(Ok(None), re.find("xxx"));
+        }
+
+        let re = RegexBuilder::new("ab|abc|bc").build().unwrap();
+        assert_eq!(Ok(Some(Match { start: 1, end: 3 })), re.find("xabc"));
+        let re = RegexBuilder::new("ab|abc|bc")
+            .match_kind(MatchKind::LeftmostLongest)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(Some(Match { start: 1, end: 4 })), re.find("xabc"));
+        let re = RegexBuilder::new("bc").build().unwrap();
+        assert_eq!(Ok(Some(Match { start: 1, end: 3 })), re.find("abc"));
+
+        let re = RegexBuilder::new("a+")
+            .engine(Engine::Dfa)
+            .anchored(true)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(Some(Match { start: 0, end: 2 })), re.find("aa"));
+        assert_eq!(Ok(None), re.find("aab"));
+
+        // DFA以外のエンジンではVMで求める
+        for engine in [
+            Engine::Backtrack,
+            Engine::PikeVm,
+            Engine::Nfa,
+            Engine::Derivative,
+        ]
+        .iter()
+        {
+            let re = RegexBuilder::new("ab+|b").engine(*engine).build().unwrap();
+            assert_eq!(Ok(Some(Match { start: 2, end: 6 })), re.find("xxabbbx"));
+            assert_eq!(Ok(None), re.find("xxx"));
+        }
+        let re = RegexBuilder::new(r"(a+)x\1").build().unwrap();
+        assert_eq!(Engine::Backtrack, re.engine());
+        assert_eq!(Ok(Some(Match { start: 1, end: 6 })), re.find("baaxaab"));
+
+        // 共通部分・補集合は範囲を求められない
+        assert_eq!(
+            Err(Error::Unsupported {
+                engine: Engine::Dfa,
+                feature: "match spans",
+            }),
+            RegexBuilder::new("(a|b)+&~(ab)")
+                .engine(Engine::Dfa)
+                .build()
+                .unwrap()
+                .find("a")
+        );
+    }
+
+    #[test]
+    fn test_builder_match_kind() {
+        for engine in ENGINES.iter().filter(|e| **e != Engine::Dfa) {
+            let first = RegexBuilder::new("(a|ab)(c|d)?")
+                .engine(*engine)
+                .build()
+                .unwrap();
+            let longest = RegexBuilder::new("(a|ab)(c|d)?")
+                .engine(*engine)
+                .match_kind(MatchKind::LeftmostLongest)
+                .build()
+                .unwrap();
+
+            // 既定は最左優先で、選択肢の左を優先するとa、最長ではabc
+            assert_eq!(
+                Ok(Some(Match { start: 1, end: 2 })),
+                first.find("xabc"),
+                "{:?}",
+                engine
+            );
+            assert_eq!(
+                Ok(Some(Match { start: 1, end: 4 })),
+                longest.find("xabc"),
+                "{:?}",
+                engine
+            );
+
+            // グループは一致範囲に収まるマッチから求める
+            let caps = first.captures("xabc").unwrap().unwrap();
+            assert_eq!(Some(Match { start: 1, end: 2 }), caps.get(1));
+            assert_eq!(None, caps.get(2));
+            let caps = longest.captures("xabc").unwrap().unwrap();
+            assert_eq!(Some(Match { start: 1, end: 3 }), caps.get(1));
+            assert_eq!(Some(Match { start: 3, end: 4 }), caps.get(2));
+        }
+
+        // DFAでは指定がなければ最長一致で、最左優先は指定できない
+        let longest = RegexBuilder::new("(a|ab)(c|d)?")
+            .engine(Engine::Dfa)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(Some(Match { start: 1, end: 4 })), longest.find("xabc"));
+        assert_eq!(
+            Some(Error::Unsupported {
+                engine: Engine::Dfa,
+                feature: "leftmost-first match kind",
+            }),
+            RegexBuilder::new("(a|ab)(c|d)?")
+                .engine(Engine::Dfa)
+                .match_kind(MatchKind::LeftmostFirst)
+                .build()
+                .err()
+        );
+
+        // 文字列の選択
+        let re = RegexBuilder::new("ab|abc").build().unwrap();
+        assert_eq!(Engine::Literal, re.engine());
+        assert_eq!(Ok(Some(Match { start: 1, end: 3 })), re.find("xabc"));
+        let re = RegexBuilder::new("ab|abc")
+            .match_kind(MatchKind::LeftmostLongest)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(Some(Match { start: 1, end: 4 })), re.find("xabc"));
+
+        assert_eq!(
+            Some(Error::Unsupported {
+                engine: Engine::Auto,
+                feature: "leftmost-first in POSIX mode",
+            }),
+            RegexBuilder::new("a")
+                .posix(true)
+                .match_kind(MatchKind::LeftmostFirst)
+                .build()
+                .err()
+        );
+        assert_eq!(Ok(MatchKind::LeftmostFirst), "leftmost-first".parse());
+    }
+
+    #[test]
+    fn test_regex_set() {
+        let set = RegexSet::new(&["aa", "a+b", r"\bb"]).unwrap();
+        assert_eq!(3, set.len());
+        assert_eq!("a+b", set.pattern(1));
+
+        let found: Vec<(usize, usize, usize)> = set
+            .find_overlapping("aaab b")
+            .map(|m| (m.pattern, m.start, m.end))
+            .collect();
+        assert_eq!(
+            vec![
+                (0, 0, 2),
+                (0, 1, 3),
+                (1, 0, 4),
+                (1, 1, 4),
+                (1, 2, 4),
+                (2, 5, 6)
+            ],
+            found
+        );
+        assert_eq!(
+            vec![(2, vec![0]), (3, vec![0]), (4, vec![1]), (6, vec![2])],
+            set.ends("aaab b")
+        );
+
+        for (pattern, feature) in [
+            (r"(a)\1", "backreferences"),
+            ("a(?=b)", "lookaround"),
+            ("a&b", "set operators"),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Some(Error::Unsupported {
+                    engine: Engine::Dfa,
+                    feature,
+                }),
+                RegexSet::new(&["a", pattern]).err()
+            );
+        }
+
+        // 状態数の上限を超える場合は構築しない
+        assert_eq!(
+            Some(Error::TooManyDfaStates { limit: 4 }),
+            RegexSetBuilder::new(&["(a|b)*a(a|b)(a|b)(a|b)", "b"])
+                .max_dfa_states(4)
+                .build()
+                .err()
+        );
+        assert!(RegexSetBuilder::new(&["(a|b)*a(a|b)(a|b)(a|b)", "b"])
+            .max_dfa_states(64)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_builder_construction() {
+        for engine in [Engine::Nfa, Engine::Dfa].iter() {
+            let re = RegexBuilder::new("a(b|c)*d+")
+                .engine(*engine)
+                .construction(Construction::Glushkov)
+                .build()
+                .unwrap();
+
+            assert_eq!(Ok(true), re.is_match("xabcdd"));
+            assert_eq!(Ok(false), re.is_match("abc"));
+        }
+
+        let re = RegexBuilder::new("ab+")
+            .engine(Engine::Dfa)
+            .construction(Construction::Glushkov)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(Some(Match { start: 1, end: 4 })), re.find("aabbc"));
+        assert_eq!(Ok(Construction::Glushkov), "glushkov".parse());
+    }
+
+    #[test]
+    fn test_builder_set_operator() {
+        for engine in [Engine::Dfa, Engine::Derivative, Engine::Auto].iter() {
+            let re = RegexBuilder::new("(a|b|f|i|o|r)+&~(if|for)")
+                .engine(*engine)
+                .anchored(true)
+                .build()
+                .unwrap();
+            assert_eq!(Ok(true), re.is_match("fi"));
+            assert_eq!(Ok(false), re.is_match("for"));
+
+            let re = RegexBuilder::new("(.*ab.*)&(.*ba.*)")
+                .engine(*engine)
+                .build()
+                .unwrap();
+            assert_eq!(Ok(true), re.is_match("xabax"));
+            assert_eq!(Ok(false), re.is_match("xabx"));
+        }
+
+        assert_eq!(
+            Some(Error::Unsupported {
+                engine: Engine::PikeVm,
+                feature: "set operators",
+            }),
+            RegexBuilder::new("a&b")
+                .engine(Engine::PikeVm)
+                .build()
+                .err()
+        );
+    }
+
+    #[test]
+    fn test_engine_from_str() {
+        assert_eq!(Ok(Engine::PikeVm), "pike".parse());
+        assert_eq!(Ok(Engine::Auto), "auto".parse());
+        assert_eq!(Ok(Engine::Derivative), "derivative".parse());
+        assert!("foo".parse::<Engine>().is_err());
+    }
+
+    #[test]
+    fn test_builder_prefilter() {
+        for engine in ENGINES.iter() {
+            for pattern in ["ab+c", "a*bc(d|e)", "(ab|ac)d+", "aa"].iter() {
+                let with = RegexBuilder::new(pattern).engine(*engine).build().unwrap();
+                let without = RegexBuilder::new(pattern)
+                    .engine(*engine)
+                    .prefilter(false)
+                    .build()
+                    .unwrap();
+
+                for input in ["abbc", "xxabcx", "zbcd", "abbbd", "xacdd", "aaa", "ab", ""].iter() {
+                    assert_eq!(without.is_match(input), with.is_match(input));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_builder_prefilter_many_candidates() {
+        // 先頭リテラルの出現位置が多くても、照合は最初の出現位置からの一度のみ
+        let input = format!("c{}", "ab".repeat(2000));
+        for engine in ENGINES.iter() {
+            let re = RegexBuilder::new("ab.*c").engine(*engine).build().unwrap();
+            assert_eq!(Ok(false), re.is_match(&input));
+            assert_eq!(Ok(true), re.is_match(&format!("{}c", input)));
+        }
+
+        // 実行ステップ数は入力長に比例する
+        let re = RegexBuilder::new("ab.*c")
+            .engine(Engine::Backtrack)
+            .max_backtrack_steps(10 * input.len())
+            .build()
+            .unwrap();
+        assert_eq!(Ok(false), re.is_match(&input));
+    }
+
+    #[test]
+    fn test_builder_limits() {
+        assert_eq!(
+            Some(Error::ProgramTooLarge { size: 4, limit: 3 }),
+            RegexBuilder::new("abc")
+                .engine(Engine::Backtrack)
+                .anchored(true)
+                .optimize(false)
+                .max_program_size(3)
+                .build()
+                .err()
+        );
+        // 最適化後の命令数（String, Match）で判定する
+        assert!(RegexBuilder::new("abc")
+            .engine(Engine::Backtrack)
+            .anchored(true)
+            .max_program_size(3)
+            .build()
+            .is_ok());
+        assert_eq!(
+            Some(Error::TooManyDfaStates { limit: 2 }),
+            RegexBuilder::new("abc")
+                .engine(Engine::Dfa)
+                .max_dfa_states(2)
+                .build()
+                .err()
+        );
+
+        let re = RegexBuilder::new("(a*)*b")
+            .engine(Engine::Backtrack)
+            .prefilter(false)
+            .max_backtrack_steps(100)
+            .build()
+            .unwrap();
+        assert_eq!(
+            Err(Error::BacktrackLimitExceeded { limit: 100 }),
+            re.is_match("aaaaaaaaaaaaaaaaaaaaaaaa")
+        );
+        assert_eq!(Ok(true), re.is_match("ab"));
+
+        // 上限は先頭リテラルの出現位置からの照合全体に適用する
+        let re = RegexBuilder::new("ab(?=b*c)")
+            .engine(Engine::Backtrack)
+            .max_backtrack_steps(200)
+            .build()
+            .unwrap();
+        let input = "ab".repeat(50);
+        assert_eq!(Ok(false), re.is_match(&input[96..]));
+        assert_eq!(
+            Err(Error::BacktrackLimitExceeded { limit: 200 }),
+            re.is_match(&input)
+        );
+    }
+
     #[test]
     fn test_exec_at_dfa() {
         let re = Regex::new("a?bc");
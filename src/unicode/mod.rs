@@ -0,0 +1,181 @@
+#![allow(dead_code)]
+
+#[rustfmt::skip]
+mod tables;
+
+use std::cmp::Ordering;
+use std::fmt;
+
+// 一般カテゴリの別名
+const ALIASES: [(&str, &str); 10] = [
+    ("Letter", "L"),
+    ("Uppercase_Letter", "Lu"),
+    ("Lowercase_Letter", "Ll"),
+    ("Mark", "M"),
+    ("Number", "N"),
+    ("Decimal_Number", "Nd"),
+    ("Punctuation", "P"),
+    ("Symbol", "S"),
+    ("Separator", "Z"),
+    ("Other", "C"),
+];
+
+// Unicodeプロパティによる文字集合（\p{..}、否定は\P{..}）
+// 範囲表は静的に持つため、コピーして各エンジンの命令・遷移に埋め込める
+#[derive(Clone, Copy)]
+pub struct Property {
+    name: &'static str,
+    ranges: &'static [(char, char)],
+    negated: bool,
+}
+
+impl Property {
+    // 一般カテゴリ（L、Luなど）・用字（Greekなど）・二値プロパティ（White_Spaceなど）の名前から作成
+    pub fn new(name: &str, negated: bool) -> Option<Self> {
+        let name = ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map_or(name, |(_, n)| n);
+        tables::GENERAL_CATEGORY
+            .iter()
+            .chain(tables::SCRIPT.iter())
+            .chain(tables::BINARY.iter())
+            .find(|(n, _)| *n == name)
+            .map(|(name, ranges)| Property {
+                name,
+                ranges,
+                negated,
+            })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    // 否定を考慮しない範囲の列（整列済みで重ならない）
+    pub fn ranges(&self) -> &'static [(char, char)] {
+        self.ranges
+    }
+
+    // 否定を考慮しない、範囲表に文字を含むか
+    pub fn in_table(&self, c: char) -> bool {
+        self.ranges
+            .binary_search_by(|(start, end)| {
+                if *end < c {
+                    Ordering::Less
+                } else if *start > c {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    // 集合に文字を含むか
+    pub fn contains(&self, c: char) -> bool {
+        self.in_table(c) != self.negated
+    }
+
+    // 集合に含まれる文字の例（空集合の場合はNone）
+    pub fn sample(&self) -> Option<char> {
+        match self.ranges.first() {
+            Some((start, _)) if !self.negated => Some(*start),
+            _ => ['a', ' ', '0', '\0']
+                .iter()
+                .copied()
+                .chain(
+                    self.ranges
+                        .iter()
+                        .filter_map(|(_, end)| std::char::from_u32(*end as u32 + 1)),
+                )
+                .find(|c| self.contains(*c)),
+        }
+    }
+}
+
+// 範囲表は名前から決まるため、名前と否定のみ比較する
+impl PartialEq for Property {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.negated == other.negated
+    }
+}
+
+impl fmt::Debug for Property {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let p = if self.negated { 'P' } else { 'p' };
+        write!(f, "\\{}{{{}}}", p, self.name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_property_contains() {
+        let letter = Property::new("L", false).unwrap();
+        assert!(letter.contains('a'));
+        assert!(letter.contains('あ'));
+        assert!(!letter.contains('1'));
+        assert!(!letter.contains(' '));
+
+        let greek = Property::new("Greek", false).unwrap();
+        assert!(greek.contains('α'));
+        assert!(greek.contains('Ω'));
+        assert!(!greek.contains('a'));
+
+        let not_space = Property::new("White_Space", true).unwrap();
+        assert!(not_space.contains('a'));
+        assert!(!not_space.contains(' '));
+        assert!(!not_space.contains('\u{3000}'));
+
+        assert!(Property::new("Nd", false).unwrap().contains('٣'));
+        assert!(Property::new("Han", false).unwrap().contains('漢'));
+    }
+
+    #[test]
+    fn test_property_name() {
+        assert_eq!(Property::new("L", false), Property::new("Letter", false));
+        assert_eq!(
+            "Lu",
+            Property::new("Uppercase_Letter", true).unwrap().name()
+        );
+        assert_ne!(Property::new("L", false), Property::new("L", true));
+        assert_eq!(None, Property::new("Klingon", false));
+        assert_eq!(None, Property::new("l", false));
+        assert_eq!(
+            r"\P{Greek}",
+            format!("{:?}", Property::new("Greek", true).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_property_sample() {
+        for (name, negated) in [("Lu", false), ("L", true), ("Cc", true), ("Zs", false)].iter() {
+            let p = Property::new(name, *negated).unwrap();
+            assert!(p.contains(p.sample().unwrap()), "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn test_tables_sorted() {
+        for (name, ranges) in tables::GENERAL_CATEGORY
+            .iter()
+            .chain(tables::SCRIPT.iter())
+            .chain(tables::BINARY.iter())
+        {
+            assert!(!ranges.is_empty(), "{}", name);
+            for (start, end) in ranges.iter() {
+                assert!(start <= end, "{}", name);
+            }
+            for pair in ranges.windows(2) {
+                assert!(pair[0].1 < pair[1].0, "{}", name);
+            }
+        }
+    }
+}
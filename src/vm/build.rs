@@ -1,36 +1,55 @@
 #![allow(dead_code)]
 
-use crate::parse::lexer::{Lexer, Token};
-use crate::parse::parser::{Ast, AstTree};
+use crate::parse::lexer::Lexer;
+use crate::parse::parser::{Assertion, Ast, AstTree, Look};
+use crate::unicode::Property;
+use std::rc::Rc;
 
 // 中間言語
 #[derive(Debug, PartialEq, Clone)]
 pub enum RegexIR {
     Char(char),
+    String(Vec<char>), // 連続する文字（最適化で作成）
     AllChar,
+    Property(Property), // Unicodeプロパティの集合に含まれる文字
     Split(usize, usize),
     Jmp(usize),
+    Save(usize),       // 現在位置をスロットへ記録（グループnの開始は2n、終了は2n+1）
+    Backref(usize),    // グループnにマッチした文字列と比較
+    Assert(Assertion), // 直前と現在位置の文字が条件を満たすか
+    // 現在位置から部分プログラムにマッチするか（否定の場合はマッチしないか）
+    LookAhead {
+        negative: bool,
+        program: Rc<Vec<RegexIR>>,
+    },
+    // 現在位置で終わる長さmin〜maxの文字列が部分プログラムにマッチするか
+    LookBehind {
+        negative: bool,
+        program: Rc<Vec<RegexIR>>,
+        min: usize,
+        max: usize,
+    },
     Match,
 }
 
 pub struct Builder {
-    pattern: String,
-    tokens: Vec<Token>,
+    ast: AstTree,
     pc: usize,
 }
 
 impl Builder {
     pub fn new(pattern: &str) -> Self {
-        Builder {
-            tokens: Lexer::new(pattern).scan(),
-            pattern: pattern.to_string(),
-            pc: 0,
-        }
+        Self::from_ast(Ast::new(&Lexer::new(pattern).scan()).parse())
+    }
+
+    // パース済みのASTから作成
+    pub fn from_ast(ast: AstTree) -> Self {
+        Builder { ast, pc: 0 }
     }
 
     // 中間言語へコンパイル
     pub fn compile(&mut self) -> Vec<RegexIR> {
-        let ast = Ast::new(&self.tokens).parse();
+        let ast = self.ast.clone();
         let mut inst = self.ast_to_inst(&ast);
         inst.push(RegexIR::Match);
         inst
@@ -39,6 +58,13 @@ impl Builder {
     // ASTからVMコードへコンパイル
     fn ast_to_inst(&mut self, ast: &AstTree) -> Vec<RegexIR> {
         match ast {
+            AstTree::And(_, _) | AstTree::Not(_) => {
+                panic!(
+                    "[Builder::ast_to_inst] set operators are not supported ({:?})",
+                    ast
+                )
+            }
+            AstTree::String(_) | AstTree::Class(_) => self.ast_to_inst(&ast.expand()),
             AstTree::Concat(a, b) => {
                 let mut l_inst = self.ast_to_inst(a);
                 let r_inst = self.ast_to_inst(b);
@@ -53,6 +79,46 @@ impl Builder {
                 self.pc += 1;
                 vec![RegexIR::AllChar]
             }
+            AstTree::Capture(n, _, ast) => {
+                self.pc += 1; // Save命令分
+                let mut inst = vec![RegexIR::Save(2 * n)];
+                inst.extend(self.ast_to_inst(ast));
+                self.pc += 1;
+                inst.push(RegexIR::Save(2 * n + 1));
+                inst
+            }
+            AstTree::Backref(n) => {
+                self.pc += 1;
+                vec![RegexIR::Backref(*n)]
+            }
+            AstTree::Assert(a) => {
+                self.pc += 1;
+                vec![RegexIR::Assert(*a)]
+            }
+            AstTree::Property(p) => {
+                self.pc += 1;
+                vec![RegexIR::Property(*p)]
+            }
+            AstTree::Look(look, ast) => {
+                self.pc += 1;
+                let negative = look.is_negative();
+                let program = Rc::new(Builder::from_ast((**ast).clone()).compile());
+                match (look, ast.width()) {
+                    (Look::Ahead, _) | (Look::NegativeAhead, _) => {
+                        vec![RegexIR::LookAhead { negative, program }]
+                    }
+                    (_, (min, Some(max))) => vec![RegexIR::LookBehind {
+                        negative,
+                        program,
+                        min,
+                        max,
+                    }],
+                    (_, (_, None)) => panic!(
+                        "[Builder::ast_to_inst] lookbehind must have bounded length ({:?})",
+                        ast
+                    ),
+                }
+            }
             AstTree::Plus(ast) => {
                 // xは今の命令を設定
                 let x = self.pc;
@@ -68,32 +134,33 @@ impl Builder {
             }
             AstTree::Question(ast) => {
                 // xはast命令に設定
-                let x = self.pc + 1;
+                self.pc += 1; // split命令分
+                let x = self.pc;
                 let ast_inst = self.ast_to_inst(ast);
 
                 // yはast命令の後に設定
-                let y = self.pc + 1;
+                let y = self.pc;
 
                 // 各命令をマージ
                 let mut inst = vec![RegexIR::Split(x, y)];
                 inst.extend(ast_inst);
-                self.pc += 1;
                 inst
             }
             AstTree::Repeat(ast) => {
                 // xはast命令に設定
                 let cur = self.pc;
-                let x = self.pc + 1;
+                self.pc += 1; // split命令分
+                let x = self.pc;
                 let ast_inst = self.ast_to_inst(ast);
 
                 // yはJmp命令の後に設定
-                let y = self.pc + 2;
+                self.pc += 1; // Jmp命令分
+                let y = self.pc;
 
                 // 各命令をマージ
                 let mut inst = vec![RegexIR::Split(x, y)];
                 inst.extend(ast_inst);
                 inst.push(RegexIR::Jmp(cur));
-                self.pc += 2;
                 inst
             }
             AstTree::Or(left, right) => {
@@ -253,6 +320,49 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_builder_compile_nested() {
+        {
+            let ir = Builder::new("(?:a*)*b").compile();
+
+            assert_eq!(7, ir.len());
+            assert_eq!(RegexIR::Split(1, 5), ir[0]);
+            assert_eq!(RegexIR::Split(2, 4), ir[1]);
+            assert_eq!(RegexIR::Char('a'), ir[2]);
+            assert_eq!(RegexIR::Jmp(1), ir[3]);
+            assert_eq!(RegexIR::Jmp(0), ir[4]);
+            assert_eq!(RegexIR::Char('b'), ir[5]);
+            assert_eq!(RegexIR::Match, ir[6]);
+        }
+        {
+            let ir = Builder::new("(?:ab?)?c").compile();
+
+            assert_eq!(6, ir.len());
+            assert_eq!(RegexIR::Split(1, 4), ir[0]);
+            assert_eq!(RegexIR::Char('a'), ir[1]);
+            assert_eq!(RegexIR::Split(3, 4), ir[2]);
+            assert_eq!(RegexIR::Char('b'), ir[3]);
+            assert_eq!(RegexIR::Char('c'), ir[4]);
+            assert_eq!(RegexIR::Match, ir[5]);
+        }
+    }
+
+    #[test]
+    fn test_builder_compile_capture() {
+        let ir = Builder::new("(a)b").compile();
+
+        assert_eq!(
+            vec![
+                RegexIR::Save(2),
+                RegexIR::Char('a'),
+                RegexIR::Save(3),
+                RegexIR::Char('b'),
+                RegexIR::Match,
+            ],
+            ir
+        );
+    }
+
     #[test]
     fn test_builder_compile_all_char() {
         {
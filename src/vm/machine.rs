@@ -1,7 +1,24 @@
 #![allow(dead_code)]
 
+use crate::error::Error;
+use crate::parse::parser::CharKind;
 use crate::vm::build::{Builder, RegexIR};
+use std::cell::Cell;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// 期限を確認するステップ間隔
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
+// Save命令で記録した位置（グループnの開始は2n、終了は2n+1番目）
+pub type Slots = Vec<Option<usize>>;
+
+// 一回の検索全体（各開始位置・先読み・後読みの部分実行を含む）で共有する実行ステップ数と期限
+#[derive(Debug)]
+pub struct Budget {
+    steps: Cell<usize>,
+    deadline: Option<Instant>,
+}
 
 // コンテキスト
 #[derive(Debug, Clone)]
@@ -10,52 +27,311 @@ struct Context {
     pc: usize,              // program counter
     inst: Rc<Vec<RegexIR>>, // instructions
     target: Rc<Vec<char>>,  // target string
+    slots: Slots,           // Save命令で記録した位置
+    budget: Rc<Budget>,     // 実行ステップ数と期限
 }
 
 impl Context {
-    pub fn new(inst: Vec<RegexIR>, target: Vec<char>) -> Self {
+    pub fn new(inst: Vec<RegexIR>, target: Vec<char>, budget: Rc<Budget>) -> Self {
         Context {
             pc: 0,
             sp: 0,
             inst: Rc::new(inst),
             target: Rc::new(target),
+            slots: vec![],
+            budget,
         }
     }
 
     pub fn clear(&mut self) {
         self.sp = 0;
         self.pc = 0;
+        self.slots.clear();
+    }
+
+    // 部分プログラムを位置spから実行するコンテキスト（記録した位置は引き継ぐ）
+    fn sub(&self, program: &Rc<Vec<RegexIR>>, sp: usize) -> Context {
+        Context {
+            pc: 0,
+            sp,
+            inst: Rc::clone(program),
+            target: Rc::clone(&self.target),
+            slots: self.slots.clone(),
+            budget: Rc::clone(&self.budget),
+        }
+    }
+
+    // 直前と現在位置の文字の種類
+    fn between(&self) -> (CharKind, CharKind) {
+        let prev = self.sp.checked_sub(1).map(|i| self.target[i]);
+        (
+            CharKind::of(prev),
+            CharKind::of(self.target.get(self.sp).copied()),
+        )
+    }
+
+    // グループnにマッチした文字列と、現在位置からの文字列が一致する場合はその長さ
+    // （グループがまだマッチしていない場合は失敗）
+    fn backref(&self, n: usize) -> Option<usize> {
+        let start = (*self.slots.get(2 * n)?)?;
+        let end = (*self.slots.get(2 * n + 1)?)?;
+        let group = &self.target[start..end];
+        if self.target[self.sp..].starts_with(group) {
+            Some(group.len())
+        } else {
+            None
+        }
+    }
+}
+
+// 実行済みの(pc, sp)の組を記録するビット集合
+// 同じ組からの再実行は必ず失敗するため、枝刈りすることで実行時間を命令数×入力長に抑える
+struct Visited {
+    bits: Vec<u64>,
+    width: usize,
+}
+
+impl Visited {
+    pub fn new(inst_len: usize, target_len: usize) -> Self {
+        let width = target_len + 1;
+        Visited {
+            bits: vec![0; (inst_len * width).div_ceil(64)],
+            width,
+        }
+    }
+
+    // 未実行であれば記録してtrueを返す
+    pub fn insert(&mut self, pc: usize, sp: usize) -> bool {
+        let index = pc * self.width + sp;
+        let mask = 1 << (index % 64);
+        let inserted = self.bits[index / 64] & mask == 0;
+        self.bits[index / 64] |= mask;
+        inserted
     }
 }
 
+#[derive(Debug)]
 pub struct Machine {
     inst: Vec<RegexIR>,
+    max_steps: Option<usize>,  // 実行ステップ数の上限
+    timeout: Option<Duration>, // 実行時間の上限
+    memoize: bool,             // (pc, sp)の組による枝刈りを行うか
+    full_match: bool,          // 入力全体へのマッチを要求するか
 }
 
 impl Machine {
     pub fn new(pattern: &str) -> Self {
+        Self::from_inst(Builder::new(pattern).compile())
+    }
+
+    // コンパイル済みの命令列から作成
+    pub fn from_inst(inst: Vec<RegexIR>) -> Self {
         Machine {
-            inst: Builder::new(pattern).compile(),
+            inst,
+            max_steps: None,
+            timeout: None,
+            memoize: true,
+            full_match: false,
         }
     }
 
+    // 実行ステップ数の上限を設定
+    pub fn max_steps(mut self, limit: usize) -> Self {
+        self.max_steps = Some(limit);
+        self
+    }
+
+    // 実行時間の上限を設定
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    // (pc, sp)の組による枝刈りの有無を設定
+    pub fn memoize(mut self, yes: bool) -> Self {
+        self.memoize = yes;
+        self
+    }
+
+    // 入力全体へのマッチを要求
+    pub fn full_match(mut self, yes: bool) -> Self {
+        self.full_match = yes;
+        self
+    }
+
     // 仮想マシン実行
-    pub fn is_match(&mut self, str: &str) -> bool {
+    // 上限を超えた場合はpanicするため、上限を設定した場合はtry_matchを使用
+    pub fn is_match(&self, str: &str) -> bool {
+        self.try_match(str)
+            .unwrap_or_else(|e| panic!("[Machine::is_match] {:?} (use try_match)", e))
+    }
+
+    // 仮想マシン実行（上限超過時はエラー）
+    pub fn try_match(&self, str: &str) -> Result<bool, Error> {
+        self.try_match_with(str, &self.budget())
+    }
+
+    // 検索一回分の実行ステップ数と期限
+    pub fn budget(&self) -> Rc<Budget> {
+        Rc::new(Budget {
+            steps: Cell::new(0),
+            deadline: self.timeout.map(|t| Instant::now() + t),
+        })
+    }
+
+    // 実行ステップ数と期限を共有して実行（入力の複数の部分への照合を一つの検索として数える）
+    pub fn try_match_with(&self, str: &str, budget: &Rc<Budget>) -> Result<bool, Error> {
         // 各命令を実行
-        let ctx = Context::new(self.inst.clone(), str.chars().collect());
-        Self::exec(ctx)
+        let ctx = Context::new(self.inst.clone(), str.chars().collect(), Rc::clone(budget));
+        let end = if self.full_match {
+            Some(ctx.target.len())
+        } else {
+            None
+        };
+        Ok(self.exec(ctx, end)?.is_some())
     }
 
-    // 正規表現VM実行
-    fn exec(mut ctx: Context) -> bool {
+    // 最も左の位置から始まるマッチで、各スロットに記録した位置（文字単位）
+    // 開始位置を先頭から順に試すため、最初に見つかったマッチが最も左で、選択肢の優先順位が最も高い
+    // （入力全体へのマッチを要求する場合は先頭のみ）
+    pub fn captures(&self, str: &str) -> Result<Option<Slots>, Error> {
+        let inst = Rc::new(self.inst.clone());
+        let target: Rc<Vec<char>> = Rc::new(str.chars().collect());
+        let budget = self.budget();
+        let (starts, end) = if self.full_match {
+            (0, Some(target.len()))
+        } else {
+            (target.len(), None)
+        };
+
+        // ある開始位置で失敗した(pc, sp)の組は、他の開始位置からでも失敗するため共有する
+        let mut visited = self.visited(&inst, target.len());
+        for start in 0..=starts {
+            let ctx = Context {
+                pc: 0,
+                sp: start,
+                inst: Rc::clone(&inst),
+                target: Rc::clone(&target),
+                slots: vec![],
+                budget: Rc::clone(&budget),
+            };
+            if let Some((_, slots)) = self.run(ctx, end, false, &mut visited)? {
+                return Ok(Some(slots));
+            }
+        }
+        Ok(None)
+    }
+
+    // startから始まりendで終わるマッチで、各スロットに記録した位置（文字単位）
+    // 一致範囲を別に求めた場合（最長一致）に、その範囲内のグループを求めるために使用
+    pub fn captures_at(&self, str: &str, start: usize, end: usize) -> Result<Option<Slots>, Error> {
+        let ctx = Context {
+            pc: 0,
+            sp: start,
+            inst: Rc::new(self.inst.clone()),
+            target: Rc::new(str.chars().collect()),
+            slots: vec![],
+            budget: self.budget(),
+        };
+        self.exec(ctx, Some(end))
+    }
+
+    // 最も左の位置から始まる最長のマッチの範囲（文字単位）
+    // 各開始位置で全ての分岐を試し、最も右で終わるマッチを選ぶ（選択肢の優先順位によらない）
+    pub fn longest(&self, str: &str) -> Result<Option<(usize, usize)>, Error> {
+        let inst = Rc::new(self.inst.clone());
+        let target: Rc<Vec<char>> = Rc::new(str.chars().collect());
+        let budget = self.budget();
+        let (starts, end) = if self.full_match {
+            (0, Some(target.len()))
+        } else {
+            (target.len(), None)
+        };
+
+        // 開始位置をまたいで実行済みの組を共有（マッチがなかった開始位置の組は全て失敗する）
+        let mut visited = self.visited(&inst, target.len());
+        for start in 0..=starts {
+            let ctx = Context {
+                pc: 0,
+                sp: start,
+                inst: Rc::clone(&inst),
+                target: Rc::clone(&target),
+                slots: vec![],
+                budget: Rc::clone(&budget),
+            };
+            if let Some((sp, _)) = self.run(ctx, end, true, &mut visited)? {
+                return Ok(Some((start, sp)));
+            }
+        }
+        Ok(None)
+    }
+
+    // 正規表現VM実行（endを指定した場合は、その位置で終わるマッチのみ）
+    fn exec(&self, ctx: Context, end: Option<usize>) -> Result<Option<Slots>, Error> {
+        let mut visited = self.visited(&ctx.inst, ctx.target.len());
+        Ok(self
+            .run(ctx, end, false, &mut visited)?
+            .map(|(_, slots)| slots))
+    }
+
+    // 実行済みの(pc, sp)の組の記録（枝刈りしない場合はNone）
+    // 後方参照がある場合は記録した位置によって結果が変わるため、枝刈りできない
+    fn visited(&self, inst: &[RegexIR], target_len: usize) -> Option<Visited> {
+        if self.memoize && !has_backref(inst) {
+            Some(Visited::new(inst.len(), target_len))
+        } else {
+            None
+        }
+    }
+
+    // マッチした位置と記録した位置を返す
+    // longestの場合は最初のマッチで終了せず、最も右の位置で終わるマッチを返す
+    // visitedは同じ命令列・入力への複数回の実行で共有できる
+    fn run(
+        &self,
+        mut ctx: Context,
+        end: Option<usize>,
+        longest: bool,
+        visited: &mut Option<Visited>,
+    ) -> Result<Option<(usize, Slots)>, Error> {
+        let mut best: Option<(usize, Slots)> = None;
         let mut threads: Vec<Context> = vec![];
+        let budget = Rc::clone(&ctx.budget);
 
         loop {
             if ctx.pc >= ctx.inst.len() {
-                return false;
+                return Ok(best);
+            }
+
+            // ステップ数・実行時間の上限を確認
+            let steps = budget.steps.get() + 1;
+            budget.steps.set(steps);
+            if let Some(limit) = self.max_steps {
+                if steps > limit {
+                    return Err(Error::BacktrackLimitExceeded { limit });
+                }
+            }
+            if let (Some(deadline), Some(timeout)) = (budget.deadline, self.timeout) {
+                if steps.is_multiple_of(DEADLINE_CHECK_INTERVAL) && Instant::now() > deadline {
+                    return Err(Error::Timeout { timeout });
+                }
             }
 
-            match ctx.inst[ctx.pc] {
+            // 実行済みの組であれば、別スレッドへ切り替え
+            if let Some(visited) = visited.as_mut() {
+                if !visited.insert(ctx.pc, ctx.sp) {
+                    match threads.pop() {
+                        Some(next) => {
+                            ctx = next;
+                            continue;
+                        }
+                        None => return Ok(best),
+                    }
+                }
+            }
+
+            let inst = Rc::clone(&ctx.inst);
+            match inst[ctx.pc] {
                 RegexIR::AllChar if ctx.sp < ctx.target.len() => {
                     ctx.pc += 1;
                     ctx.sp += 1;
@@ -64,8 +340,47 @@ impl Machine {
                     ctx.pc += 1;
                     ctx.sp += 1;
                 }
+                RegexIR::Property(p)
+                    if ctx.sp < ctx.target.len() && p.contains(ctx.target[ctx.sp]) =>
+                {
+                    ctx.pc += 1;
+                    ctx.sp += 1;
+                }
+                RegexIR::String(ref s) if ctx.target[ctx.sp..].starts_with(s) => {
+                    ctx.pc += 1;
+                    ctx.sp += s.len();
+                }
                 RegexIR::Jmp(x) => ctx.pc = x,
-                RegexIR::Match => return true,
+                RegexIR::Save(slot) => {
+                    if ctx.slots.len() <= slot {
+                        ctx.slots.resize(slot + 1, None);
+                    }
+                    ctx.slots[slot] = Some(ctx.sp);
+                    ctx.pc += 1;
+                }
+                RegexIR::Backref(n) if ctx.backref(n).is_some() => {
+                    ctx.sp += ctx.backref(n).unwrap_or(0);
+                    ctx.pc += 1;
+                }
+                RegexIR::Assert(a) if a.is_match(ctx.between().0, ctx.between().1) => {
+                    ctx.pc += 1;
+                }
+                RegexIR::LookAhead { .. } | RegexIR::LookBehind { .. } if self.look(&ctx)? => {
+                    ctx.pc += 1;
+                }
+                RegexIR::Match if end.is_none_or(|end| ctx.sp == end) && !longest => {
+                    return Ok(Some((ctx.sp, ctx.slots)))
+                }
+                // 最長一致では位置を記録し、残りのスレッドを続ける
+                RegexIR::Match if end.is_none_or(|end| ctx.sp == end) => {
+                    if best.as_ref().is_none_or(|(sp, _)| ctx.sp > *sp) {
+                        best = Some((ctx.sp, ctx.slots.clone()));
+                    }
+                    match threads.pop() {
+                        Some(next) => ctx = next,
+                        None => return Ok(best),
+                    }
+                }
                 // PC位置を変更し、スレッド起動
                 RegexIR::Split(x, y) => {
                     // 切り替え先を登録し、切り替え
@@ -75,7 +390,7 @@ impl Machine {
                 _ => {
                     // 切り替え先がある場合は切り替え
                     if threads.is_empty() {
-                        return false;
+                        return Ok(best);
                     } else {
                         ctx = threads.pop().unwrap();
                     }
@@ -84,17 +399,55 @@ impl Machine {
         }
     }
 
+    // 先読み・後読みの部分プログラムを実行し、条件を満たすか
+    // 後読みは、現在位置で終わる各長さの文字列について部分プログラムを試す
+    fn look(&self, ctx: &Context) -> Result<bool, Error> {
+        match &ctx.inst[ctx.pc] {
+            RegexIR::LookAhead { negative, program } => {
+                let matched = self.exec(ctx.sub(program, ctx.sp), None)?.is_some();
+                Ok(matched != *negative)
+            }
+            RegexIR::LookBehind {
+                negative,
+                program,
+                min,
+                max,
+            } => {
+                for len in *min..=(*max).min(ctx.sp) {
+                    let sub = ctx.sub(program, ctx.sp - len);
+                    if self.exec(sub, Some(ctx.sp))?.is_some() {
+                        return Ok(!*negative);
+                    }
+                }
+                Ok(*negative)
+            }
+            ir => panic!("[Machine::look] not a lookaround instruction: {:?}", ir),
+        }
+    }
+
     // スレッド登録
+    // 優先するxを先に取り出すため、yから積む
     fn add_thread(threads: &mut Vec<Context>, cur_ctx: &Context, x: usize, y: usize) {
-        let mut x_ctx = cur_ctx.clone();
-        x_ctx.pc = x;
-        threads.push(x_ctx);
         let mut y_ctx = cur_ctx.clone();
         y_ctx.pc = y;
         threads.push(y_ctx);
+        let mut x_ctx = cur_ctx.clone();
+        x_ctx.pc = x;
+        threads.push(x_ctx);
     }
 }
 
+// 後方参照を含むか（部分プログラムも含む）
+fn has_backref(inst: &[RegexIR]) -> bool {
+    inst.iter().any(|ir| match ir {
+        RegexIR::Backref(_) => true,
+        RegexIR::LookAhead { program, .. } | RegexIR::LookBehind { program, .. } => {
+            has_backref(program)
+        }
+        _ => false,
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -102,7 +455,7 @@ mod test {
     #[test]
     fn test_machine_only_char() {
         {
-            let mut m = Machine::new("a");
+            let m = Machine::new("a");
 
             assert!(m.is_match("a"));
             assert!(m.is_match("aa"));
@@ -110,7 +463,7 @@ mod test {
             assert!(!m.is_match(""));
         }
         {
-            let mut m = Machine::new("abc");
+            let m = Machine::new("abc");
 
             assert!(m.is_match("abc"));
             assert!(m.is_match("abcd"));
@@ -121,9 +474,10 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_machine_plus() {
         {
-            let mut m = Machine::new("a+");
+            let m = Machine::new("a+");
 
             assert!(m.is_match("a"));
             assert!(m.is_match("aa"));
@@ -131,7 +485,7 @@ mod test {
             assert!(!m.is_match(""));
         }
         {
-            let mut m = Machine::new("a+b+");
+            let m = Machine::new("a+b+");
 
             assert!(m.is_match("ab"));
             assert!(m.is_match("aabb"));
@@ -142,7 +496,7 @@ mod test {
             assert!(!m.is_match(""));
         }
         {
-            let mut m = Machine::new("a+b+c+d+e+");
+            let m = Machine::new("a+b+c+d+e+");
 
             assert!(m.is_match("abcde"));
             assert!(m.is_match("aabbccddee"));
@@ -158,7 +512,7 @@ mod test {
     #[test]
     fn test_machine_repeat() {
         {
-            let mut m = Machine::new("a*");
+            let m = Machine::new("a*");
 
             assert!(m.is_match(""));
             assert!(m.is_match("a"));
@@ -167,7 +521,7 @@ mod test {
             assert!(m.is_match("b"));
         }
         {
-            let mut m = Machine::new("aa*");
+            let m = Machine::new("aa*");
 
             assert!(m.is_match("a"));
             assert!(m.is_match("aa"));
@@ -176,7 +530,7 @@ mod test {
             assert!(!m.is_match(""));
         }
         {
-            let mut m = Machine::new("aa*bb*");
+            let m = Machine::new("aa*bb*");
 
             assert!(m.is_match("ab"));
             assert!(m.is_match("aab"));
@@ -191,7 +545,7 @@ mod test {
 
     #[test]
     fn test_machine_or() {
-        let mut m = Machine::new("a|b");
+        let m = Machine::new("a|b");
 
         assert!(m.is_match("a"));
         assert!(m.is_match("b"));
@@ -204,7 +558,7 @@ mod test {
     #[test]
     fn test_machine_question() {
         {
-            let mut m = Machine::new("a?");
+            let m = Machine::new("a?");
 
             assert!(m.is_match(""));
             assert!(m.is_match("a"));
@@ -213,7 +567,7 @@ mod test {
             assert!(m.is_match("b"));
         }
         {
-            let mut m = Machine::new("aa?");
+            let m = Machine::new("aa?");
 
             assert!(m.is_match("a"));
             assert!(m.is_match("aa"));
@@ -222,7 +576,7 @@ mod test {
             assert!(!m.is_match(""));
         }
         {
-            let mut m = Machine::new("aa?bb?");
+            let m = Machine::new("aa?bb?");
 
             assert!(m.is_match("ab"));
             assert!(m.is_match("aabb"));
@@ -234,14 +588,14 @@ mod test {
     #[test]
     fn test_machine_dot() {
         {
-            let mut m = Machine::new(".");
+            let m = Machine::new(".");
 
             assert!(m.is_match("a"));
             assert!(m.is_match("b"));
             assert!(!m.is_match(""));
         }
         {
-            let mut m = Machine::new("..*");
+            let m = Machine::new("..*");
 
             assert!(m.is_match("a"));
             assert!(m.is_match("b"));
@@ -249,7 +603,7 @@ mod test {
             assert!(!m.is_match(""));
         }
         {
-            let mut m = Machine::new("a.");
+            let m = Machine::new("a.");
 
             assert!(m.is_match("aa"));
             assert!(m.is_match("ab"));
@@ -257,7 +611,7 @@ mod test {
             assert!(!m.is_match(""));
         }
         {
-            let mut m = Machine::new("a.b.");
+            let m = Machine::new("a.b.");
 
             assert!(m.is_match("acbd"));
             assert!(m.is_match("axbz"));
@@ -266,7 +620,7 @@ mod test {
             assert!(!m.is_match("acb"));
         }
         {
-            let mut m = Machine::new("a.b.c?");
+            let m = Machine::new("a.b.c?");
 
             assert!(m.is_match("acbd"));
             assert!(m.is_match("acbdc"));
@@ -274,7 +628,7 @@ mod test {
             assert!(!m.is_match("acb"));
         }
         {
-            let mut m = Machine::new("a.b.*c?");
+            let m = Machine::new("a.b.*c?");
 
             assert!(m.is_match("azbd"));
             assert!(m.is_match("acbdd"));
@@ -284,7 +638,7 @@ mod test {
             assert!(!m.is_match("az"));
         }
         {
-            let mut m = Machine::new(".*a");
+            let m = Machine::new(".*a");
 
             assert!(m.is_match("ba"));
             assert!(m.is_match("a"));
@@ -292,13 +646,215 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_machine_max_steps() {
+        let m = Machine::new("a*a*a*b").max_steps(100).memoize(false);
+
+        assert_eq!(Ok(true), m.try_match("aab"));
+        assert_eq!(
+            Err(Error::BacktrackLimitExceeded { limit: 100 }),
+            m.try_match("aaaaaaaaaaaaaaaaaaaa")
+        );
+
+        // 上限は検索全体（各開始位置の実行の合計）に対して適用
+        let s = "a".repeat(200);
+        assert_eq!(Ok(None), Machine::new("ab").max_steps(1000).captures(&s));
+        assert_eq!(
+            Err(Error::BacktrackLimitExceeded { limit: 300 }),
+            Machine::new("ab").max_steps(300).captures(&s)
+        );
+        assert_eq!(
+            Err(Error::BacktrackLimitExceeded { limit: 300 }),
+            Machine::new("ab").max_steps(300).longest(&s)
+        );
+
+        // 先読みの部分実行も数える（各部分実行・本体の実行はそれぞれ上限未満）
+        let m = Machine::new("((?=a+)a)*").full_match(true);
+        assert_eq!(Ok(true), m.try_match(&s));
+        assert_eq!(
+            Err(Error::BacktrackLimitExceeded { limit: 2000 }),
+            m.max_steps(2000).try_match(&s)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "[Machine::is_match] BacktrackLimitExceeded")]
+    fn test_machine_is_match_limit_exceeded() {
+        let m = Machine::new("a*a*a*b").max_steps(100).memoize(false);
+        m.is_match("aaaaaaaaaaaaaaaaaaaa");
+    }
+
+    #[test]
+    fn test_machine_timeout() {
+        let m = Machine::new("(a*)*(a*)*(a*)*b")
+            .memoize(false)
+            .timeout(Duration::from_millis(10));
+
+        let s = String::from_utf8(vec![b'a'; 40]).unwrap();
+        assert_eq!(
+            Err(Error::Timeout {
+                timeout: Duration::from_millis(10)
+            }),
+            m.try_match(&s)
+        );
+    }
+
+    #[test]
+    fn test_machine_memoize() {
+        // 指数的なバックトラックが発生するパターンでも有限時間で終了する
+        let m = Machine::new("(a*)*(a*)*(a*)*b");
+
+        let s = String::from_utf8(vec![b'a'; 1000]).unwrap();
+        assert_eq!(Ok(false), m.try_match(&s));
+        assert_eq!(Ok(true), m.try_match(&(s + "b")));
+
+        let m = Machine::new("(a|aa)*c");
+        let s = String::from_utf8(vec![b'a'; 1000]).unwrap();
+        assert_eq!(Ok(false), m.try_match(&s));
+    }
+
+    #[test]
+    fn test_machine_memoize_across_starts() {
+        // 開始位置をまたいで実行済みの組を共有するため、実行ステップ数は入力長に比例する
+        let s = "a".repeat(2000);
+        let m = Machine::new("a+c|b").max_steps(10 * s.len());
+        assert_eq!(Ok(None), m.captures(&s));
+        assert_eq!(Ok(None), m.longest(&s));
+        assert_eq!(Ok(Some((0, 2001))), m.longest(&(s + "c")));
+    }
+
+    #[test]
+    fn test_machine_full_match() {
+        let m = Machine::new("a+").full_match(true);
+
+        assert!(m.is_match("aaa"));
+        assert!(!m.is_match("aab"));
+        assert!(!m.is_match(""));
+    }
+
+    #[test]
+    fn test_machine_longest() {
+        // 選択肢の優先順位によらず最長
+        let m = Machine::new("a|ab");
+        assert_eq!(Ok(Some((1, 3))), m.longest("xab"));
+        let slots = Machine::new("(a|ab)").captures("xab");
+        assert_eq!(Ok(Some(vec![None, None, Some(1), Some(2)])), slots);
+
+        let m = Machine::new("(a|ab)(c|bcd)");
+        assert_eq!(Ok(Some((0, 4))), m.longest("abcd"));
+        assert_eq!(Ok(Some((0, 3))), Machine::new("a*b?").longest("aab"));
+        assert_eq!(Ok(Some((0, 0))), Machine::new("b*").longest("ab"));
+        assert_eq!(Ok(None), Machine::new("c").longest("ab"));
+
+        // 後方参照を含む場合
+        assert_eq!(Ok(Some((1, 5))), Machine::new(r"(a|ab)\1").longest("xabab"));
+        assert_eq!(
+            Ok(None),
+            Machine::new("a|ab").full_match(true).longest("abc")
+        );
+    }
+
     #[test]
     fn test_machine_long_str() {
         {
-            let mut m = Machine::new(".*a");
+            let m = Machine::new(".*a");
 
             let s = String::from_utf8(vec![b'a'; 1000000]).unwrap();
             assert!(m.is_match(&s));
         }
     }
+
+    #[test]
+    fn test_machine_backref() {
+        {
+            let m = Machine::new(r"(ab|cd)\1").full_match(true);
+
+            assert!(m.is_match("abab"));
+            assert!(m.is_match("cdcd"));
+            assert!(!m.is_match("abcd"));
+            assert!(!m.is_match("ab"));
+        }
+        {
+            // 繰り返しの中では最後にマッチした文字列を参照する
+            let m = Machine::new(r"(a|b)+\1").full_match(true);
+
+            assert!(m.is_match("abb"));
+            assert!(m.is_match("baa"));
+            assert!(!m.is_match("aba"));
+        }
+        {
+            // 参照先のグループがマッチしていない場合は失敗する
+            let m = Machine::new(r"(a)?b\1").full_match(true);
+
+            assert!(m.is_match("aba"));
+            assert!(!m.is_match("b"));
+        }
+    }
+
+    #[test]
+    fn test_machine_lookaround() {
+        {
+            let m = Machine::new("a(?=bc)").full_match(false);
+
+            assert!(m.is_match("abc"));
+            assert!(!m.is_match("abd"));
+        }
+        {
+            let m = Machine::new("a(?!b).").full_match(true);
+
+            assert!(m.is_match("ac"));
+            assert!(!m.is_match("ab"));
+        }
+        {
+            // 後読みは長さの範囲内で現在位置より前を調べる
+            let m = Machine::new(".*(?<=a|bc)d").full_match(true);
+
+            assert!(m.is_match("xad"));
+            assert!(m.is_match("bcd"));
+            assert!(!m.is_match("cd"));
+            assert!(!m.is_match("d"));
+        }
+        {
+            let m = Machine::new(".*(?<!ab)c").full_match(true);
+
+            assert!(m.is_match("c"));
+            assert!(m.is_match("bc"));
+            assert!(!m.is_match("abc"));
+        }
+    }
+
+    #[test]
+    fn test_machine_assertion() {
+        {
+            let m = Machine::new(r".*\bfoo\b").full_match(false);
+
+            assert!(m.is_match("foo"));
+            assert!(m.is_match("a foo."));
+            assert!(!m.is_match("foobar"));
+            assert!(!m.is_match("_foo"));
+        }
+        {
+            let m = Machine::new(r"a\B.*").full_match(false);
+
+            assert!(m.is_match("ab"));
+            assert!(!m.is_match("a b"));
+            assert!(!m.is_match("a"));
+        }
+        {
+            // 先頭・末尾以外では失敗する
+            let m = Machine::new(r".*\Aa.*|.*b\z").full_match(true);
+
+            assert!(m.is_match("ax"));
+            assert!(m.is_match("xb"));
+            assert!(!m.is_match("xax"));
+            assert!(!m.is_match("bx"));
+        }
+        {
+            // 後読みの中でも前後の文字は入力全体から求める
+            let m = Machine::new(r".*(?<=\ba)b").full_match(true);
+
+            assert!(m.is_match(" ab"));
+            assert!(!m.is_match("aab"));
+        }
+    }
 }
@@ -1,2 +1,4 @@
-mod build;
+pub mod build;
 pub mod machine;
+pub mod optimize;
+pub mod pike;
@@ -0,0 +1,239 @@
+#![allow(dead_code)]
+
+use crate::vm::build::RegexIR;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+// コンパイル後の命令列に対するのぞき穴最適化（マッチする文字列は変えない）
+// - Jmpの連鎖を辿って飛び先を最終的な命令にする（Matchへのジャンプは Match にする）
+// - 到達できない命令と、直後へのJmpを除く
+// - ジャンプ先にならない連続するCharをStringにまとめる
+// - 命令の位置が変わるため、飛び先を付け直す
+// - 先読み・後読みの部分プログラムも同様に最適化する
+pub fn optimize(inst: Vec<RegexIR>) -> Vec<RegexIR> {
+    let inst = thread_jumps(inst);
+    let reachable = reachable(&inst);
+
+    // 他の命令からの飛び先（文字列の途中にはできない）
+    let mut targets: BTreeSet<usize> = BTreeSet::new();
+    targets.insert(0);
+    for (pc, ir) in inst.iter().enumerate().filter(|(pc, _)| reachable[*pc]) {
+        match ir {
+            RegexIR::Jmp(x) if *x != pc + 1 => {
+                targets.insert(*x);
+            }
+            RegexIR::Split(x, y) => {
+                targets.insert(*x);
+                targets.insert(*y);
+            }
+            _ => {}
+        }
+    }
+
+    // map[pc]: 元の命令pcの新しい位置
+    let mut map = vec![usize::MAX; inst.len()];
+    let mut out: Vec<RegexIR> = vec![];
+    let mut prev_char: Option<usize> = None; // 直前に出力したChar/Stringの元の位置
+    for (pc, ir) in inst.into_iter().enumerate() {
+        if !reachable[pc] {
+            continue;
+        }
+        map[pc] = out.len();
+        let fusible = prev_char == Some(pc.wrapping_sub(1)) && !targets.contains(&pc);
+        match ir {
+            // 直後へのJmpは次の命令と同じ位置とする
+            RegexIR::Jmp(x) if x == pc + 1 => {
+                prev_char = None;
+                continue;
+            }
+            RegexIR::Char(c) if fusible => {
+                map[pc] = out.len() - 1;
+                match out.last_mut() {
+                    Some(RegexIR::String(s)) => s.push(c),
+                    Some(last) => *last = RegexIR::String(vec![last.char(), c]),
+                    None => unreachable!(),
+                }
+            }
+            ir => out.push(ir),
+        }
+        prev_char = match out.last() {
+            Some(RegexIR::Char(_)) | Some(RegexIR::String(_)) => Some(pc),
+            _ => None,
+        };
+    }
+
+    for ir in out.iter_mut() {
+        match ir {
+            RegexIR::Jmp(x) => *x = map[*x],
+            RegexIR::Split(x, y) => {
+                *x = map[*x];
+                *y = map[*y];
+            }
+            RegexIR::LookAhead { program, .. } | RegexIR::LookBehind { program, .. } => {
+                *program = Rc::new(optimize((**program).clone()));
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+// Jmpの連鎖を辿った飛び先に置き換える
+fn thread_jumps(inst: Vec<RegexIR>) -> Vec<RegexIR> {
+    let resolve = |mut pc: usize| {
+        // 循環するJmpに備え、命令数までで打ち切る
+        for _ in 0..inst.len() {
+            match inst.get(pc) {
+                Some(RegexIR::Jmp(x)) => pc = *x,
+                _ => break,
+            }
+        }
+        pc
+    };
+
+    inst.iter()
+        .map(|ir| match ir {
+            RegexIR::Jmp(x) => match inst.get(resolve(*x)) {
+                Some(RegexIR::Match) => RegexIR::Match,
+                _ => RegexIR::Jmp(resolve(*x)),
+            },
+            RegexIR::Split(x, y) if resolve(*x) == resolve(*y) => RegexIR::Jmp(resolve(*x)),
+            RegexIR::Split(x, y) => RegexIR::Split(resolve(*x), resolve(*y)),
+            ir => ir.clone(),
+        })
+        .collect()
+}
+
+// 先頭から到達できる命令
+fn reachable(inst: &[RegexIR]) -> Vec<bool> {
+    let mut reachable = vec![false; inst.len()];
+    let mut stack = vec![0];
+    while let Some(pc) = stack.pop() {
+        if pc >= inst.len() || reachable[pc] {
+            continue;
+        }
+        reachable[pc] = true;
+        match inst[pc] {
+            RegexIR::Jmp(x) => stack.push(x),
+            RegexIR::Split(x, y) => {
+                stack.push(x);
+                stack.push(y);
+            }
+            RegexIR::Match => {}
+            _ => stack.push(pc + 1),
+        }
+    }
+    reachable
+}
+
+impl RegexIR {
+    // Char命令の文字
+    fn char(&self) -> char {
+        match self {
+            RegexIR::Char(c) => *c,
+            _ => panic!("[RegexIR::char] not a char instruction: {:?}", self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vm::build::Builder;
+    use crate::vm::machine::Machine;
+    use crate::vm::pike::PikeMachine;
+
+    #[test]
+    fn test_optimize_string() {
+        assert_eq!(
+            vec![RegexIR::String(vec!['a', 'b', 'c']), RegexIR::Match],
+            optimize(Builder::new("abc").compile())
+        );
+        // ジャンプ先の手前で文字列を区切る
+        assert_eq!(
+            vec![
+                RegexIR::String(vec!['a', 'b']),
+                RegexIR::Char('c'),
+                RegexIR::Split(1, 3),
+                RegexIR::Char('d'),
+                RegexIR::Match,
+            ],
+            optimize(Builder::new("abc+d").compile())
+        );
+    }
+
+    #[test]
+    fn test_optimize_jump() {
+        // 選択の後のMatchへのジャンプはMatchにする
+        assert_eq!(
+            vec![
+                RegexIR::Split(1, 3),
+                RegexIR::String(vec!['a', 'b']),
+                RegexIR::Match,
+                RegexIR::Char('c'),
+                RegexIR::Match,
+            ],
+            optimize(Builder::new("ab|c").compile())
+        );
+        // Jmpの連鎖と到達できない命令（先頭の命令は開始位置のため残る）
+        assert_eq!(
+            vec![RegexIR::Jmp(1), RegexIR::Char('a'), RegexIR::Match],
+            optimize(vec![
+                RegexIR::Jmp(3),
+                RegexIR::Char('x'),
+                RegexIR::Match,
+                RegexIR::Jmp(4),
+                RegexIR::Char('a'),
+                RegexIR::Jmp(6),
+                RegexIR::Match,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_optimize_equivalent() {
+        let patterns = [
+            "abc",
+            "a(bc)*d",
+            "(ab|cd)+e",
+            "a?b?c?abc",
+            "(a*)*b",
+            "x(ab|ac|a)y",
+            "((ab)?c)*",
+            ".a.b",
+            "(abc|abd)*(e|f)",
+            "ab(c|d*)ef",
+            "(?=abc)a",
+            "a(?!bc)b",
+            "(?<=ab)c",
+        ];
+        let inputs = [
+            "", "a", "ab", "abc", "abcd", "abcbcd", "ad", "abcde", "cde", "ababe", "abdabce",
+            "aab", "b", "xaby", "xacy", "xay", "xy", "ccabc", "zazb", "abef", "abcef", "abdddef",
+            "abcabdf",
+        ];
+
+        for pattern in patterns.iter() {
+            let inst = Builder::new(pattern).compile();
+            let optimized = optimize(inst.clone());
+            assert!(optimized.len() <= inst.len(), "{}", pattern);
+            let lookaround = inst
+                .iter()
+                .any(|ir| matches!(ir, RegexIR::LookAhead { .. } | RegexIR::LookBehind { .. }));
+
+            for full_match in [false, true].iter() {
+                let plain = Machine::from_inst(inst.clone()).full_match(*full_match);
+                let backtrack = Machine::from_inst(optimized.clone()).full_match(*full_match);
+                let pike = PikeMachine::from_inst(optimized.clone()).full_match(*full_match);
+                for input in inputs.iter() {
+                    let expected = plain.is_match(input);
+                    assert_eq!(expected, backtrack.is_match(input), "{} {}", pattern, input);
+                    // Pike VMは先読み・後読みに未対応
+                    if !lookaround {
+                        assert_eq!(expected, pike.is_match(input), "{} {}", pattern, input);
+                    }
+                }
+            }
+        }
+    }
+}
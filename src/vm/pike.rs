@@ -0,0 +1,430 @@
+#![allow(dead_code)]
+
+use crate::parse::parser::CharKind;
+use crate::vm::build::{Builder, RegexIR};
+use crate::vm::machine::Slots;
+
+// 一致範囲の検索で使用するスレッド
+#[derive(Debug)]
+struct Thread {
+    pc: usize,
+    offset: usize, // String命令で照合済みの文字数
+    slots: Slots,  // Save命令で記録した位置
+}
+
+// Pike VM
+// 全スレッドを入力文字ごとに並行して進めるため、入力長に対して線形時間で動作する
+#[derive(Debug)]
+pub struct PikeMachine {
+    inst: Vec<RegexIR>,
+    full_match: bool, // 入力全体へのマッチを要求するか
+}
+
+impl PikeMachine {
+    pub fn new(pattern: &str) -> Self {
+        Self::from_inst(Builder::new(pattern).compile())
+    }
+
+    // コンパイル済みの命令列から作成
+    pub fn from_inst(inst: Vec<RegexIR>) -> Self {
+        PikeMachine {
+            inst,
+            full_match: false,
+        }
+    }
+
+    // 入力全体へのマッチを要求
+    pub fn full_match(mut self, yes: bool) -> Self {
+        self.full_match = yes;
+        self
+    }
+
+    // 仮想マシン実行
+    pub fn is_match(&self, str: &str) -> bool {
+        let target: Vec<char> = str.chars().collect();
+
+        // pending[sp]: 位置spから再開するスレッド（Stringは複数文字先で再開する）
+        let mut pending: Vec<Vec<usize>> = vec![vec![]; target.len() + 1];
+        pending[0].push(0);
+        let mut last = 0; // 再開するスレッドがある最も先の位置
+
+        for sp in 0..=target.len() {
+            let mut current = vec![];
+            let mut visited = vec![false; self.inst.len()];
+            let between = (
+                CharKind::of(sp.checked_sub(1).map(|i| target[i])),
+                CharKind::of(target.get(sp).copied()),
+            );
+            for pc in std::mem::take(&mut pending[sp]) {
+                self.add_thread(&mut current, &mut visited, pc, between);
+            }
+            if current.is_empty() && last <= sp {
+                return false;
+            }
+
+            for pc in current {
+                let next = match self.inst[pc] {
+                    RegexIR::Match if !self.full_match || sp == target.len() => return true,
+                    RegexIR::AllChar if sp < target.len() => sp + 1,
+                    RegexIR::Char(c) if sp < target.len() && c == target[sp] => sp + 1,
+                    RegexIR::Property(p) if sp < target.len() && p.contains(target[sp]) => sp + 1,
+                    RegexIR::String(ref s) if target[sp..].starts_with(s) => sp + s.len(),
+                    _ => continue,
+                };
+                pending[next].push(pc + 1);
+                last = last.max(next);
+            }
+        }
+
+        false
+    }
+
+    // 最も左の位置から始まるマッチで、各スロットに記録した位置（文字単位）
+    // スレッドを優先順位の順に保ち、マッチしたスレッドより優先順位の低いスレッドを打ち切るため、
+    // バックトラック型VMと同じく選択肢の左・繰り返しの多い方を優先する（最左優先）
+    // マッチ全体を0番のグループとした命令列で使用する
+    pub fn captures(&self, str: &str) -> Option<Slots> {
+        self.search(str, false)
+    }
+
+    // 最も左の位置から始まる最長のマッチの範囲（文字単位）
+    pub fn longest(&self, str: &str) -> Option<(usize, usize)> {
+        let slots = self.search(str, true)?;
+        Some((slots.first().copied()??, slots.get(1).copied()??))
+    }
+
+    // 開始位置ごとのスレッドを優先順位の低い方へ追加しながら、全スレッドを一文字ずつ進める
+    // longestの場合はマッチしても打ち切らず、最も左で始まり最も右で終わるマッチを選ぶ
+    fn search(&self, str: &str, longest: bool) -> Option<Slots> {
+        let target: Vec<char> = str.chars().collect();
+        let between = |sp: usize| {
+            (
+                CharKind::of(sp.checked_sub(1).map(|i| target[i])),
+                CharKind::of(target.get(sp).copied()),
+            )
+        };
+        let span = |slots: &Slots| {
+            (
+                slots.first().copied().flatten(),
+                slots.get(1).copied().flatten(),
+            )
+        };
+
+        let mut current: Vec<Thread> = vec![];
+        let mut visited = vec![false; self.inst.len()];
+        let mut matched: Option<Slots> = None;
+
+        for sp in 0..=target.len() {
+            // マッチが見つかるまでは、この位置から始まるスレッドを最も低い優先順位で追加
+            if matched.is_none() && (sp == 0 || !self.full_match) {
+                let thread = Thread {
+                    pc: 0,
+                    offset: 0,
+                    slots: vec![],
+                };
+                self.add_span_thread(&mut current, &mut visited, thread, sp, between(sp));
+            }
+            if current.is_empty() && (matched.is_some() || self.full_match) {
+                break;
+            }
+
+            let mut next = vec![];
+            let mut next_visited = vec![false; self.inst.len()];
+            for t in current {
+                let step = |pc: usize| Thread {
+                    pc,
+                    offset: 0,
+                    slots: t.slots.clone(),
+                };
+                let thread = match self.inst[t.pc] {
+                    RegexIR::Match if !self.full_match || sp == target.len() => {
+                        if !longest {
+                            // 優先順位の低いスレッドは打ち切る
+                            matched = Some(t.slots);
+                            break;
+                        }
+                        let better = match &matched {
+                            None => true,
+                            Some(m) => {
+                                let (found, best) = (span(&t.slots), span(m));
+                                found.0 < best.0 || (found.0 == best.0 && found.1 > best.1)
+                            }
+                        };
+                        if better {
+                            matched = Some(t.slots);
+                        }
+                        continue;
+                    }
+                    RegexIR::AllChar if sp < target.len() => step(t.pc + 1),
+                    RegexIR::Char(c) if sp < target.len() && c == target[sp] => step(t.pc + 1),
+                    RegexIR::Property(p) if sp < target.len() && p.contains(target[sp]) => {
+                        step(t.pc + 1)
+                    }
+                    RegexIR::String(ref s) if s.get(t.offset) == target.get(sp) => {
+                        if t.offset + 1 < s.len() {
+                            // 途中の文字ではε遷移を辿らない
+                            next.push(Thread {
+                                offset: t.offset + 1,
+                                ..t
+                            });
+                            continue;
+                        }
+                        step(t.pc + 1)
+                    }
+                    _ => continue,
+                };
+                self.add_span_thread(
+                    &mut next,
+                    &mut next_visited,
+                    thread,
+                    sp + 1,
+                    between(sp + 1),
+                );
+            }
+            current = next;
+            visited = next_visited;
+        }
+
+        matched
+    }
+
+    // add_threadと同様にε遷移を辿り、Save命令では位置を記録する
+    fn add_span_thread(
+        &self,
+        threads: &mut Vec<Thread>,
+        visited: &mut [bool],
+        mut thread: Thread,
+        sp: usize,
+        between: (CharKind, CharKind),
+    ) {
+        if thread.pc >= self.inst.len() || visited[thread.pc] {
+            return;
+        }
+        visited[thread.pc] = true;
+
+        match self.inst[thread.pc] {
+            RegexIR::Jmp(x) => {
+                thread.pc = x;
+                self.add_span_thread(threads, visited, thread, sp, between);
+            }
+            RegexIR::Save(slot) => {
+                if thread.slots.len() <= slot {
+                    thread.slots.resize(slot + 1, None);
+                }
+                thread.slots[slot] = Some(sp);
+                thread.pc += 1;
+                self.add_span_thread(threads, visited, thread, sp, between);
+            }
+            RegexIR::Assert(a) => {
+                if a.is_match(between.0, between.1) {
+                    thread.pc += 1;
+                    self.add_span_thread(threads, visited, thread, sp, between);
+                }
+            }
+            RegexIR::Backref(_) => {
+                panic!("[PikeMachine::add_span_thread] backreferences are not supported")
+            }
+            RegexIR::LookAhead { .. } | RegexIR::LookBehind { .. } => {
+                panic!("[PikeMachine::add_span_thread] lookaround is not supported")
+            }
+            RegexIR::Split(x, y) => {
+                let low = Thread {
+                    pc: y,
+                    offset: 0,
+                    slots: thread.slots.clone(),
+                };
+                thread.pc = x;
+                self.add_span_thread(threads, visited, thread, sp, between);
+                self.add_span_thread(threads, visited, low, sp, between);
+            }
+            _ => threads.push(thread),
+        }
+    }
+
+    // ε遷移（Jmp/Split）を辿り、文字を消費する命令をスレッドとして登録
+    // betweenは現在位置の直前と直後の文字の種類（表明の判定に使用）
+    fn add_thread(
+        &self,
+        threads: &mut Vec<usize>,
+        visited: &mut [bool],
+        pc: usize,
+        between: (CharKind, CharKind),
+    ) {
+        if pc >= self.inst.len() || visited[pc] {
+            return;
+        }
+        visited[pc] = true;
+
+        match self.inst[pc] {
+            RegexIR::Jmp(x) => self.add_thread(threads, visited, x, between),
+            // 位置の記録は一致判定に影響しない
+            RegexIR::Save(_) => self.add_thread(threads, visited, pc + 1, between),
+            RegexIR::Assert(a) => {
+                if a.is_match(between.0, between.1) {
+                    self.add_thread(threads, visited, pc + 1, between);
+                }
+            }
+            RegexIR::Backref(_) => {
+                panic!("[PikeMachine::add_thread] backreferences are not supported")
+            }
+            RegexIR::LookAhead { .. } | RegexIR::LookBehind { .. } => {
+                panic!("[PikeMachine::add_thread] lookaround is not supported")
+            }
+            RegexIR::Split(x, y) => {
+                self.add_thread(threads, visited, x, between);
+                self.add_thread(threads, visited, y, between);
+            }
+            _ => threads.push(pc),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::lexer::Lexer;
+    use crate::parse::parser::{Ast, AstTree};
+    use crate::vm::machine::Machine;
+    use crate::vm::optimize;
+
+    // マッチ全体を0番のグループとした命令列
+    fn capture_inst(pattern: &str) -> Vec<RegexIR> {
+        let ast = Ast::new(&Lexer::new(pattern).scan()).parse();
+        Builder::from_ast(AstTree::Capture(0, None, Box::new(ast))).compile()
+    }
+
+    #[test]
+    fn test_pike_machine() {
+        {
+            let m = PikeMachine::new("a+b+");
+
+            assert!(m.is_match("ab"));
+            assert!(m.is_match("aabbc"));
+            assert!(!m.is_match("a"));
+            assert!(!m.is_match(""));
+        }
+        {
+            let m = PikeMachine::new(".*a|b");
+
+            assert!(m.is_match("zza"));
+            assert!(m.is_match("b"));
+            assert!(!m.is_match("zzb"));
+        }
+        {
+            let m = PikeMachine::new("(ab)?c*");
+
+            assert!(m.is_match(""));
+            assert!(m.is_match("abcc"));
+        }
+    }
+
+    #[test]
+    fn test_pike_machine_full_match() {
+        let m = PikeMachine::new("a+").full_match(true);
+
+        assert!(m.is_match("aaa"));
+        assert!(!m.is_match("aab"));
+        assert!(!m.is_match(""));
+    }
+
+    #[test]
+    fn test_pike_machine_assertion() {
+        let m = PikeMachine::new(r".*\bab\b");
+
+        assert!(m.is_match("x ab"));
+        assert!(m.is_match("ab."));
+        assert!(!m.is_match("xab"));
+        assert!(!m.is_match("abc"));
+
+        let m = PikeMachine::new(r"\Aa.*b\z").full_match(false);
+        assert!(m.is_match("axb"));
+        assert!(!m.is_match("axbx"));
+    }
+
+    #[test]
+    fn test_pike_machine_captures() {
+        // 選択肢の左を優先する
+        let m = PikeMachine::from_inst(capture_inst("(a|ab)(c|bcd)(d*)"));
+        assert_eq!(
+            Some(vec![
+                Some(0),
+                Some(4),
+                Some(0),
+                Some(1),
+                Some(1),
+                Some(4),
+                Some(4),
+                Some(4)
+            ]),
+            m.captures("abcd")
+        );
+        let m = PikeMachine::from_inst(capture_inst("a|ab"));
+        assert_eq!(Some(vec![Some(1), Some(2)]), m.captures("xab"));
+        assert_eq!(Some((1, 3)), m.longest("xab"));
+        assert_eq!(None, m.captures("xyz"));
+
+        // 繰り返しは多い方を優先し、空文字列にもマッチする
+        let m = PikeMachine::from_inst(capture_inst("(a*)(a|b)?"));
+        assert_eq!(
+            Some(vec![Some(0), Some(2), Some(0), Some(2)]),
+            m.captures("aa")
+        );
+        let m = PikeMachine::from_inst(capture_inst("b*"));
+        assert_eq!(Some(vec![Some(0), Some(0)]), m.captures("ab"));
+
+        let m = PikeMachine::from_inst(capture_inst("a|ab")).full_match(true);
+        assert_eq!(Some(vec![Some(0), Some(2)]), m.captures("ab"));
+        assert_eq!(None, m.longest("abc"));
+    }
+
+    #[test]
+    fn test_pike_machine_captures_backtrack() {
+        // バックトラック型VMと同じ範囲を求める（最適化後のString命令を含む）
+        for pattern in [
+            "(a|ab)(c|bcd)(d*)",
+            "(a+)(a*)b?",
+            "(x*)(ab|abc)+",
+            r"(\bab|a)(c|bc)\b",
+            "((a)|b)+",
+            "(?:abc|ab)(cd)?",
+            "(a*)*b",
+            r"(\p{Greek}+)(α|β)",
+        ]
+        .iter()
+        {
+            let inst = capture_inst(pattern);
+            for inst in [inst.clone(), optimize::optimize(inst)].iter() {
+                let pike = PikeMachine::from_inst(inst.clone());
+                let backtrack = Machine::from_inst(inst.clone());
+                for input in [
+                    "abcd", "xaab", "abcabcd", "ab c", "abab", "aaab", "αβγα", "",
+                ]
+                .iter()
+                {
+                    assert_eq!(
+                        backtrack.captures(input).unwrap(),
+                        pike.captures(input),
+                        "{} {}",
+                        pattern,
+                        input
+                    );
+                    assert_eq!(
+                        backtrack.longest(input).unwrap(),
+                        pike.longest(input),
+                        "{} {}",
+                        pattern,
+                        input
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pike_machine_long_str() {
+        let m = PikeMachine::new("(a*)*b");
+
+        let s = String::from_utf8(vec![b'a'; 10000]).unwrap();
+        assert!(!m.is_match(&s));
+    }
+}
@@ -1,6 +1,13 @@
 #[cfg(test)]
 mod test {
-    use toy_regex::regex::Regex;
+    use toy_regex::algebra::{equivalent, is_subset, Language, Size};
+    use toy_regex::audit::{audit, Complexity};
+    use toy_regex::error::Error;
+    use toy_regex::generate::Generator;
+    use toy_regex::regex::{
+        explain, normalize, Engine, Match, MatchKind, Regex, RegexBuilder, RegexSet,
+    };
+    use toy_regex::{MultiLiteral, OverlapMatch};
 
     #[test]
     fn test_integration1() {
@@ -198,4 +205,665 @@ mod test {
             assert!(re.exec(Some("".to_string()), false, false, None));
         }
     }
+
+    #[test]
+    fn test_integration_builder() {
+        let engines = [
+            Engine::Backtrack,
+            Engine::PikeVm,
+            Engine::Nfa,
+            Engine::Dfa,
+            Engine::Derivative,
+            Engine::Auto,
+        ];
+        for engine in engines.iter() {
+            let re = RegexBuilder::new("(ab)+c?")
+                .engine(*engine)
+                .case_insensitive(true)
+                .build()
+                .unwrap();
+
+            assert_eq!(Ok(true), re.is_match("zABabC"));
+            assert_eq!(Ok(true), re.is_match("ab"));
+            assert_eq!(Ok(false), re.is_match("ac"));
+
+            let re = RegexBuilder::new("(ab)+c?")
+                .engine(*engine)
+                .anchored(true)
+                .build()
+                .unwrap();
+
+            assert_eq!(Ok(true), re.is_match("ababc"));
+            assert_eq!(Ok(false), re.is_match("zababc"));
+            assert_eq!(Ok(false), re.is_match("ababcc"));
+        }
+
+        assert!(matches!(
+            RegexBuilder::new("a*b*c*")
+                .engine(Engine::Dfa)
+                .max_dfa_states(3)
+                .build(),
+            Err(Error::TooManyDfaStates { .. })
+        ));
+    }
+
+    #[test]
+    fn test_integration_auto_engine() {
+        let cases = [
+            ("error", Engine::Literal),
+            ("a+b+", Engine::Dfa),
+            ("(a|b)*a(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)", Engine::PikeVm),
+        ];
+        for (pattern, engine) in cases.iter() {
+            let re = RegexBuilder::new(pattern).build().unwrap();
+            assert_eq!(*engine, re.engine());
+
+            // 自動選択の結果は明示的にエンジンを指定した場合と一致
+            let pike = RegexBuilder::new(pattern)
+                .engine(Engine::PikeVm)
+                .build()
+                .unwrap();
+            for input in ["error", "zaabbz", "abbbbbbb", "ba", ""].iter() {
+                assert_eq!(pike.is_match(input), re.is_match(input));
+            }
+        }
+    }
+
+    #[test]
+    fn test_integration_audit() {
+        let report = audit("(a+)+b", true);
+        assert_eq!(Complexity::Exponential, report.complexity);
+
+        // 計算量はメモ化しない場合の値で、既定の設定ではメモ化により線形時間
+        assert!(report.memoized);
+        for n in [20, 40, 80].iter() {
+            let attack = report.attack_string(*n);
+            let re = RegexBuilder::new("(a+)+b")
+                .engine(Engine::Backtrack)
+                .anchored(true)
+                .prefilter(false)
+                .optimize(false)
+                .max_backtrack_steps(10 * attack.len())
+                .build()
+                .unwrap();
+            assert_eq!(Ok(false), re.is_match(&attack), "{}", n);
+        }
+
+        // 最適化で(?:a+)+がa+になるため、最適化しない場合の上限内で終了する
+        // （グループを持つ(a+)+は平坦化しない）
+        let attack = report.attack_string(20);
+        let build = |optimize| {
+            RegexBuilder::new("(?:a+)+b")
+                .engine(Engine::Backtrack)
+                .anchored(true)
+                .prefilter(false)
+                .optimize(optimize)
+                .max_backtrack_steps(80)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(Ok(false), build(true).is_match(&attack));
+        assert!(build(false).is_match(&attack).is_err());
+
+        assert!(!audit("a+b", true).is_vulnerable());
+    }
+
+    #[test]
+    fn test_integration_multi_literal() {
+        let words = ["error", "warn", "fatal", "panic"];
+        let ac = MultiLiteral::new(&words);
+        let found: Vec<&str> = ac
+            .find_iter("a panic after an error")
+            .map(|m| ac.pattern(m.pattern))
+            .collect();
+        assert_eq!(vec!["panic", "error"], found);
+
+        // リテラルの選択は他のエンジンと同じ結果になる
+        let re = RegexBuilder::new("error|warn|fatal|panic").build().unwrap();
+        let pike = RegexBuilder::new("error|warn|fatal|panic")
+            .engine(Engine::PikeVm)
+            .build()
+            .unwrap();
+        assert_eq!(Engine::Literal, re.engine());
+        for input in ["fatal", "xpanicx", "warm", "err", ""].iter() {
+            assert_eq!(pike.is_match(input), re.is_match(input));
+        }
+    }
+
+    #[test]
+    fn test_integration_dfa_find() {
+        let re = RegexBuilder::new("(a|b)*c")
+            .engine(Engine::Dfa)
+            .build()
+            .unwrap();
+
+        assert_eq!(Ok(Some(Match { start: 1, end: 5 })), re.find("xabac"));
+        assert_eq!(Ok(Some(Match { start: 0, end: 1 })), re.find("cab"));
+        assert_eq!(Ok(None), re.find("abab"));
+    }
+
+    #[test]
+    fn test_integration_overlapping() {
+        // 重なる一致も全て報告する
+        let set = RegexSet::new(&["aa"]).unwrap();
+        let found: Vec<OverlapMatch> = set.find_overlapping("aaaa").collect();
+        assert_eq!(3, found.len());
+        assert_eq!(
+            vec![(0, 2), (1, 3), (2, 4)],
+            found.iter().map(|m| (m.start, m.end)).collect::<Vec<_>>()
+        );
+
+        // 塩基配列中のモチーフ（制限酵素の認識配列と、TATAボックス）
+        let dna = "GAATTCTATAAAGAATTC";
+        let set = RegexSet::new(&["GAATTC", "TATA[AT]A", "A+T+"]).unwrap();
+        let found: Vec<(usize, usize, usize)> = set
+            .find_overlapping(dna)
+            .filter(|m| m.pattern != 2)
+            .map(|m| (m.pattern, m.start, m.end))
+            .collect();
+        assert_eq!(vec![(0, 0, 6), (1, 6, 12), (0, 12, 18)], found);
+
+        // 各位置で終わる全てのパターン
+        let ends = set.ends(dna);
+        assert_eq!(Some(&(6, vec![0])), ends.iter().find(|(end, _)| *end == 6));
+        assert_eq!(Some(&(5, vec![2])), ends.iter().find(|(end, _)| *end == 5));
+        assert_eq!(
+            Some(&(18, vec![0])),
+            ends.iter().find(|(end, _)| *end == 18)
+        );
+        let motif_ends: Vec<usize> = ends
+            .iter()
+            .filter(|(_, ids)| ids.contains(&2))
+            .map(|(end, _)| *end)
+            .collect();
+        assert_eq!(vec![4, 5, 9, 16, 17], motif_ends);
+    }
+
+    #[test]
+    fn test_integration_match_kind() {
+        // a|abをabへ適用すると、最左優先（Perl互換）ではa、最長一致（POSIX互換）ではab
+        for engine in [
+            Engine::Backtrack,
+            Engine::PikeVm,
+            Engine::Nfa,
+            Engine::Dfa,
+            Engine::Derivative,
+            Engine::Literal,
+            Engine::Auto,
+        ]
+        .iter()
+        {
+            for (kind, end) in [
+                (MatchKind::LeftmostFirst, 1),
+                (MatchKind::LeftmostLongest, 2),
+            ]
+            .iter()
+            {
+                let built = RegexBuilder::new("a|ab")
+                    .engine(*engine)
+                    .match_kind(*kind)
+                    .build();
+                // DFAは最左優先の一致範囲を求められない
+                if (*engine, *kind) == (Engine::Dfa, MatchKind::LeftmostFirst) {
+                    assert_eq!(
+                        Some(Error::Unsupported {
+                            engine: Engine::Dfa,
+                            feature: "leftmost-first match kind",
+                        }),
+                        built.err()
+                    );
+                    continue;
+                }
+                let re = built.unwrap();
+                let m = Match {
+                    start: 0,
+                    end: *end,
+                };
+                assert_eq!(Ok(Some(m)), re.find("ab"), "{:?} {:?}", engine, kind);
+                assert_eq!(
+                    Some(m),
+                    re.captures("ab").unwrap().and_then(|c| c.get(0)),
+                    "{:?} {:?}",
+                    engine,
+                    kind
+                );
+            }
+        }
+
+        // DFAエンジンは指定がなければ最長一致
+        let re = RegexBuilder::new("a|ab")
+            .engine(Engine::Dfa)
+            .build()
+            .unwrap();
+        let m = Match { start: 0, end: 2 };
+        assert_eq!(Ok(Some(m)), re.find("ab"));
+        assert_eq!(Some(m), re.captures("ab").unwrap().and_then(|c| c.get(0)));
+
+        // 選択肢の順序を入れ替えると、最左優先でも最長となる
+        let re = RegexBuilder::new("ab|a")
+            .match_kind(MatchKind::LeftmostFirst)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(Some(Match { start: 0, end: 2 })), re.find("ab"));
+
+        // 繰り返しの各回でも左の選択肢を優先するため、最左優先では末尾のabを含まない
+        for (kind, end) in [
+            (MatchKind::LeftmostFirst, 4),
+            (MatchKind::LeftmostLongest, 5),
+        ]
+        .iter()
+        {
+            let re = RegexBuilder::new("(a|ab)+")
+                .engine(Engine::PikeVm)
+                .match_kind(*kind)
+                .build()
+                .unwrap();
+            assert_eq!(
+                Ok(Some(Match {
+                    start: 1,
+                    end: *end
+                })),
+                re.find("xaaab")
+            );
+        }
+    }
+
+    #[test]
+    fn test_integration_engines() {
+        // (パターン, 入力, 部分文字列マッチ, 全体マッチ)
+        let cases = [
+            ("a?bc", "bc", true, true),
+            ("a?bc", "aabc", true, false),
+            ("a?bc", "ab", false, false),
+            ("abc", "aabc", true, false),
+            ("a+b+", "zabc", true, false),
+            ("a+b+", "aaaaaaabbbbbbb", true, true),
+            ("(ab)c", "zabcz", true, false),
+            ("a|c", "c", true, true),
+            ("a|c", "b", false, false),
+            ("(ab)*", "aa", true, false),
+            ("(ac)|(bd)", "bd", true, true),
+            ("(ab)+", "", false, false),
+            ("(ab)?", "abababab", true, false),
+            ("(ab)?", "", true, true),
+            ("a.c", "xabcx", true, false),
+        ];
+        let engines = [
+            Engine::Backtrack,
+            Engine::PikeVm,
+            Engine::Nfa,
+            Engine::Dfa,
+            Engine::Derivative,
+            Engine::Auto,
+        ];
+
+        for engine in engines.iter() {
+            for (pattern, input, substring, full) in cases.iter() {
+                let re = RegexBuilder::new(pattern).engine(*engine).build().unwrap();
+                assert_eq!(
+                    Ok(*substring),
+                    re.is_match(input),
+                    "{:?} {}",
+                    engine,
+                    pattern
+                );
+
+                let re = RegexBuilder::new(pattern)
+                    .engine(*engine)
+                    .anchored(true)
+                    .build()
+                    .unwrap();
+                assert_eq!(Ok(*full), re.is_match(input), "{:?} {}", engine, pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn test_integration_set_operator() {
+        // 識別子だがキーワードではない
+        let ident = Language::new("(a|b|e|f|i|l|s)(a|b|e|f|i|l|s|0|1)*").unwrap();
+        let keyword = Language::new("if|else").unwrap();
+        let name = ident.difference(&keyword);
+        let syntax = Language::new("(a|b|e|f|i|l|s)(a|b|e|f|i|l|s|0|1)*&~(if|else)").unwrap();
+
+        for input in ["if", "else", "iff", "a1", "1a", "elsa", ""].iter() {
+            assert_eq!(name.is_match(input), syntax.is_match(input));
+        }
+        assert!(name.is_match("iff"));
+        assert!(!name.is_match("else"));
+
+        let re = RegexBuilder::new("(a|b|e|f|i|l|s)+&~(if|else)")
+            .anchored(true)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(true), re.is_match("elsa"));
+        assert_eq!(Ok(false), re.is_match("if"));
+    }
+
+    #[test]
+    fn test_integration_compare() {
+        // リファクタリング前後のパターンが同じ文字列にマッチする
+        assert!(equivalent("api(v1|v2)users", "apiv(1|2)users")
+            .unwrap()
+            .is_ok());
+
+        let e = equivalent("api(v1|v2)users", "apiv.users")
+            .unwrap()
+            .unwrap_err();
+        assert_eq!("apivausers", e.input);
+        assert!(!e.left && e.right);
+        assert!(is_subset("api(v1|v2)users", "apiv.users").unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_integration_generate() {
+        // 生成した文字列で全エンジンの結果が一致する
+        let engines = [
+            Engine::Backtrack,
+            Engine::PikeVm,
+            Engine::Nfa,
+            Engine::Dfa,
+            Engine::Derivative,
+        ];
+        for pattern in ["a(b|c)*d", "(ab|a)+b?", "x.y*z"].iter() {
+            let mut generator = Generator::new(pattern).unwrap().seed(2024).length(0, 8);
+            for _ in 0..20 {
+                let accepted = generator.generate().unwrap();
+                let rejected = generator.near_miss().unwrap();
+                for engine in engines.iter() {
+                    let re = RegexBuilder::new(pattern)
+                        .engine(*engine)
+                        .anchored(true)
+                        .build()
+                        .unwrap();
+                    assert_eq!(
+                        Ok(true),
+                        re.is_match(&accepted),
+                        "{:?} {}",
+                        engine,
+                        accepted
+                    );
+                    assert_eq!(
+                        Ok(false),
+                        re.is_match(&rejected),
+                        "{:?} {}",
+                        engine,
+                        rejected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_integration_enumerate() {
+        // 列挙した文字列は全てマッチし、数は各長さの数の合計と一致する
+        let pattern = "(0|1)(0|1)?x?";
+        let language = Language::new(pattern).unwrap();
+        let strings = language.enumerate(100);
+        let re = RegexBuilder::new(pattern).anchored(true).build().unwrap();
+
+        assert_eq!(12, strings.len());
+        assert_eq!(vec!["0", "1", "00", "01", "0x"], strings[..5].to_vec());
+        for s in strings.iter() {
+            assert_eq!(Ok(true), re.is_match(s), "{}", s);
+        }
+        assert_eq!(Size::Finite(12u32.into()), language.size());
+        assert_eq!(Size::Infinite, Language::new("(0|1)+x?").unwrap().size());
+    }
+
+    #[test]
+    fn test_integration_export() {
+        // 集合演算の結果をパターンとして書き出し、読み込み直しても同じ言語になる
+        let ident = Language::new("(a|b|f|i|o|r)+").unwrap();
+        let keyword = Language::new("if|for").unwrap();
+        let name = ident.difference(&keyword);
+
+        let exported = name.to_pattern();
+        assert!(!exported.contains('&') && !exported.contains('~'));
+        assert_eq!(Ok(()), name.equivalent(&Language::new(&exported).unwrap()));
+
+        let re = RegexBuilder::new(&exported).anchored(true).build().unwrap();
+        assert_eq!(Ok(true), re.is_match("fi"));
+        assert_eq!(Ok(false), re.is_match("for"));
+        assert_eq!("(?:a|b)*", Language::new("(a*b*)*").unwrap().to_pattern());
+    }
+
+    #[test]
+    fn test_integration_normalize() {
+        // 括弧の付け方が異なるだけのパターンは同じ文字列へ正規化される
+        assert_eq!("(?:a|b)c*", normalize("(?:(?:a)|(?:b))(?:c)*"));
+        assert_eq!(normalize("(?:ab)|(?:c)"), normalize("ab|c"));
+        assert_ne!(normalize("(?:ab)*"), normalize("ab*"));
+        // グループの括弧は残す
+        assert_eq!("(a|b)c*", normalize("(a|b)c*"));
+
+        // 正規化は冪等
+        for pattern in ["a(b|c)*d?", "~(?:ab)&.+", "(?:(?:a|b)|c)d", "(?P<x>a)"].iter() {
+            let once = normalize(pattern);
+            assert_eq!(once, normalize(&once));
+        }
+    }
+
+    #[test]
+    fn test_integration_optimize() {
+        // 最適化の有無で全エンジンの結果が一致する
+        let engines = [
+            Engine::Backtrack,
+            Engine::PikeVm,
+            Engine::Nfa,
+            Engine::Dfa,
+            Engine::Derivative,
+        ];
+        let patterns = [
+            "abc|abd",
+            "(a*)*b",
+            "x(a|b|c)+y",
+            "ab|c|ad",
+            "[ab]c|ac?",
+            "(ab)(cd)?",
+        ];
+        let inputs = [
+            "abc", "abd", "aab", "b", "xabcy", "xy", "ad", "bc", "a", "abcd",
+        ];
+        for pattern in patterns.iter() {
+            for engine in engines.iter() {
+                for anchored in [true, false].iter() {
+                    let build = |optimize| {
+                        RegexBuilder::new(pattern)
+                            .engine(*engine)
+                            .anchored(*anchored)
+                            .optimize(optimize)
+                            .build()
+                            .unwrap()
+                    };
+                    let (optimized, plain) = (build(true), build(false));
+                    for input in inputs.iter() {
+                        assert_eq!(
+                            plain.is_match(input),
+                            optimized.is_match(input),
+                            "{} {:?} {}",
+                            pattern,
+                            engine,
+                            input
+                        );
+                    }
+                }
+            }
+        }
+
+        assert!(explain("abc|abd").contains("optimized: ab[cd]"));
+        assert!(explain("(?P<x>a)(?:b)(c)").contains("groups:    1=x, 2"));
+        assert!(explain("ab").contains("groups:    (none)"));
+    }
+
+    #[test]
+    fn test_integration_backref() {
+        // 単語の繰り返し
+        let re = RegexBuilder::new(r"\b(\w+) \1\b").build().unwrap();
+        assert_eq!(Engine::Backtrack, re.engine());
+        for (input, expected) in [
+            ("this is is a test", true),
+            ("the the", true),
+            ("this is a test", false),
+            ("this island", false),
+            ("is isn't", false),
+        ]
+        .iter()
+        {
+            assert_eq!(Ok(*expected), re.is_match(input), "{}", input);
+        }
+        let caps = re.captures("this is is a test").unwrap().unwrap();
+        assert_eq!(Some(Match { start: 5, end: 10 }), caps.get(0));
+        assert_eq!(Some(Match { start: 5, end: 7 }), caps.get(1));
+
+        let anchored = RegexBuilder::new(r"(a|b)(c|d)\2\1")
+            .anchored(true)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(true), anchored.is_match("acca"));
+        assert_eq!(Ok(false), anchored.is_match("acac"));
+
+        assert_eq!(
+            Some(Error::Unsupported {
+                engine: Engine::Dfa,
+                feature: "backreferences",
+            }),
+            RegexBuilder::new(r"(a)\1")
+                .engine(Engine::Dfa)
+                .build()
+                .err()
+        );
+
+        // 存在しないグループへの後方参照
+        assert_eq!(
+            Some(Error::InvalidBackreference {
+                group: 3,
+                groups: 1
+            }),
+            Regex::try_new(r"(\w+) \3").err()
+        );
+    }
+
+    #[test]
+    fn test_integration_captures() {
+        // 選択・繰り返しの中のグループ（繰り返しは最後の一致）
+        let re = RegexBuilder::new("(?P<key>[abc]+)is(?P<value>(?:x|y)*)(z)?")
+            .build()
+            .unwrap();
+        let input = "0 abisxyxz cisy";
+        let caps = re.captures(input).unwrap().unwrap();
+        assert_eq!("abisxyxz", caps.get(0).unwrap().as_str(input));
+        assert_eq!("ab", caps.name("key").unwrap().as_str(input));
+        assert_eq!("xyx", caps.name("value").unwrap().as_str(input));
+        assert_eq!("z", caps.get(3).unwrap().as_str(input));
+
+        let re = RegexBuilder::new("(a|(b))+").build().unwrap();
+        let caps = re.captures("abba").unwrap().unwrap();
+        assert_eq!(Some(Match { start: 3, end: 4 }), caps.get(1));
+        assert_eq!(Some(Match { start: 2, end: 3 }), caps.get(2));
+
+        // 後方参照と組み合わせる
+        let re = RegexBuilder::new("(?<q>[xy])(.*)\\1").build().unwrap();
+        let input = "say xhix now";
+        let caps = re.captures(input).unwrap().unwrap();
+        assert_eq!("hi", caps.get(2).unwrap().as_str(input));
+        assert_eq!("x", caps.name("q").unwrap().as_str(input));
+    }
+
+    #[test]
+    fn test_integration_word_boundary() {
+        // ソースコード中の識別子の検索
+        let source = "let count = counter + count_max; count2 = count";
+        let re = RegexBuilder::new(r"\bcount\b").build().unwrap();
+        assert_eq!(Engine::Dfa, re.engine());
+        assert_eq!(Ok(Some(Match { start: 4, end: 9 })), re.find(source));
+        assert_eq!(Ok(true), re.is_match("x.count"));
+        assert_eq!(Ok(false), re.is_match("counter count_max count2"));
+
+        let re = RegexBuilder::new(r"\bcount\b")
+            .engine(Engine::PikeVm)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(true), re.is_match(source));
+        assert_eq!(Ok(false), re.is_match("recount"));
+
+        // 入力の先頭・末尾
+        let re = RegexBuilder::new(r"\Alet|count\z").build().unwrap();
+        assert_eq!(Ok(true), re.is_match(source));
+        assert_eq!(Ok(false), re.is_match(" let count2"));
+
+        // 識別子の先頭部分（counter）
+        let caps = RegexBuilder::new(r"\b(?P<name>count)\B")
+            .build()
+            .unwrap()
+            .captures(source)
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(Match { start: 12, end: 17 }), caps.name("name"));
+    }
+
+    #[test]
+    fn test_integration_unicode_property() {
+        // ギリシア文字の単語
+        let text = "E = mc², Δx·Δp ≥ ħ/2, λ=550nm";
+        let re = RegexBuilder::new(r"\p{Greek}\p{L}*").build().unwrap();
+        assert_eq!(Ok(Some(Match { start: 10, end: 13 })), re.find(text));
+        assert_eq!(Ok(false), re.is_match("E = mc2"));
+
+        // 括弧内ではプロパティと文字を組み合わせられる
+        let re = RegexBuilder::new(r"[x\p{Nd}]+\P{Nd}")
+            .engine(Engine::PikeVm)
+            .build()
+            .unwrap();
+        assert_eq!(Ok(true), re.is_match("x١٢٣!"));
+        assert_eq!(Ok(false), re.is_match("١٢٣"));
+
+        // 大文字で始まる単語
+        let re = RegexBuilder::new(r"\b\p{Lu}\p{Ll}+\b").build().unwrap();
+        assert_eq!(
+            Ok(Some(Match { start: 10, end: 15 })),
+            re.find("東京 in Tokyo")
+        );
+        assert_eq!(Ok(false), re.is_match("TOKYO tokyo"));
+
+        assert_eq!(
+            r"\p{Greek}+(?:[x]|\p{Nd})",
+            normalize(r"(?:\p{Greek})+[\p{Nd}x]")
+        );
+    }
+
+    #[test]
+    fn test_integration_lookaround() {
+        // 英小文字と数字を両方含む6文字以上
+        let pattern = "(?=.*[0123456789])(?=.*[abcdefghijklmnopqrstuvwxyz]).......*";
+        let password = RegexBuilder::new(pattern).anchored(true).build().unwrap();
+        for (input, expected) in [
+            ("abc123", true),
+            ("1a2b3c4d", true),
+            ("abcdef", false),
+            ("123456", false),
+            ("ab12", false),
+        ]
+        .iter()
+        {
+            assert_eq!(Ok(*expected), password.is_match(input), "{}", input);
+        }
+
+        // 後ろに続かない・前にない
+        let re = RegexBuilder::new("(?<!x)foo(?!bar)").build().unwrap();
+        assert_eq!(Ok(true), re.is_match("afoobaz"));
+        assert_eq!(Ok(false), re.is_match("afoobar"));
+        assert_eq!(Ok(false), re.is_match("xfoobaz"));
+
+        for engine in [Engine::PikeVm, Engine::Nfa, Engine::Dfa, Engine::Derivative].iter() {
+            assert_eq!(
+                Some(Error::Unsupported {
+                    engine: *engine,
+                    feature: "lookaround",
+                }),
+                RegexBuilder::new("a(?!b)").engine(*engine).build().err()
+            );
+        }
+    }
 }
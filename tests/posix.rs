@@ -0,0 +1,204 @@
+// POSIX ERE互換モードの適合テスト
+// 各ケースは(パターン, 入力, 最も左で始まる最長の一致範囲, 各グループの範囲)
+// グループはその範囲内で選択肢の左を優先するため、POSIXの部分式の規則と異なるケースは
+// KNOWN_DEVIATIONSに分けてPOSIXでの範囲を記録する
+// パターンはこのライブラリの字句解析で扱える範囲（英数字・括弧・選択・繰り返し・アンカー・
+// 範囲とPOSIXクラスを含む括弧）のみで、否定の括弧（[^...]）は扱えない
+// 大きなPOSIXクラス（graph・print・alnumなど）はDFAの構築に時間がかかるため、
+// 所属する文字は src/parse/posix.rs で確認する
+#[cfg(test)]
+mod test {
+    use toy_regex::regex::{Match, RegexBuilder};
+
+    // (パターン, 入力, 一致範囲, 1番以降のグループの範囲)
+    type Case = (
+        &'static str,
+        &'static str,
+        Option<(usize, usize)>,
+        &'static [Option<(usize, usize)>],
+    );
+
+    const CASES: &[Case] = &[
+        // 連結
+        ("abc", "abc", Some((0, 3)), &[]),
+        ("abc", "xbc", None, &[]),
+        ("abc", "axc", None, &[]),
+        ("abc", "xabcy", Some((1, 4)), &[]),
+        ("abc", "ababc", Some((2, 5)), &[]),
+        // 繰り返し
+        ("ab*c", "abc", Some((0, 3)), &[]),
+        ("ab*c", "ac", Some((0, 2)), &[]),
+        ("ab*bc", "abbbbc", Some((0, 6)), &[]),
+        ("ab+bc", "abbc", Some((0, 4)), &[]),
+        ("ab+bc", "abc", None, &[]),
+        ("ab+bc", "abq", None, &[]),
+        ("ab?bc", "abbc", Some((0, 4)), &[]),
+        ("ab?bc", "abc", Some((0, 3)), &[]),
+        ("ab?c", "abc", Some((0, 3)), &[]),
+        ("a*", "bbb", Some((0, 0)), &[]),
+        ("a+", "baaab", Some((1, 4)), &[]),
+        ("(a*)+", "x", Some((0, 0)), &[Some((0, 0))]),
+        ("(a*|b)*", "abab", Some((0, 4)), &[Some((3, 4))]),
+        ("(a+|b)*", "ab", Some((0, 2)), &[Some((1, 2))]),
+        ("(a+|b)+", "ab", Some((0, 2)), &[Some((1, 2))]),
+        ("(a+|b)?", "ab", Some((0, 1)), &[Some((0, 1))]),
+        // 任意の一文字
+        ("a.c", "abc", Some((0, 3)), &[]),
+        ("a.c", "axc", Some((0, 3)), &[]),
+        ("a.*c", "axyzc", Some((0, 5)), &[]),
+        ("a.*c", "axyzd", None, &[]),
+        ("a.*c", "acxc", Some((0, 4)), &[]),
+        (
+            "(.*)c(.*)",
+            "abcde",
+            Some((0, 5)),
+            &[Some((0, 2)), Some((3, 5))],
+        ),
+        // 文字クラス
+        ("a[bc]d", "abd", Some((0, 3)), &[]),
+        ("a[bc]d", "aed", None, &[]),
+        ("a[bcd]*dcdcde", "adcdcde", Some((0, 7)), &[]),
+        ("a[bc]*c", "abcbcc", Some((0, 6)), &[]),
+        ("[a-c]+", "xbcad", Some((1, 4)), &[]),
+        ("[0-9x]+", "ax1x2b", Some((1, 5)), &[]),
+        ("a[b-d]e", "ace", Some((0, 3)), &[]),
+        ("a[b-d]e", "aee", None, &[]),
+        // アンカー
+        ("^abc", "abcabc", Some((0, 3)), &[]),
+        ("^abc", "xabc", None, &[]),
+        ("abc$", "abcabc", Some((3, 6)), &[]),
+        ("abc$", "abcx", None, &[]),
+        ("^a*$", "aaa", Some((0, 3)), &[]),
+        ("^a*$", "aab", None, &[]),
+        ("^$", "", Some((0, 0)), &[]),
+        ("a$|ab", "ab", Some((0, 2)), &[]),
+        ("(^a|b)+", "abab", Some((0, 2)), &[Some((1, 2))]),
+        // 選択（左右の順序に関わらず最長）
+        ("ab|cd", "abc", Some((0, 2)), &[]),
+        ("ab|cd", "abcd", Some((0, 2)), &[]),
+        ("a|ab", "ab", Some((0, 2)), &[]),
+        ("ab|a", "ab", Some((0, 2)), &[]),
+        ("a|ab|abc|abcd", "xabcd", Some((1, 5)), &[]),
+        (
+            "(ab|a)(bc|c)",
+            "abc",
+            Some((0, 3)),
+            &[Some((0, 2)), Some((2, 3))],
+        ),
+        ("(a|ab)c?", "abc", Some((0, 3)), &[Some((0, 2))]),
+        ("(a|b|c|d|e)f", "ef", Some((0, 2)), &[Some((0, 1))]),
+        ("a(b|c)*d", "abcbd", Some((0, 5)), &[Some((3, 4))]),
+        (
+            "(bc|b)(cd|d)?e?",
+            "xbcde",
+            Some((1, 5)),
+            &[Some((1, 3)), Some((3, 4))],
+        ),
+        ("xy*|x", "xyyz", Some((0, 3)), &[]),
+        // 最も左の開始位置が優先（長い一致が後にあっても）
+        ("a|bcde", "abcde", Some((0, 1)), &[]),
+        ("b+|ab+c", "abbbc", Some((0, 5)), &[]),
+        ("bc|abc", "xbcabc", Some((1, 3)), &[]),
+        // POSIXクラス
+        ("[[:digit:]]+", "abc123def", Some((3, 6)), &[]),
+        ("[[:upper:][:digit:]]", "aB1", Some((1, 2)), &[]),
+        ("[[:space:]]+x", "a \tx", Some((1, 4)), &[]),
+        ("[[:blank:]]", "a\nb c", Some((3, 4)), &[]),
+        ("[[:punct:]]", "ab,c", Some((2, 3)), &[]),
+        ("[[:xdigit:]]", "xyzB", Some((3, 4)), &[]),
+        ("[[:cntrl:]]", "ab\u{7}", Some((2, 3)), &[]),
+        ("[[:digit:]x]+", "ax1x2b", Some((1, 5)), &[]),
+        ("[[:digit:]]", "abc", None, &[]),
+        // POSIXクラスはASCIIのみ
+        ("[[:lower:]]", "éa", Some((2, 3)), &[]),
+        ("[[:digit:]]", "٣", None, &[]),
+        // 複数バイトの文字（範囲はバイト位置）
+        ("b+", "ééb", Some((4, 5)), &[]),
+    ];
+
+    // グループの範囲がPOSIXと異なる既知のケース（グループはPOSIXでの範囲）
+    // 一致範囲全体はPOSIXと一致する
+    const KNOWN_DEVIATIONS: &[Case] = &[
+        // 空の繰り返し一回として(0, 0)
+        ("(a*)*", "x", Some((0, 0)), &[Some((0, 0))]),
+        // 各部分式は左から順に最長
+        (
+            "(a|ab)(c|bcd)(d*)",
+            "abcd",
+            Some((0, 4)),
+            &[Some((0, 2)), Some((2, 3)), Some((3, 4))],
+        ),
+        (
+            "(wee|week)(knights|night)",
+            "weeknights",
+            Some((0, 10)),
+            &[Some((0, 4)), Some((4, 10))],
+        ),
+    ];
+
+    #[test]
+    fn test_posix_conformance() {
+        let span = |(start, end): (usize, usize)| Match { start, end };
+        for (pattern, input, expected, groups) in CASES.iter() {
+            let re = RegexBuilder::new(pattern).posix(true).build().unwrap();
+            let expected = expected.map(span);
+            assert_eq!(Ok(expected), re.find(input), "{} {:?}", pattern, input);
+
+            // グループ全体の範囲もfindと一致する
+            let caps = re.captures(input).unwrap();
+            assert_eq!(
+                expected,
+                caps.as_ref().and_then(|c| c.get(0)),
+                "{} {:?}",
+                pattern,
+                input
+            );
+
+            // 各グループの範囲
+            if let Some(caps) = caps {
+                assert_eq!(groups.len() + 1, caps.group_count(), "{}", pattern);
+                for (n, group) in groups.iter().enumerate() {
+                    assert_eq!(
+                        group.map(span),
+                        caps.get(n + 1),
+                        "{} {:?} group {}",
+                        pattern,
+                        input,
+                        n + 1
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_posix_known_deviations() {
+        let span = |(start, end): (usize, usize)| Match { start, end };
+        for (pattern, input, expected, groups) in KNOWN_DEVIATIONS.iter() {
+            let re = RegexBuilder::new(pattern).posix(true).build().unwrap();
+            let expected = expected.map(span);
+            assert_eq!(Ok(expected), re.find(input), "{} {:?}", pattern, input);
+
+            // グループの範囲がPOSIXと一致するようになった場合はCASESへ移す
+            let caps = re.captures(input).unwrap().unwrap();
+            assert_eq!(expected, caps.get(0), "{} {:?}", pattern, input);
+            let actual: Vec<_> = (1..caps.group_count()).map(|n| caps.get(n)).collect();
+            let posix: Vec<_> = groups.iter().map(|g| g.map(span)).collect();
+            assert_ne!(posix, actual, "{} {:?}", pattern, input);
+        }
+    }
+
+    #[test]
+    fn test_posix_conformance_anchored() {
+        // 入力全体へのマッチ
+        for (pattern, input, expected, _) in CASES.iter().chain(KNOWN_DEVIATIONS) {
+            let re = RegexBuilder::new(pattern)
+                .posix(true)
+                .anchored(true)
+                .build()
+                .unwrap();
+            let whole = expected == &Some((0, input.len()));
+            assert_eq!(Ok(whole), re.is_match(input), "{} {:?}", pattern, input);
+        }
+    }
+}